@@ -1,9 +1,11 @@
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv6Addr};
 
-use anyhow::{Ok, Result};
+use anyhow::{bail, Ok, Result};
 use ipnet::IpNet;
 
 use crate::{
+    consts,
+    error::Error,
     message::{AddressMessage, NetlinkRouteAttr},
     request::{NetlinkRequest, NetlinkRequestData},
     utils::{vec_to_addr, zero_terminated},
@@ -18,20 +20,34 @@ pub enum AddrCmd {
 pub enum AddrFamily {
     All = 0,
     V4 = 2,
+    Bridge = 7,
     V6 = 10,
+    Mpls = 28,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Address {
     pub index: i32,
     pub address: IpNet,
     pub label: String,
-    pub flags: u8,
+    /// The `IFA_F_*` flags of the address.
+    pub flags: u32,
+    /// The `RT_SCOPE_*` of the address. An IPv6 address left at `RT_SCOPE_UNIVERSE`
+    /// is added with the scope of its type: host for loopback, link for link-local.
     pub scope: u8,
+    /// The broadcast address of an IPv4 address. When `None`, it is derived from
+    /// the prefix, except for /31 and /32 prefixes and point-to-point addresses.
     pub broadcast: Option<IpAddr>,
+    /// Don't send any broadcast address, even a derived one.
+    pub no_broadcast: bool,
     pub peer: Option<IpNet>,
+    /// Lifetimes of the address in seconds, -1 being forever. When read back they are
+    /// the remaining lifetimes; when adding, 0 leaves a lifetime at forever.
     pub preferred_lifetime: i32,
     pub valid_lifetime: i32,
+    /// When the address was added and last updated, in hundredths of seconds since boot.
+    pub created: u32,
+    pub updated: u32,
 }
 
 impl Address {
@@ -41,6 +57,38 @@ impl Address {
             ..Default::default()
         }
     }
+
+    /// Whether the address was configured statically rather than e.g. by SLAAC.
+    pub fn is_permanent(&self) -> bool {
+        self.flags & consts::IFA_F_PERMANENT != 0
+    }
+
+    /// Whether the preferred lifetime of the address has expired.
+    /// Such an address should not be used as the source of new connections.
+    pub fn is_deprecated(&self) -> bool {
+        self.flags & consts::IFA_F_DEPRECATED != 0
+    }
+
+    /// Whether duplicate address detection of the address is still in progress.
+    pub fn is_tentative(&self) -> bool {
+        self.flags & consts::IFA_F_TENTATIVE != 0
+    }
+}
+
+/// Selects the addresses to list, like the `scope` and flag arguments of `ip addr show`.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct AddrFilter {
+    /// Only addresses of this `RT_SCOPE_*`.
+    pub scope: Option<u8>,
+    /// Only addresses with all of these `IFA_F_*` flags, e.g. `IFA_F_PERMANENT`.
+    pub flags_mask: Option<u32>,
+}
+
+impl AddrFilter {
+    pub fn matches(&self, addr: &Address) -> bool {
+        self.scope.is_none_or(|scope| addr.scope == scope)
+            && self.flags_mask.is_none_or(|mask| addr.flags & mask == mask)
+    }
 }
 
 pub fn addr_deserialize(buf: &[u8]) -> Result<Address> {
@@ -50,34 +98,74 @@ pub fn addr_deserialize(buf: &[u8]) -> Result<Address> {
     let mut addr = Address {
         index: if_addr_msg.index,
         scope: if_addr_msg.scope,
+        flags: if_addr_msg.flags as u32,
         ..Default::default()
     };
 
+    let mut local = None;
+
     for attr in rt_attrs {
         match attr.rt_attr.rta_type {
             libc::IFA_ADDRESS => {
                 addr.address = IpNet::new(vec_to_addr(attr.value)?, if_addr_msg.prefix_len)?;
             }
             libc::IFA_LOCAL => {
-                // TODO
+                local = Some(IpNet::new(
+                    vec_to_addr(attr.value)?,
+                    if_addr_msg.prefix_len,
+                )?);
             }
             libc::IFA_BROADCAST => {
-                // TODO
+                addr.broadcast = Some(vec_to_addr(attr.value)?);
             }
             libc::IFA_LABEL => {
-                // TODO
+                let label = attr.value.split(|&b| b == 0).next().unwrap_or_default();
+                addr.label = String::from_utf8(label.to_vec())?;
             }
             libc::IFA_CACHEINFO => {
-                // TODO
+                if attr.value.len() < consts::IFA_CACHEINFO_SIZE {
+                    return Err(Error::Parse(format!(
+                        "address cache info is too short: {} bytes",
+                        attr.value.len()
+                    ))
+                    .into());
+                }
+
+                let field = |i: usize| {
+                    u32::from_ne_bytes(attr.value[i * 4..(i + 1) * 4].try_into().unwrap())
+                };
+
+                addr.preferred_lifetime = field(0) as i32;
+                addr.valid_lifetime = field(1) as i32;
+                addr.created = field(2);
+                addr.updated = field(3);
+            }
+            // Supersedes the 8 bits of flags in the header
+            consts::IFA_FLAGS => {
+                addr.flags = u32::from_ne_bytes(attr.value[..4].try_into()?);
             }
             _ => {}
         }
     }
 
+    // For a point-to-point address, IFA_ADDRESS is the peer and IFA_LOCAL the address
+    // itself. The kernel matches IFA_LOCAL on delete, so they must not be mixed up.
+    if let Some(local) = local {
+        if local != addr.address {
+            addr.peer = Some(addr.address);
+            addr.address = local;
+        }
+    }
+
     Ok(addr)
 }
 
 pub fn addr_handle(cmd: AddrCmd, index: i32, addr: &Address) -> Result<NetlinkRequest> {
+    // `Address::default()` has 0.0.0.0/0, which is never a meaningful interface address
+    if addr.address.prefix_len() == 0 && addr.address.addr().is_unspecified() {
+        bail!("invalid address {}: address is not set", addr.address);
+    }
+
     let (proto, flags) = match cmd {
         AddrCmd::Add => (
             libc::RTM_NEWADDR,
@@ -113,8 +201,11 @@ pub fn addr_handle(cmd: AddrCmd, index: i32, addr: &Address) -> Result<NetlinkRe
     let msg = Box::new(AddressMessage {
         family: family as u8,
         prefix_len: addr.address.prefix_len(),
-        flags: addr.flags,
-        scope: addr.scope,
+        flags: addr.flags as u8,
+        scope: match addr.address {
+            IpNet::V6(net) if addr.scope == libc::RT_SCOPE_UNIVERSE => ipv6_scope(&net.addr()),
+            _ => addr.scope,
+        },
         index,
     });
 
@@ -126,17 +217,23 @@ pub fn addr_handle(cmd: AddrCmd, index: i32, addr: &Address) -> Result<NetlinkRe
     req.add_data(address_data);
 
     if family == libc::AF_INET {
+        // Like `ip`, a /31 or /32 or a point-to-point address gets no derived broadcast,
+        // which would otherwise be the address itself or its peer
         let broadcast = match addr.broadcast {
-            Some(IpAddr::V4(br)) => br.octets().to_vec(),
-            Some(IpAddr::V6(br)) => br.octets().to_vec(),
-            None => match addr.address.broadcast() {
-                IpAddr::V4(br) => br.octets().to_vec(),
-                IpAddr::V6(br) => br.octets().to_vec(),
-            },
+            _ if addr.no_broadcast => None,
+            Some(br) => Some(br),
+            None if addr.address.prefix_len() >= 31 || addr.peer.is_some() => None,
+            None => Some(addr.address.broadcast()),
         };
 
-        let broadcast_data = Box::new(NetlinkRouteAttr::new(libc::IFA_BROADCAST, broadcast));
-        req.add_data(broadcast_data);
+        if let Some(broadcast) = broadcast {
+            let broadcast = match broadcast {
+                IpAddr::V4(br) => br.octets().to_vec(),
+                IpAddr::V6(br) => br.octets().to_vec(),
+            };
+            let broadcast_data = Box::new(NetlinkRouteAttr::new(libc::IFA_BROADCAST, broadcast));
+            req.add_data(broadcast_data);
+        }
 
         if !addr.label.is_empty() {
             let label_data = Box::new(NetlinkRouteAttr::new(
@@ -145,17 +242,266 @@ pub fn addr_handle(cmd: AddrCmd, index: i32, addr: &Address) -> Result<NetlinkRe
             ));
             req.add_data(label_data);
         }
+    }
+
+    if addr.preferred_lifetime != 0 || addr.valid_lifetime != 0 {
+        let forever = |lifetime: i32| match lifetime {
+            0 => u32::MAX,
+            lifetime => lifetime as u32,
+        };
+
+        // The timestamps are set by the kernel
+        let mut cache_info = Vec::with_capacity(consts::IFA_CACHEINFO_SIZE);
+        cache_info.extend(forever(addr.preferred_lifetime).to_ne_bytes());
+        cache_info.extend(forever(addr.valid_lifetime).to_ne_bytes());
+        cache_info.extend([0; 8]);
 
-        // TODO: add support for IFA_CACHEINFO
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            libc::IFA_CACHEINFO,
+            cache_info,
+        )));
+    }
+
+    // Flags beyond the 8 bits of the header are only carried by IFA_FLAGS
+    if addr.flags > u8::MAX as u32 {
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            consts::IFA_FLAGS,
+            addr.flags.to_ne_bytes().to_vec(),
+        )));
     }
 
     Ok(req)
 }
 
-pub fn addr_list(family: AddrFamily) -> Result<NetlinkRequest> {
+/// The scope `ip addr` gives an IPv6 address by default.
+fn ipv6_scope(ip: &Ipv6Addr) -> u8 {
+    if ip.is_loopback() {
+        libc::RT_SCOPE_HOST
+    } else if ip.segments()[0] & 0xffc0 == 0xfe80 {
+        libc::RT_SCOPE_LINK
+    } else {
+        libc::RT_SCOPE_UNIVERSE
+    }
+}
+
+/// Build an `RTM_GETADDR` dump request.
+/// A non-zero `index` limits the dump to the addresses of that link,
+/// which the kernel only honors when strict checking is enabled.
+pub fn addr_list(family: AddrFamily, index: i32) -> Result<NetlinkRequest> {
     let mut req = NetlinkRequest::new(libc::RTM_GETADDR, libc::NLM_F_DUMP);
-    let msg = Box::new(AddressMessage::new(family as i32));
+    let mut msg = Box::new(AddressMessage::new(family as i32));
+    msg.index = index;
     req.add_data(msg);
 
     Ok(req)
 }
+
+/// Sort addresses by address, prefix length and scope, and drop exact duplicates,
+/// so that listing the same addresses twice gives the same result.
+pub fn addr_sort_dedup(addrs: &mut Vec<Address>) {
+    addrs.sort_by(|a, b| {
+        (a.address.addr(), a.address.prefix_len(), a.scope)
+            .cmp(&(b.address.addr(), b.address.prefix_len(), b.scope))
+            .then_with(|| (a.index, &a.label, a.flags).cmp(&(b.index, &b.label, b.flags)))
+    });
+    addrs.dedup();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // inet 192.168.0.10/24 brd 192.168.0.255 scope global eth0
+    #[rustfmt::skip]
+    static IF_NEW_ADDR_MSG: [u8; 72] = [
+        0x02, // family = AF_INET
+        0x18, // prefix len = 24
+        0x80, // flags = IFA_F_PERMANENT
+        0x00, // scope = RT_SCOPE_UNIVERSE
+        0x02, 0x00, 0x00, 0x00, // interface index = 2
+
+        // nlas
+        0x08, 0x00, 0x01, 0x00, 0xc0, 0xa8, 0x00, 0x0a, // Address L=8,T=1,V=192.168.0.10
+        0x08, 0x00, 0x02, 0x00, 0xc0, 0xa8, 0x00, 0x0a, // Local L=8,T=2,V=192.168.0.10
+        0x08, 0x00, 0x04, 0x00, 0xc0, 0xa8, 0x00, 0xff, // Broadcast L=8,T=4,V=192.168.0.255
+        0x09, 0x00, 0x03, 0x00, 0x65, 0x74, 0x68, 0x30, 0x00, // Label L=9,T=3,V=eth0
+        0x00, 0x00, 0x00, // padding
+        0x08, 0x00, 0x08, 0x00, 0x80, 0x00, 0x00, 0x00, // Flags L=8,T=8,V=IFA_F_PERMANENT
+        0x14, 0x00, 0x06, 0x00, // CacheInfo L=20,T=6
+        0xff, 0xff, 0xff, 0xff, // preferred lifetime = forever
+        0xff, 0xff, 0xff, 0xff, // valid lifetime = forever
+        0x2c, 0x01, 0x00, 0x00, // created = 300
+        0x2c, 0x01, 0x00, 0x00, // updated = 300
+    ];
+
+    // inet6 fe80::1/64 scope link
+    #[rustfmt::skip]
+    static IF_NEW_ADDR6_MSG: [u8; 28] = [
+        0x0a, // family = AF_INET6
+        0x40, // prefix len = 64
+        0x80, // flags = IFA_F_PERMANENT
+        0xfd, // scope = RT_SCOPE_LINK
+        0x02, 0x00, 0x00, 0x00, // interface index = 2
+
+        // nlas
+        0x14, 0x00, 0x01, 0x00, // Address L=20,T=1
+        0xfe, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // fe80::1
+    ];
+
+    #[test]
+    fn test_addr_deserialize() {
+        let addr = addr_deserialize(&IF_NEW_ADDR_MSG).unwrap();
+
+        assert_eq!(addr.index, 2);
+        assert_eq!(addr.address, "192.168.0.10/24".parse().unwrap());
+        assert_eq!(addr.scope, libc::RT_SCOPE_UNIVERSE);
+        assert_eq!(addr.flags, consts::IFA_F_PERMANENT);
+        assert!(addr.is_permanent());
+        assert!(!addr.is_deprecated());
+        assert!(!addr.is_tentative());
+        assert_eq!(addr.preferred_lifetime, -1);
+        assert_eq!(addr.valid_lifetime, -1);
+        assert_eq!(addr.created, 300);
+        assert_eq!(addr.updated, 300);
+        assert_eq!(addr.label, "eth0");
+        assert_eq!(addr.broadcast, Some("192.168.0.255".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_addr_lifetimes() {
+        let addr = Address {
+            valid_lifetime: 300,
+            ..Address::new("2001:db8::1/64".parse().unwrap())
+        };
+
+        let buf = addr_handle(AddrCmd::Add, 2, &addr)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let res = addr_deserialize(&buf[16..]).unwrap();
+        assert_eq!(res.preferred_lifetime, -1);
+        assert_eq!(res.valid_lifetime, 300);
+
+        // Without lifetimes, no IFA_CACHEINFO is sent
+        let buf = addr_handle(AddrCmd::Add, 2, &Address::new(addr.address))
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let attrs = NetlinkRouteAttr::map(&buf[24..]).unwrap();
+        assert!(!attrs.contains_key(&libc::IFA_CACHEINFO));
+
+        let mut msg = IF_NEW_ADDR_MSG.to_vec();
+        msg[52] = 0x10; // CacheInfo L=16
+        msg.truncate(68);
+        assert!(addr_deserialize(&msg).is_err());
+    }
+
+    #[test]
+    fn test_addr_deserialize_v6() {
+        let addr = addr_deserialize(&IF_NEW_ADDR6_MSG).unwrap();
+
+        assert_eq!(addr.index, 2);
+        assert_eq!(addr.address, "fe80::1/64".parse().unwrap());
+        assert_eq!(addr.scope, libc::RT_SCOPE_LINK);
+    }
+
+    // inet 10.0.0.1 peer 10.0.0.2/32 scope global
+    #[rustfmt::skip]
+    static IF_NEW_ADDR_PEER_MSG: [u8; 24] = [
+        0x02, // family = AF_INET
+        0x20, // prefix len = 32
+        0x80, // flags = IFA_F_PERMANENT
+        0x00, // scope = RT_SCOPE_UNIVERSE
+        0x02, 0x00, 0x00, 0x00, // interface index = 2
+
+        // nlas
+        0x08, 0x00, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x02, // Address L=8,T=1,V=10.0.0.2
+        0x08, 0x00, 0x02, 0x00, 0x0a, 0x00, 0x00, 0x01, // Local L=8,T=2,V=10.0.0.1
+    ];
+
+    #[test]
+    fn test_addr_deserialize_peer() {
+        let addr = addr_deserialize(&IF_NEW_ADDR_PEER_MSG).unwrap();
+
+        assert_eq!(addr.address, "10.0.0.1/32".parse().unwrap());
+        assert_eq!(addr.peer, Some("10.0.0.2/32".parse().unwrap()));
+
+        // Deleting it sends the local address as IFA_LOCAL and the peer as IFA_ADDRESS
+        let buf = addr_handle(AddrCmd::Del, 2, &addr)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        assert_eq!(&buf[16..20], &IF_NEW_ADDR_PEER_MSG[..4]);
+        let res = addr_deserialize(&buf[16..]).unwrap();
+        assert_eq!(res.address, addr.address);
+        assert_eq!(res.peer, addr.peer);
+    }
+
+    #[test]
+    fn test_addr_sort_dedup() {
+        let a = Address::new("10.0.0.2/24".parse().unwrap());
+        let b = Address::new("10.0.0.1/24".parse().unwrap());
+        let c = Address::new("10.0.0.1/16".parse().unwrap());
+        let d = Address {
+            scope: libc::RT_SCOPE_LINK,
+            ..Address::new("10.0.0.1/16".parse().unwrap())
+        };
+
+        let mut addrs = vec![a.clone(), d.clone(), b.clone(), a.clone(), c.clone()];
+        addr_sort_dedup(&mut addrs);
+
+        assert_eq!(addrs, vec![c, d, b, a]);
+    }
+
+    #[test]
+    fn test_addr_flags() {
+        let addr = Address {
+            flags: consts::IFA_F_NOPREFIXROUTE | consts::IFA_F_NODAD,
+            ..Address::new("fe80::1/64".parse().unwrap())
+        };
+
+        // The request is the header followed by the address itself
+        let buf = addr_handle(AddrCmd::Add, 2, &addr)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let res = addr_deserialize(&buf[16..]).unwrap();
+
+        assert_eq!(res.flags, addr.flags);
+    }
+
+    #[test]
+    fn test_ipv6_scope() {
+        assert_eq!(ipv6_scope(&"::1".parse().unwrap()), libc::RT_SCOPE_HOST);
+        assert_eq!(ipv6_scope(&"fe80::1".parse().unwrap()), libc::RT_SCOPE_LINK);
+        assert_eq!(ipv6_scope(&"febf::1".parse().unwrap()), libc::RT_SCOPE_LINK);
+        assert_eq!(
+            ipv6_scope(&"2001:db8::1".parse().unwrap()),
+            libc::RT_SCOPE_UNIVERSE
+        );
+    }
+
+    #[test]
+    fn test_addr_handle_unspecified() {
+        let err = addr_handle(AddrCmd::Add, 1, &Address::default())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("address is not set"));
+
+        let addr = Address::new("::/0".parse().unwrap());
+        assert!(addr_handle(AddrCmd::Add, 1, &addr).is_err());
+
+        let addr = Address::new("0.0.0.0/8".parse().unwrap());
+        assert!(addr_handle(AddrCmd::Add, 1, &addr).is_ok());
+    }
+
+    #[test]
+    fn test_addr_family_values() {
+        assert_eq!(AddrFamily::All as i32, libc::AF_UNSPEC);
+        assert_eq!(AddrFamily::V4 as i32, libc::AF_INET);
+        assert_eq!(AddrFamily::Bridge as i32, libc::AF_BRIDGE);
+        assert_eq!(AddrFamily::V6 as i32, libc::AF_INET6);
+        assert_eq!(AddrFamily::Mpls as i32, libc::AF_MPLS);
+    }
+}
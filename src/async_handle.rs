@@ -0,0 +1,151 @@
+use anyhow::Result;
+use tokio::io::{unix::AsyncFd, Interest};
+
+use crate::{
+    consts,
+    error::{self, Error},
+    handle::{collect_response, link_get_response, wrong_sender},
+    link::{self, Link, LinkAttrs},
+    message::NetlinkMessage,
+    request::NetlinkRequest,
+    socket::NetlinkSocket,
+};
+
+/// A `SocketHandle` for tokio, available with the `async` feature.
+/// The socket is non-blocking and waits for responses without blocking the runtime,
+/// so requests can be awaited alongside other tasks.
+///
+/// It must be created from within a tokio runtime with I/O enabled.
+///
+/// # Examples
+///
+/// ```
+/// use lnwasi::{async_handle::AsyncSocketHandle, link::{Link, LinkAttrs}};
+///
+/// # tokio::runtime::Builder::new_current_thread().enable_io().build().unwrap().block_on(async {
+/// let mut handle = AsyncSocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+/// let lo = handle.link_get(&LinkAttrs::new("lo")).await.unwrap();
+/// assert_eq!(lo.attrs().index, 1);
+/// # });
+/// ```
+pub struct AsyncSocketHandle {
+    pub seq: u32,
+    socket: AsyncFd<NetlinkSocket>,
+}
+
+impl AsyncSocketHandle {
+    pub fn new(protocol: i32) -> Result<Self> {
+        let socket = NetlinkSocket::new(protocol, 0, 0).map_err(|e| match e.raw_os_error() {
+            Some(errno @ (libc::EAFNOSUPPORT | libc::EPROTONOSUPPORT)) => {
+                Error::Unsupported { protocol, errno }.into()
+            }
+            _ => anyhow::Error::from(e),
+        })?;
+
+        let _ = socket.set_ext_ack(true);
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            seq: 0,
+            socket: AsyncFd::new(socket)?,
+        })
+    }
+
+    pub async fn link_get(&mut self, attrs: &LinkAttrs) -> Result<Box<dyn Link>> {
+        let mut req = link::link_get(attrs)?;
+        link_get_response(attrs, self.execute(&mut req, 0).await)
+    }
+
+    pub async fn execute(
+        &mut self,
+        req: &mut NetlinkRequest,
+        res_type: u16,
+    ) -> error::Result<Vec<Vec<u8>>> {
+        Ok(self
+            .request(req, res_type)
+            .await?
+            .into_iter()
+            .map(|m| m.data)
+            .collect())
+    }
+
+    /// Send a fully-formed request and return the raw messages of the response,
+    /// like `SocketHandle::request`.
+    pub async fn request(
+        &mut self,
+        req: &mut NetlinkRequest,
+        res_type: u16,
+    ) -> error::Result<Vec<NetlinkMessage>> {
+        req.header.nlmsg_seq = {
+            self.seq += 1;
+            self.seq
+        };
+
+        let buf = req.serialize().map_err(error::request_error)?;
+
+        self.socket
+            .async_io(Interest::WRITABLE, |socket| socket.send(&buf))
+            .await?;
+
+        self.receive(req.header.nlmsg_seq, res_type).await
+    }
+
+    async fn receive(&mut self, seq: u32, res_type: u16) -> error::Result<Vec<NetlinkMessage>> {
+        let pid = self.socket.get_ref().pid()?;
+        let mut res: Vec<NetlinkMessage> = Vec::new();
+
+        loop {
+            let (msgs, from) = self
+                .socket
+                .async_io(Interest::READABLE, |socket| socket.recv())
+                .await
+                .map_err(error::recv_error)?;
+
+            if from.nl_pid != consts::PID_KERNEL {
+                return Err(wrong_sender(from.nl_pid));
+            }
+
+            if collect_response(msgs, seq, pid, res_type, &mut res)? {
+                break;
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::Error,
+        handle::SocketHandle,
+        link::{Link, LinkAttrs},
+        test_setup,
+    };
+
+    use super::AsyncSocketHandle;
+
+    #[tokio::test]
+    async fn test_link_get() {
+        test_setup!();
+        let mut sync = SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        sync.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+
+        // Requests on two sockets are awaited concurrently
+        let get = |name: &'static str| async move {
+            let mut handle = AsyncSocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+            handle.link_get(&LinkAttrs::new(name)).await.unwrap()
+        };
+        let (foo, bar) = tokio::join!(get("foo"), get("bar"));
+
+        assert_eq!(foo.link_type(), "veth");
+        assert_eq!(foo.attrs().parent_index, bar.attrs().index);
+
+        let mut handle = AsyncSocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let err = handle.link_get(&LinkAttrs::new("baz")).await.err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::NotFound(_))
+        ));
+    }
+}
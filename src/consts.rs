@@ -7,6 +7,19 @@ pub const NLMSG_HDRLEN: usize = 0x10;
 
 pub const NLA_F_NESTED: u16 = 0x8000;
 
+pub const NLM_F_DUMP_FILTERED: u16 = 0x20;
+pub const NLM_F_CAPPED: u16 = 0x100;
+pub const NLM_F_ACK_TLVS: u16 = 0x200;
+
+pub const NLMSGERR_ATTR_MSG: u16 = 1;
+pub const NLMSGERR_ATTR_OFFS: u16 = 2;
+
+pub const NETLINK_ADD_MEMBERSHIP: i32 = 1;
+pub const NETLINK_DROP_MEMBERSHIP: i32 = 2;
+pub const NETLINK_PKTINFO: i32 = 3;
+pub const NETLINK_EXT_ACK: i32 = 11;
+pub const NETLINK_GET_STRICT_CHK: i32 = 12;
+
 pub const RECV_BUF_SIZE: usize = 65536;
 pub const PID_KERNEL: u32 = 0;
 
@@ -21,17 +34,186 @@ pub const RT_ATTR_SIZE: usize = 0x4;
 pub const IF_INFO_MSG_SIZE: usize = 0x10;
 pub const IF_ADDR_MSG_SIZE: usize = 0x8;
 pub const ROUTE_MSG_SIZE: usize = 0xC;
+pub const NEIGH_MSG_SIZE: usize = 0xC;
+pub const RULE_MSG_SIZE: usize = 0xC;
+pub const RT_NEXTHOP_SIZE: usize = 0x8;
+pub const NDA_CACHEINFO_SIZE: usize = 0x10;
+pub const IFA_CACHEINFO_SIZE: usize = 0x10;
+
+pub const IFALIASZ: usize = 256;
+pub const ETH_ALEN: usize = 6;
+
+pub const IFA_FLAGS: u16 = 8;
+
+pub const IFA_F_SECONDARY: u32 = 0x01;
+pub const IFA_F_NODAD: u32 = 0x02;
+pub const IFA_F_OPTIMISTIC: u32 = 0x04;
+pub const IFA_F_DADFAILED: u32 = 0x08;
+pub const IFA_F_HOMEADDRESS: u32 = 0x10;
+pub const IFA_F_DEPRECATED: u32 = 0x20;
+pub const IFA_F_TENTATIVE: u32 = 0x40;
+pub const IFA_F_PERMANENT: u32 = 0x80;
+pub const IFA_F_MANAGETEMPADDR: u32 = 0x100;
+pub const IFA_F_NOPREFIXROUTE: u32 = 0x200;
+pub const IFA_F_MCAUTOJOIN: u32 = 0x400;
+pub const IFA_F_STABLE_PRIVACY: u32 = 0x800;
+
+pub const RTA_EXPIRES: u16 = 23;
+// Offset of rta_expires in struct rta_cacheinfo
+pub const RTA_CACHEINFO_EXPIRES: usize = 0x8;
+
+pub const FRA_DST: u16 = 1;
+pub const FRA_SRC: u16 = 2;
+pub const FRA_IIFNAME: u16 = 3;
+pub const FRA_PRIORITY: u16 = 6;
+pub const FRA_FWMARK: u16 = 10;
+pub const FRA_TABLE: u16 = 15;
+pub const FRA_FWMASK: u16 = 16;
+pub const FRA_OIFNAME: u16 = 17;
+
+pub const FR_ACT_TO_TBL: u8 = 1;
+
+pub const RTM_F_LOOKUP_TABLE: u32 = 0x1000;
+pub const RTNH_F_ONLINK: u32 = 0x4;
 
 pub const IFLA_BR_HELLO_TIME: u16 = 0x2;
 pub const IFLA_BR_AGEING_TIME: u16 = 0x4;
+pub const IFLA_BR_STP_STATE: u16 = 0x5;
+pub const IFLA_BR_PRIORITY: u16 = 0x6;
 pub const IFLA_BR_VLAN_FILTERING: u16 = 0x7;
 pub const IFLA_BR_MCAST_SNOOPING: u16 = 0x17;
 
+pub const IFLA_BRPORT_STATE: u16 = 0x1;
+pub const IFLA_BRPORT_PRIORITY: u16 = 0x2;
+pub const IFLA_BRPORT_COST: u16 = 0x3;
+
+pub const BR_STATE_DISABLED: u8 = 0;
+pub const BR_STATE_LISTENING: u8 = 1;
+pub const BR_STATE_LEARNING: u8 = 2;
+pub const BR_STATE_FORWARDING: u8 = 3;
+pub const BR_STATE_BLOCKING: u8 = 4;
+
+pub const IF_OPER_UNKNOWN: u8 = 0;
+pub const IF_OPER_NOTPRESENT: u8 = 1;
+pub const IF_OPER_DOWN: u8 = 2;
+pub const IF_OPER_LOWERLAYERDOWN: u8 = 3;
+pub const IF_OPER_TESTING: u8 = 4;
+pub const IF_OPER_DORMANT: u8 = 5;
+pub const IF_OPER_UP: u8 = 6;
+
+pub const IFLA_BOND_SLAVE_STATE: u16 = 0x1;
+pub const IFLA_BOND_SLAVE_MII_STATUS: u16 = 0x2;
+pub const IFLA_BOND_SLAVE_LINK_FAILURE_COUNT: u16 = 0x3;
+pub const IFLA_BOND_SLAVE_PERM_HWADDR: u16 = 0x4;
+pub const IFLA_BOND_SLAVE_QUEUE_ID: u16 = 0x5;
+
+pub const BOND_STATE_ACTIVE: u8 = 0;
+pub const BOND_STATE_BACKUP: u8 = 1;
+
+pub const BOND_LINK_UP: u8 = 0;
+pub const BOND_LINK_FAIL: u8 = 1;
+pub const BOND_LINK_DOWN: u8 = 2;
+pub const BOND_LINK_BACK: u8 = 3;
+
 pub const IFLA_XDP_FD: u16 = 0x1;
 pub const IFLA_XDP_ATTACHED: u16 = 0x2;
 pub const IFLA_XDP_FLAGS: u16 = 0x3;
 pub const IFLA_XDP_PROG_ID: u16 = 0x4;
 
+pub const IFLA_QDISC: u16 = 0x6;
+
 pub const IFLA_GRO_MAX_SIZE: u16 = 0x3a;
+pub const IFLA_CARRIER: u16 = 0x21;
+pub const IFLA_TSO_MAX_SIZE: u16 = 0x3b;
+pub const IFLA_TSO_MAX_SEGS: u16 = 0x3c;
+pub const IFLA_GSO_IPV4_MAX_SIZE: u16 = 0x3f;
+pub const IFLA_GRO_IPV4_MAX_SIZE: u16 = 0x40;
 
 pub const VETH_INFO_PEER: u16 = 1;
+
+pub const IFLA_VLAN_ID: u16 = 1;
+pub const IFLA_VLAN_PROTOCOL: u16 = 5;
+
+pub const IFLA_MACVLAN_MODE: u16 = 1;
+
+pub const MACVLAN_MODE_PRIVATE: u32 = 1;
+pub const MACVLAN_MODE_VEPA: u32 = 2;
+pub const MACVLAN_MODE_BRIDGE: u32 = 4;
+pub const MACVLAN_MODE_PASSTHRU: u32 = 8;
+
+pub const IFLA_VXLAN_ID: u16 = 1;
+pub const IFLA_VXLAN_GROUP: u16 = 2;
+pub const IFLA_VXLAN_LINK: u16 = 3;
+pub const IFLA_VXLAN_LOCAL: u16 = 4;
+pub const IFLA_VXLAN_LEARNING: u16 = 7;
+pub const IFLA_VXLAN_PORT: u16 = 15;
+pub const IFLA_VXLAN_GROUP6: u16 = 16;
+pub const IFLA_VXLAN_LOCAL6: u16 = 17;
+
+pub const IFLA_GRE_IFLAGS: u16 = 2;
+pub const IFLA_GRE_OFLAGS: u16 = 3;
+pub const IFLA_GRE_IKEY: u16 = 4;
+pub const IFLA_GRE_OKEY: u16 = 5;
+pub const IFLA_GRE_LOCAL: u16 = 6;
+pub const IFLA_GRE_REMOTE: u16 = 7;
+pub const IFLA_GRE_TTL: u16 = 8;
+
+pub const GRE_KEY: u16 = 0x2000;
+
+pub const IFLA_IPTUN_LOCAL: u16 = 2;
+pub const IFLA_IPTUN_REMOTE: u16 = 3;
+pub const IFLA_IPTUN_TTL: u16 = 4;
+
+pub const IFLA_BOND_MODE: u16 = 1;
+pub const IFLA_BOND_MIIMON: u16 = 3;
+
+pub const BOND_MODE_ROUNDROBIN: u8 = 0;
+pub const BOND_MODE_ACTIVEBACKUP: u8 = 1;
+pub const BOND_MODE_XOR: u8 = 2;
+pub const BOND_MODE_BROADCAST: u8 = 3;
+pub const BOND_MODE_8023AD: u8 = 4;
+pub const BOND_MODE_TLB: u8 = 5;
+pub const BOND_MODE_ALB: u8 = 6;
+
+pub const IFLA_NETKIT_PEER_INFO: u16 = 1;
+pub const IFLA_NETKIT_PRIMARY: u16 = 2;
+pub const IFLA_NETKIT_POLICY: u16 = 3;
+pub const IFLA_NETKIT_PEER_POLICY: u16 = 4;
+pub const IFLA_NETKIT_MODE: u16 = 5;
+
+pub const NETKIT_PASS: u32 = 0;
+pub const NETKIT_DROP: u32 = 2;
+
+pub const NETKIT_L2: u32 = 0;
+pub const NETKIT_L3: u32 = 1;
+
+pub const GENL_HDR_SIZE: usize = 0x4;
+pub const GENL_ID_CTRL: u16 = 0x10;
+pub const CTRL_CMD_GETFAMILY: u8 = 3;
+pub const CTRL_ATTR_FAMILY_ID: u16 = 1;
+pub const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+pub const WG_GENL_NAME: &str = "wireguard";
+pub const WG_GENL_VERSION: u8 = 1;
+pub const WG_KEY_LEN: usize = 32;
+pub const WG_CMD_SET_DEVICE: u8 = 1;
+
+pub const WGDEVICE_A_IFINDEX: u16 = 1;
+pub const WGDEVICE_A_PRIVATE_KEY: u16 = 3;
+pub const WGDEVICE_A_FLAGS: u16 = 5;
+pub const WGDEVICE_A_LISTEN_PORT: u16 = 6;
+pub const WGDEVICE_A_FWMARK: u16 = 7;
+pub const WGDEVICE_A_PEERS: u16 = 8;
+pub const WGDEVICE_F_REPLACE_PEERS: u32 = 1;
+
+pub const WGPEER_A_PUBLIC_KEY: u16 = 1;
+pub const WGPEER_A_PRESHARED_KEY: u16 = 2;
+pub const WGPEER_A_FLAGS: u16 = 3;
+pub const WGPEER_A_ENDPOINT: u16 = 4;
+pub const WGPEER_A_PERSISTENT_KEEPALIVE_INTERVAL: u16 = 5;
+pub const WGPEER_A_ALLOWEDIPS: u16 = 9;
+pub const WGPEER_F_REPLACE_ALLOWEDIPS: u32 = 2;
+
+pub const WGALLOWEDIP_A_FAMILY: u16 = 1;
+pub const WGALLOWEDIP_A_IPADDR: u16 = 2;
+pub const WGALLOWEDIP_A_CIDR_MASK: u16 = 3;
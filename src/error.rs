@@ -0,0 +1,242 @@
+use std::fmt;
+
+use ipnet::IpNet;
+
+use crate::message::ErrorMessage;
+
+/// Errors reported by lnwasi that callers may want to handle specifically.
+/// `SocketHandle::request` and `SocketHandle::execute` return them as is, other
+/// methods wrapped in an `anyhow::Error`, from which they can be recovered with
+/// `downcast_ref::<Error>()`.
+///
+/// Errors reported by the kernel are returned as `Error::Netlink`, which carries
+/// the errno, and socket failures as `Error::Io`; `errno` gets the errno from either.
+#[derive(Debug)]
+pub enum Error {
+    /// The kernel rejected a request. `msg` describes the error, along with
+    /// the reason and the offending attribute given by extended ACKs, if any.
+    Netlink { errno: i32, msg: String },
+    /// A socket call failed.
+    Io(std::io::Error),
+    /// The link read back after creation is not of the requested kind.
+    KindMismatch { expected: String, actual: String },
+    /// Netlink, or the requested netlink protocol, is not available on this system
+    /// (e.g. in restricted containers). `errno` is the error returned by `socket(2)`.
+    Unsupported { protocol: i32, errno: i32 },
+    /// The link already has the requested address with a different prefix length.
+    AlreadyExists { requested: IpNet, existing: IpNet },
+    /// No complete response was received before the deadline of the request.
+    Timeout,
+    /// The route can't be sent to the kernel as it is, e.g. it has neither a destination,
+    /// a gateway nor an output link.
+    InvalidRoute(String),
+    /// The kernel can't create links of this kind, usually because the module
+    /// providing it (e.g. `vxlan`) is not loaded.
+    LinkTypeUnsupported { kind: String },
+    /// The requested object doesn't exist, e.g. no link has the given name or index.
+    NotFound(String),
+    /// A message received from the kernel is malformed or truncated.
+    Parse(String),
+    /// The addresses of a request are not all of the same address family.
+    FamilyMismatch(String),
+    /// A value exceeds the limit the device reports for it, e.g. a `gso_max_size`
+    /// above its `tso_max_size`.
+    OutOfRange {
+        attr: String,
+        value: u32,
+        limit: String,
+        max: u32,
+    },
+    /// A hardware address does not have the length of the link's current one.
+    InvalidHwAddr { expected: usize, actual: usize },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// The errno of an error reported by the kernel or by a socket call, if any.
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            Error::Netlink { errno, .. } | Error::Unsupported { errno, .. } => Some(*errno),
+            Error::Io(err) => err.raw_os_error(),
+            _ => None,
+        }
+    }
+}
+
+// `std::io::Error` can't be compared, socket failures are equal if their kind and errno are
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Error::Netlink { errno, msg },
+                Error::Netlink {
+                    errno: other_errno,
+                    msg: other_msg,
+                },
+            ) => errno == other_errno && msg == other_msg,
+            (Error::Io(err), Error::Io(other)) => {
+                err.kind() == other.kind() && err.raw_os_error() == other.raw_os_error()
+            }
+            (
+                Error::KindMismatch { expected, actual },
+                Error::KindMismatch {
+                    expected: other_expected,
+                    actual: other_actual,
+                },
+            ) => expected == other_expected && actual == other_actual,
+            (
+                Error::Unsupported { protocol, errno },
+                Error::Unsupported {
+                    protocol: other_protocol,
+                    errno: other_errno,
+                },
+            ) => protocol == other_protocol && errno == other_errno,
+            (
+                Error::AlreadyExists {
+                    requested,
+                    existing,
+                },
+                Error::AlreadyExists {
+                    requested: other_requested,
+                    existing: other_existing,
+                },
+            ) => requested == other_requested && existing == other_existing,
+            (Error::Timeout, Error::Timeout) => true,
+            (Error::InvalidRoute(a), Error::InvalidRoute(b))
+            | (Error::NotFound(a), Error::NotFound(b))
+            | (Error::Parse(a), Error::Parse(b))
+            | (Error::FamilyMismatch(a), Error::FamilyMismatch(b)) => a == b,
+            (
+                Error::OutOfRange {
+                    attr,
+                    value,
+                    limit,
+                    max,
+                },
+                Error::OutOfRange {
+                    attr: other_attr,
+                    value: other_value,
+                    limit: other_limit,
+                    max: other_max,
+                },
+            ) => {
+                attr == other_attr
+                    && value == other_value
+                    && limit == other_limit
+                    && max == other_max
+            }
+            (
+                Error::InvalidHwAddr { expected, actual },
+                Error::InvalidHwAddr {
+                    expected: other_expected,
+                    actual: other_actual,
+                },
+            ) => expected == other_expected && actual == other_actual,
+            (Error::LinkTypeUnsupported { kind }, Error::LinkTypeUnsupported { kind: other }) => {
+                kind == other
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Netlink { msg, .. } => write!(f, "{msg}"),
+            Error::Io(err) => write!(f, "{err}"),
+            Error::KindMismatch { expected, actual } => {
+                write!(f, "link kind mismatch: expected {expected}, got {actual}")
+            }
+            Error::Unsupported { protocol, errno } => write!(
+                f,
+                "netlink protocol {protocol} is not supported: {}",
+                std::io::Error::from_raw_os_error(*errno)
+            ),
+            Error::AlreadyExists {
+                requested,
+                existing,
+            } => write!(f, "address {requested} conflicts with existing {existing}"),
+            Error::Timeout => write!(f, "timed out waiting for a netlink response"),
+            Error::InvalidRoute(reason) => write!(f, "invalid route: {reason}"),
+            Error::LinkTypeUnsupported { kind } => {
+                write!(f, "link kind {kind} is not supported by the kernel")
+            }
+            Error::NotFound(what) => write!(f, "{what} not found"),
+            Error::Parse(reason) => write!(f, "failed to parse netlink message: {reason}"),
+            Error::FamilyMismatch(reason) => write!(f, "address family mismatch: {reason}"),
+            Error::OutOfRange {
+                attr,
+                value,
+                limit,
+                max,
+            } => write!(f, "{attr} {value} exceeds the device limit ({limit} {max})"),
+            Error::InvalidHwAddr { expected, actual } => write!(
+                f,
+                "invalid hardware address: expected {expected} bytes, got {actual}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<ErrorMessage> for Error {
+    fn from(err: ErrorMessage) -> Self {
+        Error::Netlink {
+            errno: -err.errno,
+            msg: err.to_string(),
+        }
+    }
+}
+
+// A receive timeout set on the socket expires like the timeout of a request
+pub(crate) fn recv_error(err: std::io::Error) -> Error {
+    match err.kind() {
+        std::io::ErrorKind::TimedOut => Error::Timeout,
+        _ => Error::Io(err),
+    }
+}
+
+// Building a request or parsing a response report `anyhow::Error`s, whose cause
+// is kept if typed already
+pub(crate) fn request_error(err: anyhow::Error) -> Error {
+    err.downcast::<Error>().unwrap_or_else(|err| {
+        Error::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            err.to_string(),
+        ))
+    })
+}
+
+pub(crate) fn parse_error(err: anyhow::Error) -> Error {
+    err.downcast::<Error>()
+        .unwrap_or_else(|err| Error::Parse(err.to_string()))
+}
+
+/// The errno of an error reported by the kernel or by a socket call, if any,
+/// e.g. `libc::EPERM` when lacking `CAP_NET_ADMIN`.
+pub fn errno(err: &anyhow::Error) -> Option<i32> {
+    match err.downcast_ref::<Error>() {
+        Some(err) => err.errno(),
+        None => err
+            .downcast_ref::<std::io::Error>()
+            .and_then(|e| e.raw_os_error()),
+    }
+}
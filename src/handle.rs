@@ -1,42 +1,171 @@
-use std::net::IpAddr;
+use std::{
+    net::IpAddr,
+    os::fd::RawFd,
+    time::{Duration, Instant},
+};
 
 use anyhow::{bail, Result};
 
 use crate::{
-    addr::{self, AddrCmd, AddrFamily, Address},
+    addr::{self, AddrCmd, AddrFamily, AddrFilter, Address},
     consts,
-    link::{self, Link, LinkAttrs},
+    error::{self, Error},
+    link::{self, Kind, Link, LinkAttrs},
+    message::{ErrorMessage, NetlinkMessage},
+    neigh::{self, NeighCmd, Neighbor, NeighborState},
     request::NetlinkRequest,
     route::{self, Route, RtCmd, RtFilter},
+    rule::{self, Rule, RuleCmd},
     socket::NetlinkSocket,
+    wireguard::{self, WireguardConfig},
 };
 
+/// The number of requests `route_get_many` sends per datagram.
+const BATCH_SIZE: usize = 64;
+
 pub struct SocketHandle {
     pub seq: u32,
     pub socket: NetlinkSocket,
+    pub strict_check: bool,
+    pub timeout: Option<Duration>,
 }
 
 impl SocketHandle {
     pub fn new(protocol: i32) -> Result<Self> {
+        let socket = NetlinkSocket::new(protocol, 0, 0).map_err(|e| match e.raw_os_error() {
+            Some(errno @ (libc::EAFNOSUPPORT | libc::EPROTONOSUPPORT)) => {
+                Error::Unsupported { protocol, errno }.into()
+            }
+            _ => anyhow::Error::from(e),
+        })?;
+
+        // Extended ACKs only add detail to errors, so kernels without them are fine
+        let _ = socket.set_ext_ack(true);
+
         Ok(Self {
             seq: 0,
-            socket: NetlinkSocket::new(protocol, 0, 0)?,
+            socket,
+            strict_check: false,
+            timeout: None,
         })
     }
 
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub fn set_strict_check(&mut self, on: bool) -> Result<()> {
+        self.socket.set_strict_check(on)?;
+        self.strict_check = on;
+        Ok(())
+    }
+
     pub fn link_new(&mut self, link: &(impl Link + ?Sized), flags: i32) -> Result<()> {
         let mut req = link::link_new(link, flags)?;
-        let _ = self.execute(&mut req, 0)?;
+        let _ = self.execute(&mut req, 0).map_err(|e| {
+            // ENODEV also reports a missing parent link, which is not about the kind
+            let creating = flags & libc::NLM_F_CREATE != 0 && link.attrs().parent_index == 0;
+
+            match e.errno() {
+                Some(libc::EOPNOTSUPP | libc::ENODEV) if creating => Error::LinkTypeUnsupported {
+                    kind: link.link_type(),
+                },
+                _ => e,
+            }
+        })?;
+
+        // The master, if any, is set by the IFLA_MASTER of the request itself.
+        // A new link has no alias yet, so an empty one needs no request.
+        let alias = link.attrs().alias.as_deref().unwrap_or_default();
+        if flags & libc::NLM_F_CREATE != 0 && !alias.is_empty() {
+            if let Err(e) = self.link_set_alias(link.attrs(), alias) {
+                // Don't leave behind a link that only got half of its attributes
+                let _ = self.link_del(link.attrs());
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn link_set_master(&mut self, attrs: &LinkAttrs, master: &LinkAttrs) -> Result<()> {
+        let link = self.link_get(attrs)?;
+        let master = self.link_get(master)?;
+        let index = link.attrs().index;
 
-        if link.attrs().master_index != 0 {
-            let index = self.ensure_index(link.attrs())?;
-            let mut req = link::link_set_master(index, link.attrs().master_index)?;
+        // Bonds refuse to enslave a link that is up, they bring it up themselves once enslaved
+        if master.link_type() == "bond" && link.attrs().raw_flags & libc::IFF_UP as u32 != 0 {
+            let mut req = link::link_change_flags(index, 0, libc::IFF_UP as u32)?;
             let _ = self.execute(&mut req, 0)?;
         }
 
+        let mut req = link::link_set_master(index, master.attrs().index)?;
+        let _ = self.execute(&mut req, 0)?;
         Ok(())
     }
 
+    pub fn link_list(&mut self, master_index: i32) -> Result<Vec<Box<dyn Link>>> {
+        let mut req = link::link_list(master_index)?;
+        let msgs = self.request(&mut req, libc::RTM_NEWLINK)?;
+
+        let filtered = msgs.iter().any(|m| m.is_dump_filtered());
+
+        Ok(msgs
+            .into_iter()
+            .filter_map(|m| link::link_deserialize(&m.data).ok())
+            .filter(|link| {
+                filtered || master_index == 0 || link.attrs().master_index == master_index
+            })
+            .collect())
+    }
+
+    pub fn link_new_verify(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        flags: i32,
+    ) -> Result<Box<dyn Link>> {
+        self.link_new(link, flags)?;
+
+        let created = self.link_get(link.attrs())?;
+
+        if created.link_type() != link.link_type() {
+            return Err(Error::KindMismatch {
+                expected: link.link_type(),
+                actual: created.link_type(),
+            }
+            .into());
+        }
+
+        Ok(created)
+    }
+
+    pub fn veth_add_pair(
+        &mut self,
+        attrs: &LinkAttrs,
+        peer_name: &str,
+    ) -> Result<(Box<dyn Link>, Box<dyn Link>)> {
+        let veth = Kind::Veth {
+            attrs: attrs.clone(),
+            peer_name: peer_name.to_string(),
+            peer_hw_addr: None,
+            peer_ns: None,
+        };
+
+        self.link_new(
+            &veth,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        )?;
+
+        let link = self.link_get(attrs)?;
+
+        // A veth reports its peer as the parent link, so the peer can be looked up by index
+        let mut peer_attrs = LinkAttrs::new(peer_name);
+        peer_attrs.index = link.attrs().parent_index;
+        let peer = self.link_get(&peer_attrs)?;
+
+        Ok((link, peer))
+    }
+
     pub fn link_del(&mut self, attrs: &LinkAttrs) -> Result<()> {
         let index = self.ensure_index(attrs)?;
         let mut req = link::link_del(index)?;
@@ -44,17 +173,27 @@ impl SocketHandle {
         Ok(())
     }
 
-    pub fn link_get(&mut self, attrs: &LinkAttrs) -> Result<Box<dyn Link>> {
-        let mut req = link::link_get(attrs)?;
-        let msgs = self.execute(&mut req, 0)?;
+    /// Bring the link down before deleting it, retrying the deletion once
+    /// if the driver still reports it as busy.
+    pub fn link_del_force(&mut self, attrs: &LinkAttrs) -> Result<()> {
+        let index = self.ensure_index(attrs)?;
+        let attrs = LinkAttrs {
+            index,
+            ..Default::default()
+        };
 
-        match msgs.len() {
-            0 => bail!("no link found"),
-            1 => link::link_deserialize(&msgs[0]),
-            _ => bail!("multiple links found"),
+        self.link_setdown(&attrs)?;
+        match self.link_del(&attrs) {
+            Err(e) if error::errno(&e) == Some(libc::EBUSY) => self.link_del(&attrs),
+            res => res,
         }
     }
 
+    pub fn link_get(&mut self, attrs: &LinkAttrs) -> Result<Box<dyn Link>> {
+        let mut req = link::link_get(attrs)?;
+        link_get_response(attrs, self.execute(&mut req, 0))
+    }
+
     pub fn link_setup(&mut self, attrs: &LinkAttrs) -> Result<()> {
         let index = self.ensure_index(attrs)?;
         let mut req = link::link_setup(index)?;
@@ -62,6 +201,170 @@ impl SocketHandle {
         Ok(())
     }
 
+    pub fn link_setdown(&mut self, attrs: &LinkAttrs) -> Result<()> {
+        let index = self.ensure_index(attrs)?;
+        let mut req = link::link_setdown(index)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    /// The counterpart of `link_setup`, same as `link_setdown`.
+    pub fn link_down(&mut self, attrs: &LinkAttrs) -> Result<()> {
+        self.link_setdown(attrs)
+    }
+
+    pub fn link_set_mtu(&mut self, attrs: &LinkAttrs, mtu: u32) -> Result<()> {
+        let index = self.ensure_index(attrs)?;
+        let mut req = link::link_set_mtu(index, mtu)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    /// Like `link_set_mtu`, but reads the link first to return its previous MTU.
+    pub fn link_set_mtu_returning_old(&mut self, attrs: &LinkAttrs, mtu: u32) -> Result<u32> {
+        let link = self.link_get(attrs)?;
+        self.link_set_mtu(link.attrs(), mtu)?;
+        Ok(link.attrs().mtu)
+    }
+
+    pub fn link_set_txqlen(&mut self, attrs: &LinkAttrs, qlen: u32) -> Result<()> {
+        let index = self.ensure_index(attrs)?;
+        let mut req = link::link_set_txqlen(index, qlen)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    /// Like `link_set_txqlen`, but reads the link first to return its previous queue length.
+    pub fn link_set_txqlen_returning_old(&mut self, attrs: &LinkAttrs, qlen: u32) -> Result<u32> {
+        let link = self.link_get(attrs)?;
+        self.link_set_txqlen(link.attrs(), qlen)?;
+        Ok(link.attrs().tx_queue_len as u32)
+    }
+
+    pub fn link_set_name(&mut self, attrs: &LinkAttrs, name: &str) -> Result<()> {
+        let index = self.ensure_index(attrs)?;
+        let mut req = link::link_set_name(index, name)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    pub fn link_set_alias(&mut self, attrs: &LinkAttrs, alias: &str) -> Result<()> {
+        let index = self.ensure_index(attrs)?;
+        let mut req = link::link_set_alias(index, alias)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    /// Set the hardware address of a link. The address must have the length of the
+    /// current one, e.g. 6 bytes for Ethernet devices, or [`Error::InvalidHwAddr`]
+    /// is returned.
+    pub fn link_set_hw_addr(&mut self, attrs: &LinkAttrs, hw_addr: &[u8]) -> Result<()> {
+        let link = self.link_get(attrs)?;
+        let len = link.attrs().hw_addr.len();
+
+        // The kernel only reports a length mismatch as EINVAL
+        if hw_addr.len() != len {
+            return Err(Error::InvalidHwAddr {
+                expected: len,
+                actual: hw_addr.len(),
+            }
+            .into());
+        }
+
+        let mut req = link::link_set_hw_addr(link.attrs().index, hw_addr)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    pub fn link_set_ns_fd(&mut self, attrs: &LinkAttrs, fd: RawFd) -> Result<()> {
+        let index = self.ensure_index(attrs)?;
+        let mut req = link::link_set_ns_fd(index, fd)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    pub fn link_set_ns_pid(&mut self, attrs: &LinkAttrs, pid: i32) -> Result<()> {
+        let index = self.ensure_index(attrs)?;
+        let mut req = link::link_set_ns_pid(index, pid)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    pub fn link_set_gso_max_size(&mut self, attrs: &LinkAttrs, size: u32) -> Result<()> {
+        let link = self.link_get(attrs)?;
+        let max = link.attrs().tso_max_size;
+
+        // Older kernels don't report the limit, leave the check to the kernel then.
+        if max != 0 && size > max {
+            return Err(Error::OutOfRange {
+                attr: "gso_max_size".to_string(),
+                value: size,
+                limit: "tso_max_size".to_string(),
+                max,
+            }
+            .into());
+        }
+
+        let mut req = link::link_set_gso_max_size(link.attrs().index, size)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    pub fn link_set_gso_ipv4_max_size(&mut self, attrs: &LinkAttrs, size: u32) -> Result<()> {
+        let link = self.link_get(attrs)?;
+
+        // Kernels that know the attribute always report it, others reject it with EINVAL
+        if link.attrs().gso_ipv4_max_size == 0 {
+            bail!("gso_ipv4_max_size is not supported by this kernel (requires Linux 6.3)");
+        }
+
+        let max = link.attrs().tso_max_size;
+        if max != 0 && size > max {
+            return Err(Error::OutOfRange {
+                attr: "gso_ipv4_max_size".to_string(),
+                value: size,
+                limit: "tso_max_size".to_string(),
+                max,
+            }
+            .into());
+        }
+
+        let mut req = link::link_set_gso_ipv4_max_size(link.attrs().index, size)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    pub fn link_set_gro_ipv4_max_size(&mut self, attrs: &LinkAttrs, size: u32) -> Result<()> {
+        let link = self.link_get(attrs)?;
+
+        if link.attrs().gro_ipv4_max_size == 0 {
+            bail!("gro_ipv4_max_size is not supported by this kernel (requires Linux 6.3)");
+        }
+
+        let mut req = link::link_set_gro_ipv4_max_size(link.attrs().index, size)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    pub fn link_set_gso_max_segs(&mut self, attrs: &LinkAttrs, segs: u32) -> Result<()> {
+        let link = self.link_get(attrs)?;
+        let max = link.attrs().tso_max_segs;
+
+        if max != 0 && segs > max {
+            return Err(Error::OutOfRange {
+                attr: "gso_max_segs".to_string(),
+                value: segs,
+                limit: "tso_max_segs".to_string(),
+                max,
+            }
+            .into());
+        }
+
+        let mut req = link::link_set_gso_max_segs(link.attrs().index, segs)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
     pub fn addr_handle(&mut self, cmd: AddrCmd, attrs: &LinkAttrs, addr: &Address) -> Result<()> {
         let index = self.ensure_index(attrs)?;
         let mut req = addr::addr_handle(cmd, index, addr)?;
@@ -69,18 +372,101 @@ impl SocketHandle {
         Ok(())
     }
 
-    pub fn addr_list(
+    pub fn addr_ensure(&mut self, attrs: &LinkAttrs, addr: &Address) -> Result<()> {
+        let index = self.ensure_index(attrs)?;
+
+        let existing = self
+            .addr_list_by_index(AddrFamily::All, index)?
+            .into_iter()
+            .find(|a| a.address.addr() == addr.address.addr());
+
+        match existing {
+            Some(a) if a.address == addr.address => Ok(()),
+            Some(a) => Err(Error::AlreadyExists {
+                requested: addr.address,
+                existing: a.address,
+            }
+            .into()),
+            None => {
+                let mut req = addr::addr_handle(AddrCmd::Add, index, addr)?;
+                let _ = self.execute(&mut req, 0)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn addr_list(&mut self, attrs: &LinkAttrs, family: AddrFamily) -> Result<Vec<Address>> {
+        let index = self.ensure_index(attrs)?;
+        self.addr_list_by_index(family, index)
+    }
+
+    pub fn addr_list_exact(
         &mut self,
-        link: &(impl Link + ?Sized),
+        attrs: &LinkAttrs,
         family: AddrFamily,
+        scope: Option<u8>,
     ) -> Result<Vec<Address>> {
-        let mut req = addr::addr_list(family)?;
+        let filter = AddrFilter {
+            scope,
+            ..Default::default()
+        };
+        self.addr_list_filtered(attrs, family, &filter)
+    }
 
-        Ok(self
-            .execute(&mut req, libc::RTM_NEWADDR)?
+    pub fn addr_list_filtered(
+        &mut self,
+        attrs: &LinkAttrs,
+        family: AddrFamily,
+        filter: &AddrFilter,
+    ) -> Result<Vec<Address>> {
+        let index = self.ensure_index(attrs)?;
+        let addrs = self.addr_list_by_index(family, index)?;
+
+        Ok(addrs
+            .into_iter()
+            .filter(|addr| filter.matches(addr))
+            .collect())
+    }
+
+    fn addr_list_by_index(&mut self, family: AddrFamily, index: i32) -> Result<Vec<Address>> {
+        let mut req = addr::addr_list(family, index)?;
+        let msgs = self.dump(&mut req, libc::RTM_NEWADDR)?;
+
+        let filtered = msgs.iter().any(|m| m.is_dump_filtered());
+
+        let mut addrs = msgs
+            .into_iter()
+            .filter_map(|m| addr::addr_deserialize(&m.data).ok())
+            .filter(|addr| filtered || addr.index == index)
+            .collect();
+        addr::addr_sort_dedup(&mut addrs);
+
+        Ok(addrs)
+    }
+
+    pub fn neigh_handle(&mut self, cmd: NeighCmd, neigh: &Neighbor) -> Result<()> {
+        let mut req = neigh::neigh_handle(cmd, neigh)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    pub fn neigh_list(
+        &mut self,
+        family: AddrFamily,
+        index: i32,
+        state: Option<NeighborState>,
+    ) -> Result<Vec<Neighbor>> {
+        let mut req = neigh::neigh_list(family, index)?;
+        let msgs = self.request(&mut req, libc::RTM_NEWNEIGH)?;
+
+        let filtered = msgs.iter().any(|m| m.is_dump_filtered());
+
+        // The kernel can't filter a neighbor dump by state
+        Ok(msgs
             .into_iter()
-            .filter_map(|m| addr::addr_deserialize(&m).ok())
-            .filter(|addr| addr.index == link.attrs().index)
+            .filter_map(|m| neigh::neigh_deserialize(&m.data).ok())
+            .filter(|neigh| filtered || index == 0 || neigh.index == index)
+            .filter(|neigh| state.is_none_or(|state| neigh.state.matches(state)))
             .collect())
     }
 
@@ -90,6 +476,46 @@ impl SocketHandle {
         Ok(())
     }
 
+    pub fn route_prepend(&mut self, route: &Route) -> Result<u32> {
+        let family = match route.dst.map(|dst| dst.addr()).or(route.gw) {
+            Some(IpAddr::V6(_)) => AddrFamily::V6,
+            None if route.family == libc::AF_INET6 as u8 => AddrFamily::V6,
+            _ => AddrFamily::V4,
+        };
+        // The full table id, as the header only holds ids up to 255
+        let table = match route.table_id.unwrap_or(route.table as u32) {
+            id if id == libc::RT_TABLE_UNSPEC as u32 => libc::RT_TABLE_MAIN as u32,
+            id => id,
+        };
+
+        // The routes the kernel would choose between, i.e. with the same dst, tos and table
+        let best = self
+            .route_list(family, 0, RtFilter::Table(table))?
+            .into_iter()
+            .filter(|r| r.dst == route.dst && r.tos == route.tos)
+            // IPv4 routes with metric 0 have no RTA_PRIORITY
+            .map(|r| r.priority.unwrap_or(0))
+            .min();
+
+        let priority = match best {
+            None => route.priority.unwrap_or(0),
+            Some(0) => bail!(
+                "no metric below 0 is free to prepend a route to {:?}",
+                route.dst
+            ),
+            Some(best) => best - 1,
+        };
+
+        self.route_handle(
+            RtCmd::Add,
+            &Route {
+                priority: Some(priority),
+                ..route.clone()
+            },
+        )?;
+        Ok(priority)
+    }
+
     pub fn route_get(&mut self, dst: &IpAddr) -> Result<Vec<Route>> {
         let mut req = route::route_get(dst)?;
 
@@ -100,110 +526,603 @@ impl SocketHandle {
             .collect())
     }
 
+    /// Look up the routes to many destinations, sending the lookups in batches.
+    /// A failed lookup only fails the result of its own destination.
+    pub fn route_get_many(&mut self, dsts: &[IpAddr]) -> Result<Vec<(IpAddr, Result<Vec<Route>>)>> {
+        let mut res = Vec::with_capacity(dsts.len());
+
+        for chunk in dsts.chunks(BATCH_SIZE) {
+            let mut reqs = chunk
+                .iter()
+                .map(route::route_get)
+                .collect::<Result<Vec<_>>>()?;
+            let responses = self.request_many(&mut reqs, libc::RTM_NEWROUTE)?;
+
+            res.extend(chunk.iter().zip(responses).map(|(dst, msgs)| {
+                let routes = msgs.map_err(Into::into).map(|msgs| {
+                    msgs.into_iter()
+                        .filter_map(|m| route::route_deserialize(&m.data).ok())
+                        .collect()
+                });
+                (*dst, routes)
+            }));
+        }
+
+        Ok(res)
+    }
+
     pub fn route_list(
         &mut self,
         family: AddrFamily,
         index: i32,
         filter_mask: RtFilter,
     ) -> Result<Vec<Route>> {
+        let (oif_index, table_id) = match filter_mask {
+            RtFilter::Table(table) => (0, Some(table)),
+            RtFilter::OifTable(table) => (index, Some(table)),
+            RtFilter::Oif => (index, None),
+            RtFilter::None => (0, None),
+        };
+
         let route = Route {
             family: family as u8,
-            oif_index: index,
+            oif_index,
+            table_id,
             ..Default::default()
         };
 
         let mut req = route::route_handle(RtCmd::Show, &route)?;
+        let msgs = match self.dump(&mut req, 0) {
+            // With strict checking, the dump of a table that doesn't exist fails
+            Err(e) if table_id.is_some() && error::errno(&e) == Some(libc::ENOENT) => Vec::new(),
+            res => res?,
+        };
 
-        Ok(self
-            .execute(&mut req, 0)?
+        // Fall back to filtering here when the kernel ignored the requested filter.
+        let filtered = msgs.iter().any(|m| m.is_dump_filtered());
+        let table_of = |route: &Route| route.table_id.unwrap_or(route.table as u32);
+
+        Ok(msgs
             .into_iter()
-            .filter_map(|m| route::route_deserialize(&m).ok())
+            .filter_map(|m| route::route_deserialize(&m.data).ok())
             .filter(|route| match filter_mask {
+                _ if filtered => true,
                 RtFilter::Oif => route.oif_index == index,
+                RtFilter::Table(table) => table_of(route) == table,
+                RtFilter::OifTable(table) => route.oif_index == index && table_of(route) == table,
                 RtFilter::None => true,
             })
             .collect())
     }
 
-    fn ensure_index(&mut self, attrs: &LinkAttrs) -> Result<i32> {
+    pub fn rule_handle(&mut self, cmd: RuleCmd, rule: &Rule) -> Result<()> {
+        let mut req = rule::rule_handle(cmd, rule)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    pub fn rule_list(&mut self, family: AddrFamily) -> Result<Vec<Rule>> {
+        let mut req = rule::rule_list(family)?;
+
+        Ok(self
+            .execute(&mut req, libc::RTM_NEWRULE)?
+            .into_iter()
+            .filter_map(|m| rule::rule_deserialize(&m).ok())
+            .collect())
+    }
+
+    /// The id of the generic netlink family `name`, on a `NETLINK_GENERIC` socket.
+    pub fn genl_family_id(&mut self, name: &str) -> Result<u16> {
+        let mut req = wireguard::family_get(name)?;
+        let msgs = self
+            .execute(&mut req, consts::GENL_ID_CTRL)
+            .map_err(|e| match e.errno() {
+                Some(libc::ENOENT) => Error::NotFound(format!("generic netlink family {name}")),
+                _ => e,
+            })?;
+
+        match msgs.first() {
+            Some(msg) => wireguard::family_id_deserialize(msg),
+            None => Err(Error::NotFound(format!("generic netlink family {name}")).into()),
+        }
+    }
+
+    /// Configure the WireGuard link at `index`, on a `NETLINK_GENERIC` socket.
+    pub fn wireguard_set(&mut self, index: i32, config: &WireguardConfig) -> Result<()> {
+        let family_id = self.genl_family_id(consts::WG_GENL_NAME)?;
+        let mut req = wireguard::wireguard_set(family_id, index, config)?;
+        let _ = self.execute(&mut req, 0)?;
+        Ok(())
+    }
+
+    pub(crate) fn ensure_index(&mut self, attrs: &LinkAttrs) -> Result<i32> {
         Ok(match attrs.index {
             0 => self.link_get(attrs)?.attrs().index,
             _ => attrs.index,
         })
     }
 
-    fn execute(&mut self, req: &mut NetlinkRequest, res_type: u16) -> Result<Vec<Vec<u8>>> {
+    fn execute(&mut self, req: &mut NetlinkRequest, res_type: u16) -> error::Result<Vec<Vec<u8>>> {
+        Ok(self
+            .request(req, res_type)?
+            .into_iter()
+            .map(|m| m.data)
+            .collect())
+    }
+
+    /// Send a fully-formed request and return the raw messages of the response,
+    /// without interpreting them. This allows building on the socket plumbing
+    /// for message types the crate doesn't support yet.
+    ///
+    /// A non-zero `res_type` skips the response messages of any other type.
+    /// An error reported by the kernel is returned as an error.
+    pub fn request(
+        &mut self,
+        req: &mut NetlinkRequest,
+        res_type: u16,
+    ) -> error::Result<Vec<NetlinkMessage>> {
         req.header.nlmsg_seq = {
             self.seq += 1;
             self.seq
         };
 
-        let buf = req.serialize()?;
+        let buf = req.serialize().map_err(error::request_error)?;
 
         self.socket.send(&buf)?;
 
-        let pid = self.socket.pid()?;
-        let mut res: Vec<Vec<u8>> = Vec::new();
+        self.receive(req.header.nlmsg_seq, res_type)
+    }
 
-        'done: loop {
-            let (msgs, from) = self.socket.recv()?;
+    /// Send a dump request with strict checking enabled for it, so that the kernel
+    /// applies the filters of the request (e.g. a link index) instead of returning
+    /// everything, and restore the previous setting afterwards.
+    ///
+    /// Kernels before 4.20 reject `NETLINK_GET_STRICT_CHK` with `ENOPROTOOPT` or
+    /// `EINVAL`; the dump is then sent as is, and its messages lack `NLM_F_DUMP_FILTERED`
+    /// so that callers know to filter the results themselves.
+    fn dump(&mut self, req: &mut NetlinkRequest, res_type: u16) -> Result<Vec<NetlinkMessage>> {
+        if self.strict_check {
+            return Ok(self.request(req, res_type)?);
+        }
 
-            if from.nl_pid != consts::PID_KERNEL {
-                bail!(
-                    "wrong sender pid: {}, expected: {}",
-                    from.nl_pid,
-                    consts::PID_KERNEL
-                );
+        match self.socket.set_strict_check(true) {
+            Ok(()) => {}
+            Err(e) if matches!(e.raw_os_error(), Some(libc::ENOPROTOOPT | libc::EINVAL)) => {
+                return Ok(self.request(req, res_type)?);
             }
+            Err(e) => return Err(e.into()),
+        }
 
-            for m in msgs {
-                if m.header.nlmsg_seq != req.header.nlmsg_seq {
-                    continue;
-                }
+        let res = self.request(req, res_type);
+        self.socket.set_strict_check(false)?;
+        Ok(res?)
+    }
 
-                if m.header.nlmsg_pid != pid {
-                    continue;
-                }
+    /// Send several requests in a single datagram and return the response to each,
+    /// in the order of the requests. Responses are matched to their request by
+    /// sequence number, and an error reported by the kernel for one request
+    /// doesn't affect the others.
+    pub fn request_many(
+        &mut self,
+        reqs: &mut [NetlinkRequest],
+        res_type: u16,
+    ) -> error::Result<Vec<error::Result<Vec<NetlinkMessage>>>> {
+        let first_seq = self.seq + 1;
+        let mut buf = Vec::new();
+
+        for req in reqs.iter_mut() {
+            self.seq += 1;
+            req.header.nlmsg_seq = self.seq;
+            buf.extend(req.serialize().map_err(error::request_error)?);
+        }
 
-                match m.header.nlmsg_type {
-                    consts::NLMSG_DONE | consts::NLMSG_ERROR => {
-                        let err_no = i32::from_ne_bytes(m.data[0..4].try_into()?);
+        if reqs.is_empty() {
+            return Ok(Vec::new());
+        }
 
-                        if err_no == 0 {
-                            break 'done;
-                        }
+        self.socket.send(&buf)?;
 
-                        let err_msg = unsafe { std::ffi::CStr::from_ptr(libc::strerror(-err_no)) };
-                        bail!("{} ({}): {:?}", err_msg.to_str()?, -err_no, &m.data[4..]);
-                    }
-                    t if res_type != 0 && t != res_type => {
-                        continue;
-                    }
-                    _ => {
-                        res.push(m.data);
-                    }
-                }
+        self.receive_many(first_seq, reqs.len(), res_type)
+    }
+
+    fn receive(&mut self, seq: u32, res_type: u16) -> error::Result<Vec<NetlinkMessage>> {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let pid = self.socket.pid()?;
+        let mut res: Vec<NetlinkMessage> = Vec::new();
 
-                if m.header.nlmsg_flags & libc::NLM_F_MULTI as u16 == 0 {
-                    break 'done;
+        loop {
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+
+                if !self.socket.wait_readable(remaining)? {
+                    return Err(Error::Timeout);
                 }
             }
+
+            let (msgs, from) = self.socket.recv().map_err(error::recv_error)?;
+
+            if from.nl_pid != consts::PID_KERNEL {
+                return Err(wrong_sender(from.nl_pid));
+            }
+
+            if collect_response(msgs, seq, pid, res_type, &mut res)? {
+                break;
+            }
         }
 
         Ok(res)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{
-        addr,
-        link::{self, Kind, LinkAttrs},
-        route::{Route, RtCmd},
-        test_setup,
-    };
+    fn receive_many(
+        &mut self,
+        first_seq: u32,
+        count: usize,
+        res_type: u16,
+    ) -> error::Result<Vec<error::Result<Vec<NetlinkMessage>>>> {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let pid = self.socket.pid()?;
+        let mut res: Vec<error::Result<Vec<NetlinkMessage>>> =
+            (0..count).map(|_| Ok(Vec::new())).collect();
+        let mut done = vec![false; count];
+        let mut pending = count;
 
-    #[test]
+        while pending > 0 {
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+
+                if !self.socket.wait_readable(remaining)? {
+                    return Err(Error::Timeout);
+                }
+            }
+
+            let (msgs, from) = self.socket.recv().map_err(error::recv_error)?;
+
+            if from.nl_pid != consts::PID_KERNEL {
+                return Err(wrong_sender(from.nl_pid));
+            }
+
+            for m in msgs {
+                let seq = m.header.nlmsg_seq;
+                let i = seq.wrapping_sub(first_seq) as usize;
+                if i >= count || done[i] {
+                    continue;
+                }
+
+                // Messages are collected one at a time so that an error only ends its own response
+                let complete = match &mut res[i] {
+                    Ok(msgs) => match collect_response(vec![m], seq, pid, res_type, msgs) {
+                        Ok(complete) => complete,
+                        Err(e) => {
+                            res[i] = Err(e);
+                            true
+                        }
+                    },
+                    Err(_) => true,
+                };
+
+                if complete {
+                    done[i] = true;
+                    pending -= 1;
+                }
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+// Responses only come from the kernel
+pub(crate) fn wrong_sender(pid: u32) -> Error {
+    Error::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("wrong sender pid: {pid}, expected: {}", consts::PID_KERNEL),
+    ))
+}
+
+/// The link of the response to a `link_get` request
+pub(crate) fn link_get_response(
+    attrs: &LinkAttrs,
+    res: error::Result<Vec<Vec<u8>>>,
+) -> Result<Box<dyn Link>> {
+    let not_found = || Error::NotFound(format!("link {:?} index {}", attrs.name, attrs.index));
+
+    let msgs = res.map_err(|e| match e.errno() {
+        Some(libc::ENODEV) => not_found(),
+        _ => e,
+    })?;
+
+    match msgs.len() {
+        0 => Err(not_found().into()),
+        1 => link::link_deserialize(&msgs[0]),
+        n => bail!(
+            "{n} links found for name {:?} index {}, expected one",
+            attrs.name,
+            attrs.index
+        ),
+    }
+}
+
+/// Collect the messages of the response to `seq` from one received buffer
+/// and return whether the response is complete. The whole buffer is processed,
+/// so messages of the response packed after an ACK in the same datagram are kept.
+pub(crate) fn collect_response(
+    msgs: Vec<NetlinkMessage>,
+    seq: u32,
+    pid: u32,
+    res_type: u16,
+    res: &mut Vec<NetlinkMessage>,
+) -> error::Result<bool> {
+    let mut done = false;
+
+    for m in msgs {
+        if m.header.nlmsg_seq != seq {
+            continue;
+        }
+
+        if m.header.nlmsg_pid != pid {
+            continue;
+        }
+
+        match m.header.nlmsg_type {
+            consts::NLMSG_DONE | consts::NLMSG_ERROR => {
+                let err = ErrorMessage::deserialize(&m).map_err(error::parse_error)?;
+
+                if err.errno == 0 {
+                    done = true;
+                    continue;
+                }
+
+                // The echoed request tells whether the error answers this request
+                if err.request.is_some_and(|r| r.nlmsg_seq != seq) {
+                    continue;
+                }
+
+                return Err(err.into());
+            }
+            t if res_type != 0 && t != res_type => {
+                continue;
+            }
+            _ => {}
+        }
+
+        let multi = m.header.nlmsg_flags & libc::NLM_F_MULTI as u16 != 0;
+        res.push(m);
+
+        if !multi {
+            done = true;
+        }
+    }
+
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::IpAddr, os::fd::AsRawFd};
+
+    use crate::{
+        addr, consts,
+        error::Error,
+        link::{self, BondMode, BridgePortState, Kind, Link, LinkAttrs, MacvlanMode, SlaveInfo},
+        message::{ErrorMessage, InfoMessage, NetlinkMessage, NetlinkRouteAttr},
+        neigh::{self, NeighCmd, Neighbor, NeighborState},
+        request::{NetlinkRequest, NetlinkRequestData},
+        route::{self, NextHop, Route, RouteType, RtCmd, RtFilter},
+        rule::{Rule, RuleCmd},
+        test_setup,
+    };
+
+    fn message(nlmsg_type: u16, seq: u32, data: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(16 + data.len() as u32).to_ne_bytes());
+        buf.extend_from_slice(&nlmsg_type.to_ne_bytes());
+        buf.extend_from_slice(&0u16.to_ne_bytes());
+        buf.extend_from_slice(&seq.to_ne_bytes());
+        buf.extend_from_slice(&42u32.to_ne_bytes());
+        buf.extend_from_slice(data);
+        buf
+    }
+
+    #[test]
+    fn test_collect_response_after_ack() {
+        // An ACK, a response message and a message of another request in one datagram
+        let mut buf = message(consts::NLMSG_ERROR, 1, &[0; 20]);
+        buf.extend(message(libc::RTM_NEWLINK, 1, &[1; 16]));
+        buf.extend(message(libc::RTM_NEWLINK, 2, &[2; 16]));
+
+        let msgs = NetlinkMessage::from(&buf).unwrap();
+        let mut res = Vec::new();
+
+        assert!(super::collect_response(msgs, 1, 42, libc::RTM_NEWLINK, &mut res).unwrap());
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].data, [1; 16]);
+    }
+
+    #[test]
+    fn test_collect_response_error() {
+        let mut buf = message(libc::RTM_NEWLINK, 1, &[1; 16]);
+        buf.extend(message(
+            consts::NLMSG_ERROR,
+            1,
+            &(-libc::ENODEV).to_ne_bytes(),
+        ));
+
+        let msgs = NetlinkMessage::from(&buf).unwrap();
+        let err = super::collect_response(msgs, 1, 42, 0, &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains(&format!("({})", libc::ENODEV)));
+        assert!(matches!(
+            err,
+            Error::Netlink {
+                errno: libc::ENODEV,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_collect_response_ext_ack() {
+        // The rejected RTM_NEWLINK request: an ifinfomsg followed by IFLA_MTU
+        let mut request = message(libc::RTM_NEWLINK, 1, &[0; 16]);
+        request.extend_from_slice(&8u16.to_ne_bytes());
+        request.extend_from_slice(&libc::IFLA_MTU.to_ne_bytes());
+        request.extend_from_slice(&0u32.to_ne_bytes());
+        let len = request.len() as u32;
+        request[..4].copy_from_slice(&len.to_ne_bytes());
+
+        let mut data = (-libc::EINVAL).to_ne_bytes().to_vec();
+        data.extend_from_slice(&request);
+        // NLMSGERR_ATTR_MSG
+        data.extend_from_slice(&12u16.to_ne_bytes());
+        data.extend_from_slice(&consts::NLMSGERR_ATTR_MSG.to_ne_bytes());
+        data.extend_from_slice(b"bad mtu\0");
+        // NLMSGERR_ATTR_OFFS
+        data.extend_from_slice(&8u16.to_ne_bytes());
+        data.extend_from_slice(&consts::NLMSGERR_ATTR_OFFS.to_ne_bytes());
+        data.extend_from_slice(&32u32.to_ne_bytes());
+
+        let mut buf = message(consts::NLMSG_ERROR, 1, &data);
+        buf[6..8].copy_from_slice(&consts::NLM_F_ACK_TLVS.to_ne_bytes());
+
+        let msgs = NetlinkMessage::from(&buf).unwrap();
+        let err = ErrorMessage::deserialize(&msgs[0]).unwrap();
+
+        assert_eq!(err.errno, -libc::EINVAL);
+        assert_eq!(err.request.unwrap().nlmsg_seq, 1);
+        assert_eq!(err.ext_msg.as_deref(), Some("bad mtu"));
+        assert_eq!(err.offset, Some(32));
+        assert_eq!(err.attr_type, Some(libc::IFLA_MTU));
+
+        let err = super::collect_response(msgs, 1, 42, 0, &mut Vec::new()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&format!("({}): bad mtu", libc::EINVAL)));
+        assert!(err
+            .to_string()
+            .contains(&format!("type {} at offset 32", libc::IFLA_MTU)));
+    }
+
+    #[test]
+    fn test_collect_response_ext_ack_capped() {
+        let mut data = (-libc::EINVAL).to_ne_bytes().to_vec();
+        data.extend_from_slice(&message(libc::RTM_NEWLINK, 1, &[0; 24])[..16]);
+        // NLMSGERR_ATTR_OFFS
+        data.extend_from_slice(&8u16.to_ne_bytes());
+        data.extend_from_slice(&consts::NLMSGERR_ATTR_OFFS.to_ne_bytes());
+        data.extend_from_slice(&32u32.to_ne_bytes());
+
+        let mut buf = message(consts::NLMSG_ERROR, 1, &data);
+        let flags = consts::NLM_F_CAPPED | consts::NLM_F_ACK_TLVS;
+        buf[6..8].copy_from_slice(&flags.to_ne_bytes());
+
+        let msgs = NetlinkMessage::from(&buf).unwrap();
+        let err = ErrorMessage::deserialize(&msgs[0]).unwrap();
+
+        assert_eq!(err.offset, Some(32));
+        assert_eq!(err.attr_type, None);
+    }
+
+    #[test]
+    fn test_collect_response_ext_ack_short_offset() {
+        let mut data = (-libc::EINVAL).to_ne_bytes().to_vec();
+        data.extend_from_slice(&message(libc::RTM_NEWLINK, 1, &[0; 16]));
+        // NLMSGERR_ATTR_OFFS with a 2-byte value
+        data.extend_from_slice(&6u16.to_ne_bytes());
+        data.extend_from_slice(&consts::NLMSGERR_ATTR_OFFS.to_ne_bytes());
+        data.extend_from_slice(&[32, 0, 0, 0]);
+
+        let mut buf = message(consts::NLMSG_ERROR, 1, &data);
+        buf[6..8].copy_from_slice(&consts::NLM_F_ACK_TLVS.to_ne_bytes());
+
+        let msgs = NetlinkMessage::from(&buf).unwrap();
+        let err = ErrorMessage::deserialize(&msgs[0]).unwrap();
+
+        assert_eq!(err.errno, -libc::EINVAL);
+        assert_eq!(err.offset, None);
+        assert_eq!(err.attr_type, None);
+    }
+
+    #[test]
+    fn test_ext_ack() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+
+        // The kernel explains why it rejects an MTU below the minimum of the device
+        let err = handle.link_set_mtu(foo.attrs(), 1).unwrap_err();
+        assert!(err.to_string().contains("mtu less than device minimum"));
+    }
+
+    #[test]
+    fn test_unsupported_protocol() {
+        // Protocols beyond MAX_LINKS are never available
+        let err = super::SocketHandle::new(32).err().unwrap();
+
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::Unsupported {
+                protocol: 32,
+                errno: libc::EPROTONOSUPPORT
+            })
+        );
+    }
+
+    #[test]
+    fn test_execute_timeout() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        handle.set_timeout(Some(std::time::Duration::from_millis(100)));
+
+        let mut req = link::link_get(&LinkAttrs::new("lo")).unwrap();
+        req.header.nlmsg_seq = 1;
+        handle.socket.send(&req.serialize().unwrap()).unwrap();
+
+        // The response for seq 1 is skipped while waiting for seq 2
+        let err = handle.receive(2, 0).err().unwrap();
+        assert_eq!(err, Error::Timeout);
+
+        // Still usable afterwards
+        handle.link_get(&LinkAttrs::new("lo")).unwrap();
+    }
+
+    #[test]
+    fn test_execute_netlink_error() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let mut attrs = LinkAttrs::new("");
+        attrs.index = 4242;
+        let mut req = link::link_get(&attrs).unwrap();
+
+        // The errno is matched on, not parsed from the message
+        let err = handle.execute(&mut req, 0).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Netlink {
+                errno: libc::ENODEV,
+                ..
+            }
+        ));
+        assert_eq!(err.errno(), Some(libc::ENODEV));
+        assert_eq!(crate::error::errno(&err.into()), Some(libc::ENODEV));
+    }
+
+    #[test]
+    fn test_execute_recv_timeout() {
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        handle
+            .socket
+            .set_recv_timeout(Some(std::time::Duration::from_millis(50)))
+            .unwrap();
+
+        // No request is pending, so no response ever arrives
+        let err = handle.receive(1, 0).err().unwrap();
+        assert_eq!(err, Error::Timeout);
+
+        handle.link_get(&LinkAttrs::new("lo")).unwrap();
+    }
+
+    #[test]
     fn test_link_add_modify_del() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
@@ -237,6 +1156,25 @@ mod tests {
         assert!(res.is_some());
     }
 
+    #[test]
+    fn test_link_ifb() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let attr = LinkAttrs::new("ifb0");
+
+        let link = handle
+            .link_new_verify(
+                &Kind::Ifb(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+        assert_eq!(link.link_type(), "ifb");
+        assert!(matches!(link.kind(), Kind::Ifb(_)));
+
+        handle.link_del(link.attrs()).unwrap();
+        assert!(handle.link_get(&attr).is_err());
+    }
+
     #[test]
     fn test_link_bridge() {
         test_setup!();
@@ -249,6 +1187,8 @@ mod tests {
             ageing_time: Some(30102),
             multicast_snooping: None,
             vlan_filtering: Some(true),
+            stp_state: None,
+            priority: None,
         };
 
         handle
@@ -269,6 +1209,7 @@ mod tests {
                 ageing_time,
                 multicast_snooping,
                 vlan_filtering,
+                ..
             } => {
                 assert_eq!(hello_time.unwrap(), 200);
                 assert_eq!(ageing_time.unwrap(), 30102);
@@ -285,19 +1226,52 @@ mod tests {
     }
 
     #[test]
-    fn test_link_veth() {
+    fn test_link_type_unsupported() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let link = Kind::Generic {
+            attrs: LinkAttrs::new("foo"),
+            kind: "nosuchkind".to_string(),
+            info_data: vec![],
+        };
+
+        let err = handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::LinkTypeUnsupported {
+                kind: "nosuchkind".to_string()
+            })
+        );
+
+        // Other errors of the request are passed through
+        let err = handle.link_new(&link, libc::NLM_F_ACK).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::Netlink { .. })
+        ));
+    }
+
+    #[test]
+    fn test_link_bridge_stp() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
 
-        let attr = LinkAttrs::new("br");
         let link = Kind::Bridge {
-            attrs: attr.clone(),
+            attrs: LinkAttrs::new("foo"),
             hello_time: None,
-            ageing_time: Some(30102),
+            ageing_time: None,
             multicast_snooping: None,
-            vlan_filtering: Some(true),
+            vlan_filtering: None,
+            stp_state: Some(1),
+            priority: Some(0x1000),
         };
-
         handle
             .link_new(
                 &link,
@@ -305,24 +1279,59 @@ mod tests {
             )
             .unwrap();
 
-        let link = handle.link_get(&attr).unwrap();
-        let master_index = link.attrs().index;
-
-        let mut attr = LinkAttrs::new("foo");
-        attr.mtu = 1400;
-        attr.tx_queue_len = 100;
-        attr.num_tx_queues = 4;
-        attr.num_rx_queues = 8;
-        attr.master_index = master_index;
+        let link = handle.link_get(link.attrs()).unwrap();
+        match link.kind() {
+            Kind::Bridge {
+                stp_state,
+                priority,
+                ..
+            } => {
+                assert_eq!(*stp_state, Some(1));
+                assert_eq!(*priority, Some(0x1000));
+            }
+            _ => panic!("wrong link type"),
+        }
 
-        // TODO: need to set peer hw addr and peer ns
-        let link = Kind::Veth {
-            attrs: attr.clone(),
-            peer_name: "bar".to_string(),
-            peer_hw_addr: None,
-            peer_ns: None,
+        // Disable STP in place
+        let link = Kind::Bridge {
+            attrs: link.attrs().clone(),
+            hello_time: None,
+            ageing_time: None,
+            multicast_snooping: None,
+            vlan_filtering: None,
+            stp_state: Some(0),
+            priority: None,
         };
+        handle.link_new(&link, libc::NLM_F_ACK).unwrap();
+
+        let link = handle.link_get(link.attrs()).unwrap();
+        match link.kind() {
+            Kind::Bridge {
+                stp_state,
+                priority,
+                ..
+            } => {
+                assert_eq!(*stp_state, Some(0));
+                assert_eq!(*priority, Some(0x1000));
+            }
+            _ => panic!("wrong link type"),
+        }
+    }
+
+    #[test]
+    fn test_link_bridge_modify() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
 
+        let link = Kind::Bridge {
+            attrs: LinkAttrs::new("foo"),
+            hello_time: None,
+            ageing_time: Some(30102),
+            multicast_snooping: None,
+            vlan_filtering: None,
+            stp_state: None,
+            priority: None,
+        };
         handle
             .link_new(
                 &link,
@@ -330,102 +1339,1954 @@ mod tests {
             )
             .unwrap();
 
-        let link = handle.link_get(&attr).unwrap();
-
-        let peer = handle
-            .link_get(&LinkAttrs {
-                name: "bar".to_string(),
-                ..Default::default()
-            })
-            .unwrap();
-
-        assert_eq!(link.attrs().link_type, "veth");
-        assert_eq!(link.attrs().name, "foo");
-        assert_eq!(link.attrs().mtu, 1400);
-        assert_eq!(link.attrs().tx_queue_len, 100);
-        assert_eq!(link.attrs().num_tx_queues, 4);
-        assert_eq!(link.attrs().num_rx_queues, 8);
-
-        assert_eq!(peer.attrs().link_type, "veth");
-        assert_eq!(peer.attrs().name, "bar");
-        assert_eq!(peer.attrs().mtu, 1400);
-        assert_eq!(peer.attrs().tx_queue_len, 100);
-        assert_eq!(peer.attrs().num_tx_queues, 4);
-        assert_eq!(peer.attrs().num_rx_queues, 8);
+        let link = handle.link_get(link.attrs()).unwrap();
+        let link = match link.kind() {
+            Kind::Bridge {
+                attrs,
+                hello_time,
+                multicast_snooping,
+                ..
+            } => Kind::Bridge {
+                attrs: attrs.clone(),
+                hello_time: *hello_time,
+                ageing_time: Some(60000),
+                multicast_snooping: Some(!multicast_snooping.unwrap()),
+                vlan_filtering: None,
+                stp_state: None,
+                priority: None,
+            },
+            _ => panic!("wrong link type"),
+        };
 
-        handle.link_del(peer.attrs()).unwrap();
+        // Without NLM_F_CREATE the kernel changes the existing bridge in place
+        handle.link_new(&link, libc::NLM_F_ACK).unwrap();
 
-        let res = handle.link_get(&attr).err();
-        assert!(res.is_some());
+        let modified = handle.link_get(link.attrs()).unwrap();
+        assert_eq!(modified.attrs().index, link.attrs().index);
+
+        match (modified.kind(), &link) {
+            (
+                Kind::Bridge {
+                    ageing_time,
+                    multicast_snooping,
+                    ..
+                },
+                Kind::Bridge {
+                    multicast_snooping: requested,
+                    ..
+                },
+            ) => {
+                assert_eq!(*ageing_time, Some(60000));
+                assert_eq!(multicast_snooping, requested);
+            }
+            _ => panic!("wrong link type"),
+        }
     }
 
     #[test]
-    fn test_link_get() {
+    fn test_veth_add_pair() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
-        let attr = link::LinkAttrs::new("lo");
 
-        let link = handle.link_get(&attr).unwrap();
+        let (link, peer) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
 
-        assert_eq!(link.attrs().index, 1);
-        assert_eq!(link.attrs().name, "lo");
+        assert_eq!(link.link_type(), "veth");
+        assert_eq!(link.attrs().name, "foo");
+        assert_eq!(peer.link_type(), "veth");
+        assert_eq!(peer.attrs().name, "bar");
+        assert_eq!(link.attrs().parent_index, peer.attrs().index);
+        assert_eq!(peer.attrs().parent_index, link.attrs().index);
     }
 
     #[test]
-    fn test_addr_handle() {
+    fn test_link_generic() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
-        let attr = link::LinkAttrs::new("lo");
 
-        let link = handle.link_get(&attr).unwrap();
+        // IFLA_VXLAN_ID
+        let info_data = NetlinkRouteAttr::new(1, 42u32.to_ne_bytes().to_vec())
+            .serialize()
+            .unwrap();
 
-        let address = "127.0.0.2/24".parse().unwrap();
-        let addr = addr::Address {
-            address,
-            ..Default::default()
+        let link = Kind::Generic {
+            attrs: LinkAttrs::new("foo"),
+            kind: "vxlan".to_string(),
+            info_data,
         };
 
         handle
-            .addr_handle(addr::AddrCmd::Add, link.attrs(), &addr)
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
             .unwrap();
 
-        let addrs = handle.addr_list(&link, addr::AddrFamily::All).unwrap();
+        // vxlan links are read back natively
+        let link = handle.link_get(link.attrs()).unwrap();
+        assert_eq!(link.link_type(), "vxlan");
 
-        assert_eq!(addrs.len(), 1);
-        assert_eq!(addrs[0].address, address);
+        match link.kind() {
+            Kind::Vxlan { vxlan_id, .. } => assert_eq!(*vxlan_id, 42),
+            _ => panic!("wrong link type"),
+        }
     }
 
     #[test]
-    fn test_route_handle() {
+    fn test_link_vxlan() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
-        let attr = link::LinkAttrs::new("lo");
-
-        let link = handle.link_get(&attr).unwrap();
 
-        handle.link_setup(link.attrs()).unwrap();
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
 
-        let route = Route {
-            oif_index: link.attrs().index,
-            dst: Some("192.168.0.0/24".parse().unwrap()),
-            src: Some("127.0.0.2".parse().unwrap()),
-            ..Default::default()
+        let link = Kind::Vxlan {
+            attrs: LinkAttrs::new("vxlan100"),
+            vxlan_id: 100,
+            vtep_dev_index: Some(foo.attrs().index),
+            group: None,
+            local: None,
+            port: Some(4789),
+            learning: Some(false),
         };
 
-        handle.route_handle(RtCmd::Add, &route).unwrap();
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
 
-        let routes = handle.route_get(&route.dst.unwrap().addr()).unwrap();
+        let link = handle.link_get(link.attrs()).unwrap();
+        assert_eq!(link.link_type(), "vxlan");
 
-        assert_eq!(routes.len(), 1);
-        assert_eq!(routes[0].oif_index, link.attrs().index);
+        match link.kind() {
+            Kind::Vxlan {
+                vxlan_id,
+                vtep_dev_index,
+                port,
+                learning,
+                ..
+            } => {
+                assert_eq!(*vxlan_id, 100);
+                assert_eq!(*vtep_dev_index, Some(foo.attrs().index));
+                assert_eq!(*port, Some(4789));
+                assert_eq!(*learning, Some(false));
+            }
+            _ => panic!("wrong link type"),
+        }
+    }
+
+    #[test]
+    fn test_link_alias() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let attrs = LinkAttrs {
+            alias: Some("uplink-to-core".to_string()),
+            ..LinkAttrs::new("foo")
+        };
+        handle
+            .link_new(
+                &Kind::Ifb(attrs.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let mut link = handle.link_get(&attrs).unwrap();
+        assert_eq!(link.attrs().alias.as_deref(), Some("uplink-to-core"));
+
+        link.attrs_mut().alias = Some("uplink".to_string());
+        handle.link_new(&link, libc::NLM_F_ACK).unwrap();
         assert_eq!(
-            routes[0].dst.unwrap().network(),
-            route.dst.unwrap().network()
+            handle.link_get(&attrs).unwrap().attrs().alias.as_deref(),
+            Some("uplink")
         );
 
-        handle.route_handle(RtCmd::Del, &route).unwrap();
+        link.attrs_mut().alias = Some(String::new());
+        handle.link_new(&link, libc::NLM_F_ACK).unwrap();
+        assert_eq!(handle.link_get(&attrs).unwrap().attrs().alias, None);
 
-        let res = handle.route_get(&route.dst.unwrap().addr()).err();
+        // A link whose alias can't be set is removed again
+        let attrs = LinkAttrs {
+            alias: Some("a".repeat(256)),
+            ..LinkAttrs::new("bar")
+        };
+        assert!(handle
+            .link_new(
+                &Kind::Ifb(attrs.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .is_err());
+        assert!(handle.link_get(&attrs).is_err());
+    }
+
+    #[test]
+    fn test_link_macvlan() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+
+        let link = Kind::Macvlan {
+            attrs: LinkAttrs {
+                parent_index: foo.attrs().index,
+                ..LinkAttrs::new("baz")
+            },
+            mode: MacvlanMode::Bridge,
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(link.attrs()).unwrap();
+        assert_eq!(link.link_type(), "macvlan");
+        assert_eq!(link.attrs().parent_index, foo.attrs().index);
+
+        match link.kind() {
+            Kind::Macvlan { mode, .. } => assert_eq!(*mode, MacvlanMode::Bridge),
+            _ => panic!("wrong link type"),
+        }
+    }
+
+    #[test]
+    fn test_link_set_master_bond() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+
+        let bond = Kind::Bond {
+            attrs: LinkAttrs::new("bond0"),
+            mode: BondMode::ActiveBackup,
+            miimon: Some(100),
+        };
+        handle.link_new(&bond, flags).unwrap();
+        let bond = handle.link_get(bond.attrs()).unwrap();
+
+        match bond.kind() {
+            Kind::Bond { mode, miimon, .. } => {
+                assert_eq!(*mode, BondMode::ActiveBackup);
+                assert_eq!(*miimon, Some(100));
+            }
+            _ => panic!("wrong link type"),
+        }
+
+        for name in ["foo", "bar"] {
+            let dummy = Kind::Dummy(LinkAttrs::new(name));
+            handle.link_new(&dummy, flags).unwrap();
+            // Bonds need the slave to be down, which is handled by link_set_master
+            handle.link_setup(dummy.attrs()).unwrap();
+            handle.link_set_master(dummy.attrs(), bond.attrs()).unwrap();
+        }
+
+        for name in ["foo", "bar"] {
+            let link = handle.link_get(&LinkAttrs::new(name)).unwrap();
+            assert_eq!(link.attrs().master_index, bond.attrs().index);
+            assert!(matches!(link.attrs().slave, Some(SlaveInfo::Bond { .. })));
+        }
+
+        let mut slaves = handle
+            .link_list(bond.attrs().index)
+            .unwrap()
+            .into_iter()
+            .map(|link| link.attrs().name.clone())
+            .collect::<Vec<_>>();
+        slaves.sort();
+
+        assert_eq!(slaves, vec!["bar", "foo"]);
+    }
+
+    #[test]
+    fn test_link_list_by_master() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let br = Kind::Bridge {
+            attrs: LinkAttrs::new("br0"),
+            hello_time: None,
+            ageing_time: None,
+            multicast_snooping: None,
+            vlan_filtering: None,
+            stp_state: None,
+            priority: None,
+        };
+        handle
+            .link_new(&br, libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK)
+            .unwrap();
+        let br = handle.link_get(br.attrs()).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        handle.link_set_master(foo.attrs(), br.attrs()).unwrap();
+
+        let links = handle.link_list(0).unwrap();
+        assert!(links.len() >= 4);
+
+        let slaves = handle.link_list(br.attrs().index).unwrap();
+        assert_eq!(slaves.len(), 1);
+        assert_eq!(slaves[0].attrs().name, "foo");
+
+        handle.set_strict_check(true).unwrap();
+
+        let slaves = handle.link_list(br.attrs().index).unwrap();
+        assert_eq!(slaves.len(), 1);
+        assert_eq!(slaves[0].attrs().name, "foo");
+    }
+
+    #[test]
+    fn test_link_new_with_index() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let mut attr = LinkAttrs::new("foo");
+        attr.index = 1000;
+
+        let link = Kind::Bridge {
+            attrs: attr.clone(),
+            hello_time: None,
+            ageing_time: None,
+            multicast_snooping: None,
+            vlan_filtering: None,
+            stp_state: None,
+            priority: None,
+        };
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+
+        handle.link_new(&link, flags).unwrap();
+
+        let link = handle.link_get(&LinkAttrs::new("foo")).unwrap();
+        assert_eq!(link.attrs().index, 1000);
+
+        // The index is already taken
+        let mut attr = LinkAttrs::new("bar");
+        attr.index = 1000;
+
+        let link = Kind::Bridge {
+            attrs: attr,
+            hello_time: None,
+            ageing_time: None,
+            multicast_snooping: None,
+            vlan_filtering: None,
+            stp_state: None,
+            priority: None,
+        };
+
+        assert!(handle.link_new(&link, flags).is_err());
+    }
+
+    #[test]
+    fn test_link_bridge_port_state() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let attr = LinkAttrs::new("br");
+        let link = Kind::Bridge {
+            attrs: attr.clone(),
+            hello_time: None,
+            ageing_time: None,
+            multicast_snooping: None,
+            vlan_filtering: None,
+            stp_state: None,
+            priority: None,
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let br = handle.link_get(&attr).unwrap();
+
+        let mut attr = LinkAttrs::new("foo");
+        attr.master_index = br.attrs().index;
+
+        let link = Kind::Veth {
+            attrs: attr.clone(),
+            peer_name: "bar".to_string(),
+            peer_hw_addr: None,
+            peer_ns: None,
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let port = handle.link_get(&attr).unwrap();
+        assert!(br.attrs().slave.is_none());
+        assert!(matches!(
+            port.attrs().slave,
+            Some(SlaveInfo::Bridge {
+                state: BridgePortState::Disabled,
+                ..
+            })
+        ));
+
+        handle.link_setup(br.attrs()).unwrap();
+        handle.link_setup(port.attrs()).unwrap();
+        handle.link_setup(&LinkAttrs::new("bar")).unwrap();
+
+        // Without STP, a port with carrier goes straight to forwarding
+        let port = handle.link_get(&attr).unwrap();
+        assert!(matches!(
+            port.attrs().slave,
+            Some(SlaveInfo::Bridge {
+                state: BridgePortState::Forwarding,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_link_veth_hw_addr() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let attr = LinkAttrs {
+            mtu: 1400,
+            tx_queue_len: 100,
+            hw_addr: vec![0x02, 0x00, 0x00, 0x00, 0x00, 0x01],
+            ..LinkAttrs::new("foo")
+        };
+        let link = Kind::Veth {
+            attrs: attr.clone(),
+            peer_name: "bar".to_string(),
+            peer_hw_addr: Some(vec![0x02, 0x00, 0x00, 0x00, 0x00, 0x02]),
+            peer_ns: None,
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().hw_addr, attr.hw_addr);
+        assert_eq!(link.attrs().mtu, 1400);
+        assert_eq!(link.attrs().tx_queue_len, 100);
+
+        let peer = handle.link_get(&LinkAttrs::new("bar")).unwrap();
+        assert_eq!(peer.attrs().hw_addr, [0x02, 0x00, 0x00, 0x00, 0x00, 0x02]);
+
+        // Modifying a link read back leaves its address alone
+        let mut lo = handle.link_get(&LinkAttrs::new("lo")).unwrap();
+        lo.attrs_mut().mtu = 1500;
+        handle.link_new(&lo, libc::NLM_F_ACK).unwrap();
+    }
+
+    #[test]
+    fn test_link_veth() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let attr = LinkAttrs::new("br");
+        let link = Kind::Bridge {
+            attrs: attr.clone(),
+            hello_time: None,
+            ageing_time: Some(30102),
+            multicast_snooping: None,
+            vlan_filtering: Some(true),
+            stp_state: None,
+            priority: None,
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        let master_index = link.attrs().index;
+
+        let mut attr = LinkAttrs::new("foo");
+        attr.mtu = 1400;
+        attr.tx_queue_len = 100;
+        attr.num_tx_queues = 4;
+        attr.num_rx_queues = 8;
+        attr.master_index = master_index;
+
+        // TODO: need to set peer ns
+        let link = Kind::Veth {
+            attrs: attr.clone(),
+            peer_name: "bar".to_string(),
+            peer_hw_addr: None,
+            peer_ns: None,
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        let peer = handle
+            .link_get(&LinkAttrs {
+                name: "bar".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(link.attrs().link_type, "veth");
+        assert_eq!(link.attrs().name, "foo");
+        assert_eq!(link.attrs().mtu, 1400);
+        assert_eq!(link.attrs().tx_queue_len, 100);
+        assert_eq!(link.attrs().num_tx_queues, 4);
+        assert_eq!(link.attrs().num_rx_queues, 8);
+
+        assert_eq!(peer.attrs().link_type, "veth");
+        assert_eq!(peer.attrs().name, "bar");
+        assert_eq!(peer.attrs().mtu, 1400);
+        assert_eq!(peer.attrs().tx_queue_len, 100);
+        assert_eq!(peer.attrs().num_tx_queues, 4);
+        assert_eq!(peer.attrs().num_rx_queues, 8);
+
+        handle.link_del(peer.attrs()).unwrap();
+
+        let res = handle.link_get(&attr).err();
         assert!(res.is_some());
     }
+
+    #[test]
+    fn test_link_new_verify() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let link = Kind::Veth {
+            attrs: LinkAttrs::new("foo"),
+            peer_name: "bar".to_string(),
+            peer_hw_addr: None,
+            peer_ns: None,
+        };
+
+        let created = handle
+            .link_new_verify(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        assert_eq!(created.link_type(), "veth");
+        assert_eq!(created.attrs().name, "foo");
+    }
+
+    #[test]
+    fn test_kind_mismatch_error() {
+        let err: anyhow::Error = Error::KindMismatch {
+            expected: "vrf".to_string(),
+            actual: "device".to_string(),
+        }
+        .into();
+
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::KindMismatch {
+                expected: "vrf".to_string(),
+                actual: "device".to_string(),
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            "link kind mismatch: expected vrf, got device"
+        );
+    }
+
+    #[test]
+    fn test_link_change_flags_and_mtu() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let link = Kind::Veth {
+            attrs: LinkAttrs::new("foo"),
+            peer_name: "bar".to_string(),
+            peer_hw_addr: None,
+            peer_ns: None,
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(link.attrs()).unwrap();
+        assert_eq!(link.attrs().raw_flags & libc::IFF_UP as u32, 0);
+
+        let mut req =
+            link::link_change_flags(link.attrs().index, libc::IFF_UP as u32, libc::IFF_UP as u32)
+                .unwrap();
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_MTU,
+            1400u32.to_ne_bytes().to_vec(),
+        )));
+
+        handle.execute(&mut req, 0).unwrap();
+
+        let link = handle.link_get(link.attrs()).unwrap();
+        assert_eq!(link.attrs().mtu, 1400);
+        assert_ne!(link.attrs().raw_flags & libc::IFF_UP as u32, 0);
+    }
+
+    #[test]
+    fn test_link_get() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let attr = link::LinkAttrs::new("lo");
+
+        let link = handle.link_get(&attr).unwrap();
+
+        assert_eq!(link.attrs().index, 1);
+        assert_eq!(link.attrs().name, "lo");
+
+        // The qdisc of a link that is down is noop until it is brought up
+        assert_eq!(link.attrs().qdisc.as_deref(), Some("noop"));
+        handle.link_setup(link.attrs()).unwrap();
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().qdisc.as_deref(), Some("noqueue"));
+    }
+
+    #[test]
+    fn test_link_statistics() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let attr = link::LinkAttrs::new("lo");
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().statistics.unwrap().tx_packets, 0);
+
+        handle.link_setup(&attr).unwrap();
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .send_to(b"foo", socket.local_addr().unwrap())
+            .unwrap();
+
+        let stats = handle.link_get(&attr).unwrap().attrs().statistics.unwrap();
+        assert_eq!(stats.tx_packets, 1);
+        assert_eq!(stats.rx_packets, 1);
+        assert!(stats.tx_bytes > 3);
+    }
+
+    #[test]
+    fn test_rule_add_del() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let rule = Rule {
+            priority: Some(100),
+            table: 100,
+            mark: Some(0x1),
+            ..Default::default()
+        };
+        handle.rule_handle(RuleCmd::Add, &rule).unwrap();
+
+        let rules = handle.rule_list(addr::AddrFamily::V4).unwrap();
+        let found = rules.iter().find(|r| r.priority == Some(100)).unwrap();
+        assert_eq!(found.table, 100);
+        assert_eq!(found.mark, Some(0x1));
+        assert_eq!(found.family, libc::AF_INET as u8);
+
+        // Not in the IPv6 rules
+        let rules = handle.rule_list(addr::AddrFamily::V6).unwrap();
+        assert!(rules.iter().all(|r| r.priority != Some(100)));
+
+        // A table beyond the 8-bit header field
+        let large = Rule {
+            priority: Some(200),
+            table: 1000,
+            src: Some("10.0.0.0/8".parse().unwrap()),
+            ..Default::default()
+        };
+        handle.rule_handle(RuleCmd::Add, &large).unwrap();
+
+        let rules = handle.rule_list(addr::AddrFamily::V4).unwrap();
+        let found = rules.iter().find(|r| r.priority == Some(200)).unwrap();
+        assert_eq!(found.table, 1000);
+        assert_eq!(found.src, large.src);
+
+        handle.rule_handle(RuleCmd::Del, &rule).unwrap();
+        handle.rule_handle(RuleCmd::Del, &large).unwrap();
+
+        let rules = handle.rule_list(addr::AddrFamily::V4).unwrap();
+        assert_eq!(rules.len(), 3);
+        assert!(handle.rule_handle(RuleCmd::Del, &rule).is_err());
+    }
+
+    #[test]
+    fn test_route_get_many() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        handle.link_setup(&LinkAttrs::new("lo")).unwrap();
+
+        let mut dsts = (1..=100)
+            .map(|i| IpAddr::from([127, 0, 0, i]))
+            .collect::<Vec<_>>();
+        // Nothing routes to this one without a default route
+        dsts.insert(50, "192.0.2.1".parse().unwrap());
+
+        let res = handle.route_get_many(&dsts).unwrap();
+        assert_eq!(res.len(), dsts.len());
+
+        for (i, (dst, routes)) in res.iter().enumerate() {
+            assert_eq!(*dst, dsts[i]);
+
+            if i == 50 {
+                let err = routes.as_ref().unwrap_err();
+                assert_eq!(crate::error::errno(err), Some(libc::ENETUNREACH));
+            } else {
+                let routes = routes.as_ref().unwrap();
+                assert_eq!(routes.len(), 1);
+                assert_eq!(routes[0].dst.unwrap().addr(), *dst);
+            }
+        }
+
+        assert!(handle.route_get_many(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_link_del_force() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let attrs = LinkAttrs::new("foo");
+        handle
+            .link_new(
+                &Kind::Ifb(attrs.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+        handle.link_setup(&attrs).unwrap();
+        let link = handle.link_get(&attrs).unwrap();
+        assert_ne!(link.attrs().flags & libc::IFF_UP as u32, 0);
+
+        handle.link_setdown(&attrs).unwrap();
+        let link = handle.link_get(&attrs).unwrap();
+        assert_eq!(link.attrs().flags & libc::IFF_UP as u32, 0);
+
+        handle.link_setup(&attrs).unwrap();
+        handle.link_del_force(&attrs).unwrap();
+        assert!(handle.link_get(&attrs).is_err());
+
+        assert!(handle.link_del_force(&attrs).is_err());
+    }
+
+    #[test]
+    fn test_link_set_mtu() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        let mtu = foo.attrs().mtu;
+
+        let old = handle
+            .link_set_mtu_returning_old(foo.attrs(), 1400)
+            .unwrap();
+        assert_eq!(old, mtu);
+        assert_eq!(handle.link_get(foo.attrs()).unwrap().attrs().mtu, 1400);
+
+        // Rolling back
+        let old = handle.link_set_mtu_returning_old(foo.attrs(), old).unwrap();
+        assert_eq!(old, 1400);
+        assert_eq!(handle.link_get(foo.attrs()).unwrap().attrs().mtu, mtu);
+    }
+
+    #[test]
+    fn test_link_set_txqlen() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        let qlen = foo.attrs().tx_queue_len as u32;
+
+        let old = handle
+            .link_set_txqlen_returning_old(foo.attrs(), 42)
+            .unwrap();
+        assert_eq!(old, qlen);
+
+        handle.link_set_txqlen(foo.attrs(), 100).unwrap();
+        let foo = handle.link_get(foo.attrs()).unwrap();
+        assert_eq!(foo.attrs().tx_queue_len, 100);
+    }
+
+    #[test]
+    fn test_link_set_name() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let attrs = LinkAttrs::new("foo");
+        handle
+            .link_new(
+                &Kind::Ifb(attrs.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+        let index = handle.link_get(&attrs).unwrap().attrs().index;
+
+        handle.link_set_name(&attrs, "baz").unwrap();
+
+        let link = handle.link_get(&LinkAttrs::new("baz")).unwrap();
+        assert_eq!(link.attrs().index, index);
+        assert!(handle.link_get(&attrs).is_err());
+
+        assert!(handle.link_set_name(link.attrs(), "").is_err());
+        assert!(handle.link_set_name(link.attrs(), &"a".repeat(16)).is_err());
+    }
+
+    #[test]
+    fn test_link_up_down() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        assert_eq!(foo.attrs().flags & libc::IFF_UP as u32, 0);
+
+        handle.link_setup(foo.attrs()).unwrap();
+        let foo = handle.link_get(foo.attrs()).unwrap();
+        assert_ne!(foo.attrs().flags & libc::IFF_UP as u32, 0);
+
+        handle.link_down(foo.attrs()).unwrap();
+        let foo = handle.link_get(foo.attrs()).unwrap();
+        assert_eq!(foo.attrs().flags & libc::IFF_UP as u32, 0);
+    }
+
+    #[test]
+    fn test_link_set_hw_addr() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let attrs = LinkAttrs::new("foo");
+        handle.veth_add_pair(&attrs, "bar").unwrap();
+
+        let hw_addr = [0x02, 0x42, 0xac, 0x11, 0x00, 0x02];
+        handle.link_set_hw_addr(&attrs, &hw_addr).unwrap();
+        assert_eq!(handle.link_get(&attrs).unwrap().attrs().hw_addr, hw_addr);
+
+        let err = handle.link_set_hw_addr(&attrs, &hw_addr[..4]).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::InvalidHwAddr {
+                expected: 6,
+                actual: 4
+            })
+        );
+        let err = handle.link_set_hw_addr(&attrs, &[]).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::InvalidHwAddr {
+                expected: 6,
+                actual: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_link_set_ns() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let attrs = LinkAttrs::new("foo");
+        handle.veth_add_pair(&attrs, "bar").unwrap();
+        let index = handle.link_get(&attrs).unwrap().attrs().index;
+
+        // Moving a link into its current namespace keeps it as it is. Each test
+        // thread has its own namespace, so it is looked up by thread id.
+        let ns = std::fs::File::open("/proc/thread-self/ns/net").unwrap();
+        handle.link_set_ns_fd(&attrs, ns.as_raw_fd()).unwrap();
+        assert_eq!(handle.link_get(&attrs).unwrap().attrs().index, index);
+
+        handle
+            .link_set_ns_pid(&attrs, nix::unistd::gettid().as_raw())
+            .unwrap();
+        assert_eq!(handle.link_get(&attrs).unwrap().attrs().index, index);
+
+        assert!(handle.link_set_ns_fd(&attrs, -1).is_err());
+    }
+
+    #[test]
+    fn test_link_set_alias() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let attr = link::LinkAttrs::new("lo");
+
+        handle.link_set_alias(&attr, "loopback").unwrap();
+        assert_eq!(
+            handle.link_get(&attr).unwrap().attrs().alias.as_deref(),
+            Some("loopback")
+        );
+
+        // A zero-length IFLA_IFALIAS removes the alias, the kernel then omits the attribute
+        handle.link_set_alias(&attr, "").unwrap();
+        assert_eq!(handle.link_get(&attr).unwrap().attrs().alias, None);
+
+        let alias = "a".repeat(256);
+        assert!(handle.link_set_alias(&attr, &alias).is_err());
+    }
+
+    #[test]
+    fn test_link_set_gso_max_segs() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let attr = link::LinkAttrs::new("lo");
+
+        let link = handle.link_get(&attr).unwrap();
+        let max = link.attrs().tso_max_segs;
+        assert_ne!(max, 0);
+
+        handle.link_set_gso_max_segs(link.attrs(), max / 2).unwrap();
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().gso_max_segs, max / 2);
+
+        let err = handle
+            .link_set_gso_max_segs(link.attrs(), max + 1)
+            .unwrap_err();
+        assert!(err.to_string().contains("tso_max_segs"));
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::OutOfRange {
+                attr: "gso_max_segs".to_string(),
+                value: max + 1,
+                limit: "tso_max_segs".to_string(),
+                max,
+            })
+        );
+    }
+
+    #[test]
+    fn test_link_set_ipv4_max_size() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        assert_ne!(foo.attrs().gso_ipv4_max_size, 0);
+        assert_ne!(foo.attrs().gro_ipv4_max_size, 0);
+
+        handle
+            .link_set_gso_ipv4_max_size(foo.attrs(), 32768)
+            .unwrap();
+        handle
+            .link_set_gro_ipv4_max_size(foo.attrs(), 32768)
+            .unwrap();
+
+        let foo = handle.link_get(foo.attrs()).unwrap();
+        assert_eq!(foo.attrs().gso_ipv4_max_size, 32768);
+        assert_eq!(foo.attrs().gro_ipv4_max_size, 32768);
+
+        let max = foo.attrs().tso_max_size;
+        let err = handle
+            .link_set_gso_ipv4_max_size(foo.attrs(), max + 1)
+            .unwrap_err();
+        assert!(err.to_string().contains("tso_max_size"));
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::OutOfRange { max: m, .. }) if *m == max
+        ));
+    }
+
+    #[test]
+    fn test_addr_handle() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let attr = link::LinkAttrs::new("lo");
+
+        let link = handle.link_get(&attr).unwrap();
+
+        let address = "127.0.0.2/24".parse().unwrap();
+        let addr = addr::Address {
+            address,
+            ..Default::default()
+        };
+
+        handle
+            .addr_handle(addr::AddrCmd::Add, link.attrs(), &addr)
+            .unwrap();
+
+        let addrs = handle
+            .addr_list(link.attrs(), addr::AddrFamily::All)
+            .unwrap();
+
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].address, address);
+    }
+
+    #[test]
+    fn test_route_handle() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let attr = link::LinkAttrs::new("lo");
+
+        let link = handle.link_get(&attr).unwrap();
+
+        handle.link_setup(link.attrs()).unwrap();
+
+        let route = Route {
+            oif_index: link.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            src: Some("127.0.0.2".parse().unwrap()),
+            ..Default::default()
+        };
+
+        handle.route_handle(RtCmd::Add, &route).unwrap();
+
+        let routes = handle.route_get(&route.dst.unwrap().addr()).unwrap();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].oif_index, link.attrs().index);
+        assert_eq!(
+            routes[0].dst.unwrap().network(),
+            route.dst.unwrap().network()
+        );
+
+        handle.route_handle(RtCmd::Del, &route).unwrap();
+
+        let res = handle.route_get(&route.dst.unwrap().addr()).err();
+        assert!(res.is_some());
+    }
+
+    #[test]
+    fn test_route_tos() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let link = handle.link_get(&LinkAttrs::new("lo")).unwrap();
+        handle.link_setup(link.attrs()).unwrap();
+
+        let mut route = Route {
+            oif_index: link.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            ..Default::default()
+        };
+        handle.route_handle(RtCmd::Add, &route).unwrap();
+
+        route.tos = 0x10;
+        handle.route_handle(RtCmd::Add, &route).unwrap();
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, link.attrs().index, RtFilter::Oif)
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.dst == route.dst)
+            .collect::<Vec<_>>();
+
+        assert_eq!(routes.len(), 2);
+        assert!(routes.iter().any(|r| r.tos == 0));
+        assert!(routes.iter().any(|r| r.tos == 0x10));
+
+        handle.route_handle(RtCmd::Del, &route).unwrap();
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, link.attrs().index, RtFilter::Oif)
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.dst == route.dst)
+            .collect::<Vec<_>>();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].tos, 0);
+    }
+
+    #[test]
+    fn test_route_table() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        handle.link_setup(foo.attrs()).unwrap();
+
+        let route = Route {
+            oif_index: foo.attrs().index,
+            dst: Some("10.1.0.0/24".parse().unwrap()),
+            table: 10,
+            ..Default::default()
+        };
+        handle.route_handle(RtCmd::Add, &route).unwrap();
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, foo.attrs().index, RtFilter::Oif)
+            .unwrap();
+        let routes: Vec<_> = routes.iter().filter(|r| r.dst == route.dst).collect();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].table, 10);
+
+        handle.route_handle(RtCmd::Del, &route).unwrap();
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, foo.attrs().index, RtFilter::Oif)
+            .unwrap();
+        assert!(!routes.iter().any(|r| r.dst == route.dst));
+    }
+
+    #[test]
+    fn test_route_table_id() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        handle.link_setup(foo.attrs()).unwrap();
+
+        let route = Route {
+            oif_index: foo.attrs().index,
+            dst: Some("10.1.0.0/24".parse().unwrap()),
+            table_id: Some(10000),
+            ..Default::default()
+        };
+        handle.route_handle(RtCmd::Add, &route).unwrap();
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, foo.attrs().index, RtFilter::Oif)
+            .unwrap();
+        let routes: Vec<_> = routes.iter().filter(|r| r.dst == route.dst).collect();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].table_id, Some(10000));
+
+        handle.route_handle(RtCmd::Del, routes[0]).unwrap();
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, foo.attrs().index, RtFilter::Oif)
+            .unwrap();
+        assert!(!routes.iter().any(|r| r.dst == route.dst));
+    }
+
+    #[test]
+    fn test_route_prepend_table() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        handle.link_setup(foo.attrs()).unwrap();
+
+        // Tables above 255 share the same id in the header
+        for (table_id, priority) in [(10000, 100), (10001, 50)] {
+            let route = Route {
+                oif_index: foo.attrs().index,
+                dst: Some("10.1.0.0/24".parse().unwrap()),
+                table_id: Some(table_id),
+                priority: Some(priority),
+                ..Default::default()
+            };
+            handle.route_handle(RtCmd::Add, &route).unwrap();
+        }
+
+        let route = Route {
+            oif_index: foo.attrs().index,
+            dst: Some("10.1.0.0/24".parse().unwrap()),
+            table_id: Some(10000),
+            ..Default::default()
+        };
+        assert_eq!(handle.route_prepend(&route).unwrap(), 99);
+
+        let route = Route {
+            table_id: Some(10002),
+            priority: Some(10),
+            ..route
+        };
+        assert_eq!(handle.route_prepend(&route).unwrap(), 10);
+    }
+
+    #[test]
+    fn test_route_list_table() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        handle.link_setup(foo.attrs()).unwrap();
+
+        for i in 0..16 {
+            let route = Route {
+                oif_index: foo.attrs().index,
+                dst: Some(format!("10.1.{i}.0/24").parse().unwrap()),
+                table_id: Some(if i == 0 { 10000 } else { 100 }),
+                ..Default::default()
+            };
+            handle.route_handle(RtCmd::Add, &route).unwrap();
+        }
+
+        for strict in [false, true] {
+            handle.set_strict_check(strict).unwrap();
+
+            let routes = handle
+                .route_list(addr::AddrFamily::V4, 0, RtFilter::Table(10000))
+                .unwrap();
+            assert_eq!(routes.len(), 1);
+            assert_eq!(routes[0].dst, Some("10.1.0.0/24".parse().unwrap()));
+
+            let routes = handle
+                .route_list(
+                    addr::AddrFamily::V4,
+                    foo.attrs().index,
+                    RtFilter::OifTable(100),
+                )
+                .unwrap();
+            assert_eq!(routes.len(), 15);
+            assert!(routes.iter().all(|r| r.table == 100));
+
+            let routes = handle
+                .route_list(addr::AddrFamily::V4, 0, RtFilter::Table(4242))
+                .unwrap();
+            assert!(routes.is_empty());
+        }
+
+        // With strict checking, the kernel only sends the routes of the table
+        let mut req = route::route_handle(
+            RtCmd::Show,
+            &Route {
+                family: libc::AF_INET as u8,
+                table_id: Some(10000),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let msgs = handle.request(&mut req, 0).unwrap();
+        assert_eq!(msgs.len(), 1);
+        assert!(msgs[0].is_dump_filtered());
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, 0, RtFilter::None)
+            .unwrap();
+        assert_eq!(routes.len(), 16);
+
+        // Without filter, the index is not sent and the routes of every link are listed
+        let (baz, _) = handle.veth_add_pair(&LinkAttrs::new("baz"), "qux").unwrap();
+        handle.link_setup(baz.attrs()).unwrap();
+        let route = Route {
+            oif_index: baz.attrs().index,
+            dst: Some("10.2.0.0/24".parse().unwrap()),
+            ..Default::default()
+        };
+        handle.route_handle(RtCmd::Add, &route).unwrap();
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, foo.attrs().index, RtFilter::None)
+            .unwrap();
+        assert_eq!(routes.len(), 17);
+        assert!(routes.iter().any(|r| r.oif_index == baz.attrs().index));
+    }
+
+    #[test]
+    fn test_route_multipath() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let mut hops = vec![];
+        for (name, peer, addr, gw) in [
+            ("foo", "bar", "10.0.0.1/24", "10.0.0.2"),
+            ("baz", "qux", "10.0.1.1/24", "10.0.1.2"),
+        ] {
+            let (link, _) = handle.veth_add_pair(&LinkAttrs::new(name), peer).unwrap();
+            handle.link_setup(link.attrs()).unwrap();
+            handle
+                .addr_handle(
+                    addr::AddrCmd::Add,
+                    link.attrs(),
+                    &addr::Address::new(addr.parse().unwrap()),
+                )
+                .unwrap();
+
+            hops.push(NextHop {
+                gw: Some(gw.parse().unwrap()),
+                oif_index: link.attrs().index,
+                weight: hops.len() as u8 + 1,
+            });
+        }
+
+        let route = Route {
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            multipath: hops,
+            ..Default::default()
+        };
+        handle.route_handle(RtCmd::Add, &route).unwrap();
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, 0, RtFilter::None)
+            .unwrap();
+        let routes: Vec<_> = routes.iter().filter(|r| r.dst == route.dst).collect();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].multipath, route.multipath);
+    }
+
+    #[test]
+    fn test_route_list_local_table() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let link = handle.link_get(&LinkAttrs::new("lo")).unwrap();
+        handle.link_setup(link.attrs()).unwrap();
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, link.attrs().index, RtFilter::Oif)
+            .unwrap();
+
+        let local = routes
+            .iter()
+            .filter(|r| r.table == libc::RT_TABLE_LOCAL)
+            .collect::<Vec<_>>();
+
+        assert!(local.iter().any(|r| r.route_type() == RouteType::Local
+            && r.dst == Some("127.0.0.1/32".parse().unwrap())));
+        assert!(local.iter().any(|r| r.route_type() == RouteType::Broadcast));
+    }
+
+    #[test]
+    fn test_addr_host_no_broadcast() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        handle.link_setup(foo.attrs()).unwrap();
+
+        let addr = addr::Address::new("10.0.0.5/32".parse().unwrap());
+        handle
+            .addr_handle(addr::AddrCmd::Add, foo.attrs(), &addr)
+            .unwrap();
+
+        let addr = addr::Address {
+            no_broadcast: true,
+            ..addr::Address::new("10.1.0.1/24".parse().unwrap())
+        };
+        handle
+            .addr_handle(addr::AddrCmd::Add, foo.attrs(), &addr)
+            .unwrap();
+
+        let addrs = handle.addr_list(foo.attrs(), addr::AddrFamily::V4).unwrap();
+        assert_eq!(addrs.len(), 2);
+        assert!(addrs.iter().all(|a| a.broadcast.is_none()));
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, foo.attrs().index, RtFilter::Oif)
+            .unwrap();
+        // A broadcast of the /32 would have been the address itself
+        let host = Some("10.0.0.5/32".parse().unwrap());
+        assert!(routes
+            .iter()
+            .any(|r| r.dst == host && r.route_type() == RouteType::Local));
+        assert!(!routes
+            .iter()
+            .any(|r| r.dst == host && r.route_type() == RouteType::Broadcast));
+    }
+
+    #[test]
+    fn test_dump_strict_check() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let lo = handle.link_get(&LinkAttrs::new("lo")).unwrap();
+        handle.link_setup(lo.attrs()).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        let addr = addr::Address::new("10.0.0.1/24".parse().unwrap());
+        handle
+            .addr_handle(addr::AddrCmd::Add, foo.attrs(), &addr)
+            .unwrap();
+
+        // Filtered by the kernel without enabling strict checking first
+        let mut req = addr::addr_list(addr::AddrFamily::V4, lo.attrs().index).unwrap();
+        let msgs = handle.dump(&mut req, libc::RTM_NEWADDR).unwrap();
+        assert_eq!(msgs.len(), 1);
+        assert!(msgs[0].is_dump_filtered());
+
+        // Only for the dump
+        assert!(!handle.strict_check);
+        let mut req = addr::addr_list(addr::AddrFamily::V4, lo.attrs().index).unwrap();
+        let msgs = handle.request(&mut req, libc::RTM_NEWADDR).unwrap();
+        assert_eq!(msgs.len(), 2);
+        assert!(!msgs.iter().any(|m| m.is_dump_filtered()));
+    }
+
+    #[test]
+    fn test_strict_check_dump_filter() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        handle.set_strict_check(true).unwrap();
+
+        let lo = handle.link_get(&LinkAttrs::new("lo")).unwrap();
+        handle.link_setup(lo.attrs()).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        let addr = addr::Address::new("10.0.0.1/24".parse().unwrap());
+        handle
+            .addr_handle(addr::AddrCmd::Add, foo.attrs(), &addr)
+            .unwrap();
+
+        let mut req = addr::addr_list(addr::AddrFamily::V4, lo.attrs().index).unwrap();
+        let msgs = handle.request(&mut req, libc::RTM_NEWADDR).unwrap();
+        assert!(!msgs.is_empty());
+        assert!(msgs.iter().all(|m| m.is_dump_filtered()));
+
+        let addrs = handle.addr_list(lo.attrs(), addr::AddrFamily::V4).unwrap();
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].index, lo.attrs().index);
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, lo.attrs().index, RtFilter::Oif)
+            .unwrap();
+        assert!(!routes.is_empty());
+        assert!(routes.iter().all(|r| r.oif_index == lo.attrs().index));
+    }
+
+    #[test]
+    fn test_addr_list_exact() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        // Bringing lo up makes the kernel add 127.0.0.1/8 and ::1
+        let lo = handle.link_get(&LinkAttrs::new("lo")).unwrap();
+        handle.link_setup(lo.attrs()).unwrap();
+
+        let address = addr::Address::new("127.0.0.2/32".parse().unwrap());
+        handle
+            .addr_handle(addr::AddrCmd::Add, lo.attrs(), &address)
+            .unwrap();
+
+        let addrs = handle.addr_list(lo.attrs(), addr::AddrFamily::All).unwrap();
+        assert_eq!(addrs.len(), 3);
+
+        let addrs = handle
+            .addr_list_exact(
+                &LinkAttrs::new("lo"),
+                addr::AddrFamily::All,
+                Some(libc::RT_SCOPE_UNIVERSE),
+            )
+            .unwrap();
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].address, address.address);
+
+        let addrs = handle
+            .addr_list_exact(lo.attrs(), addr::AddrFamily::V4, None)
+            .unwrap();
+        assert_eq!(addrs.len(), 2);
+    }
+
+    #[test]
+    fn test_addr_list_filtered() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+
+        for (address, scope) in [
+            ("10.0.0.1/24", libc::RT_SCOPE_HOST),
+            ("10.0.1.1/24", libc::RT_SCOPE_UNIVERSE),
+        ] {
+            let address = addr::Address {
+                scope,
+                ..addr::Address::new(address.parse().unwrap())
+            };
+            handle
+                .addr_handle(addr::AddrCmd::Add, foo.attrs(), &address)
+                .unwrap();
+        }
+
+        let filter = addr::AddrFilter {
+            scope: Some(libc::RT_SCOPE_UNIVERSE),
+            ..Default::default()
+        };
+        let addrs = handle
+            .addr_list_filtered(foo.attrs(), addr::AddrFamily::V4, &filter)
+            .unwrap();
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].address, "10.0.1.1/24".parse().unwrap());
+
+        // Both addresses were added statically
+        let filter = addr::AddrFilter {
+            flags_mask: Some(consts::IFA_F_PERMANENT),
+            ..Default::default()
+        };
+        let addrs = handle
+            .addr_list_filtered(foo.attrs(), addr::AddrFamily::V4, &filter)
+            .unwrap();
+        assert_eq!(addrs.len(), 2);
+    }
+
+    #[test]
+    fn test_genl_family_id() {
+        let mut handle = super::SocketHandle::new(libc::NETLINK_GENERIC).unwrap();
+
+        // The controller itself has a fixed id
+        assert_eq!(
+            handle.genl_family_id("nlctrl").unwrap(),
+            consts::GENL_ID_CTRL
+        );
+
+        let err = handle.genl_family_id("foo").err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_addr_label() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+
+        let address = addr::Address {
+            label: "foo:1".to_string(),
+            ..addr::Address::new("10.0.0.1/24".parse().unwrap())
+        };
+        handle
+            .addr_handle(addr::AddrCmd::Add, foo.attrs(), &address)
+            .unwrap();
+
+        let addrs = handle.addr_list(foo.attrs(), addr::AddrFamily::V4).unwrap();
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].label, "foo:1");
+        assert_eq!(addrs[0].broadcast, Some("10.0.0.255".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_addr_lifetimes() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+
+        let address = addr::Address {
+            preferred_lifetime: 100,
+            valid_lifetime: 300,
+            ..addr::Address::new("10.0.0.1/24".parse().unwrap())
+        };
+        handle
+            .addr_handle(addr::AddrCmd::Add, foo.attrs(), &address)
+            .unwrap();
+
+        let addrs = handle.addr_list(foo.attrs(), addr::AddrFamily::V4).unwrap();
+        assert_eq!(addrs.len(), 1);
+        assert!((99..=100).contains(&addrs[0].preferred_lifetime));
+        assert!((299..=300).contains(&addrs[0].valid_lifetime));
+        assert!(addrs[0].created > 0);
+        assert!(!addrs[0].is_permanent());
+    }
+
+    #[test]
+    fn test_addr_peer_del() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        let addr = addr::Address {
+            peer: Some("10.0.0.2/32".parse().unwrap()),
+            ..addr::Address::new("10.0.0.1/32".parse().unwrap())
+        };
+        handle
+            .addr_handle(addr::AddrCmd::Add, foo.attrs(), &addr)
+            .unwrap();
+
+        // The address is read back with its local address and its peer
+        let addrs = handle.addr_list(foo.attrs(), addr::AddrFamily::V4).unwrap();
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].address, addr.address);
+        assert_eq!(addrs[0].peer, addr.peer);
+
+        handle
+            .addr_handle(addr::AddrCmd::Del, foo.attrs(), &addrs[0])
+            .unwrap();
+
+        let addrs = handle.addr_list(foo.attrs(), addr::AddrFamily::V4).unwrap();
+        assert!(addrs.is_empty());
+
+        // Deleting the address given as added works as well
+        handle
+            .addr_handle(addr::AddrCmd::Add, foo.attrs(), &addr)
+            .unwrap();
+        handle
+            .addr_handle(addr::AddrCmd::Del, foo.attrs(), &addr)
+            .unwrap();
+
+        let addrs = handle.addr_list(foo.attrs(), addr::AddrFamily::V4).unwrap();
+        assert!(addrs.is_empty());
+    }
+
+    #[test]
+    fn test_addr_list_order() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        for addr in ["10.0.0.3/24", "10.0.0.1/24", "10.1.0.1/16", "10.0.0.2/8"] {
+            let addr = addr::Address::new(addr.parse().unwrap());
+            handle
+                .addr_handle(addr::AddrCmd::Add, foo.attrs(), &addr)
+                .unwrap();
+        }
+
+        let addrs = handle.addr_list(foo.attrs(), addr::AddrFamily::V4).unwrap();
+        let ips = addrs.iter().map(|a| a.address).collect::<Vec<_>>();
+        assert_eq!(
+            ips,
+            ["10.0.0.1/24", "10.0.0.2/8", "10.0.0.3/24", "10.1.0.1/16"]
+                .map(|a| a.parse::<ipnet::IpNet>().unwrap())
+        );
+
+        for _ in 0..3 {
+            let again = handle.addr_list(foo.attrs(), addr::AddrFamily::V4).unwrap();
+            assert_eq!(again, addrs);
+        }
+    }
+
+    #[test]
+    fn test_addr_flags() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        let addr = addr::Address {
+            flags: consts::IFA_F_NOPREFIXROUTE,
+            ..addr::Address::new("2001:db8::1/64".parse().unwrap())
+        };
+        handle
+            .addr_handle(addr::AddrCmd::Add, foo.attrs(), &addr)
+            .unwrap();
+
+        let addrs = handle.addr_list(foo.attrs(), addr::AddrFamily::V6).unwrap();
+        let addrs: Vec<_> = addrs.iter().filter(|a| a.address == addr.address).collect();
+
+        assert_eq!(addrs.len(), 1);
+        assert!(addrs[0].is_permanent());
+        assert!(!addrs[0].is_deprecated());
+        assert_ne!(addrs[0].flags & consts::IFA_F_NOPREFIXROUTE, 0);
+    }
+
+    #[test]
+    fn test_addr_ipv6_scope() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+
+        for (address, scope) in [
+            ("fe80::1234/64", libc::RT_SCOPE_LINK),
+            ("2001:db8::1/64", libc::RT_SCOPE_UNIVERSE),
+        ] {
+            let addr = addr::Address::new(address.parse().unwrap());
+            handle
+                .addr_handle(addr::AddrCmd::Add, foo.attrs(), &addr)
+                .unwrap();
+
+            let addrs = handle.addr_list(foo.attrs(), addr::AddrFamily::V6).unwrap();
+            let addr = addrs.iter().find(|a| a.address == addr.address).unwrap();
+            assert_eq!(addr.scope, scope);
+        }
+    }
+
+    #[test]
+    fn test_addr_ensure() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let lo = handle.link_get(&LinkAttrs::new("lo")).unwrap();
+        let addr = addr::Address::new("127.0.0.2/24".parse().unwrap());
+
+        // Missing, so it is added
+        handle.addr_ensure(lo.attrs(), &addr).unwrap();
+        let addrs = handle.addr_list(lo.attrs(), addr::AddrFamily::V4).unwrap();
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].address, addr.address);
+
+        // Identical, so nothing to do
+        handle.addr_ensure(lo.attrs(), &addr).unwrap();
+        let addrs = handle.addr_list(lo.attrs(), addr::AddrFamily::V4).unwrap();
+        assert_eq!(addrs.len(), 1);
+
+        // Same address with a different prefix
+        let conflicting = addr::Address::new("127.0.0.2/16".parse().unwrap());
+        let err = handle.addr_ensure(lo.attrs(), &conflicting).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::AlreadyExists {
+                requested: conflicting.address,
+                existing: addr.address,
+            })
+        );
+    }
+
+    #[test]
+    fn test_route_get_src() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, bar) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        handle.link_setup(foo.attrs()).unwrap();
+        handle.link_setup(bar.attrs()).unwrap();
+
+        let addr = addr::Address::new("10.0.0.1/24".parse().unwrap());
+        handle
+            .addr_handle(addr::AddrCmd::Add, foo.attrs(), &addr)
+            .unwrap();
+
+        let route = Route {
+            oif_index: foo.attrs().index,
+            gw: Some("10.0.0.2".parse().unwrap()),
+            ..Default::default()
+        };
+        handle.route_handle(RtCmd::Add, &route).unwrap();
+
+        let routes = handle.route_get(&"8.8.8.8".parse().unwrap()).unwrap();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].oif_index, foo.attrs().index);
+        assert_eq!(routes[0].gw, route.gw);
+        assert_eq!(routes[0].src, Some("10.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_route_expires() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        handle.link_setup(foo.attrs()).unwrap();
+
+        let route = Route {
+            oif_index: foo.attrs().index,
+            dst: Some("2001:db8::/64".parse().unwrap()),
+            expires: Some(3600),
+            ..Default::default()
+        };
+        handle.route_handle(RtCmd::Add, &route).unwrap();
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V6, foo.attrs().index, RtFilter::Oif)
+            .unwrap();
+        let routes: Vec<_> = routes.iter().filter(|r| r.dst == route.dst).collect();
+
+        assert_eq!(routes.len(), 1);
+        let expires = routes[0].expires.unwrap();
+        assert!(expires > 3500 && expires <= 3600);
+    }
+
+    #[test]
+    fn test_route_list_many() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        handle.link_setup(foo.attrs()).unwrap();
+
+        // Enough routes for the dump to span many datagrams
+        for i in 0..1000u32 {
+            let route = Route {
+                oif_index: foo.attrs().index,
+                dst: Some(format!("10.{}.{}.0/24", i / 256, i % 256).parse().unwrap()),
+                ..Default::default()
+            };
+            handle.route_handle(RtCmd::Add, &route).unwrap();
+        }
+
+        let routes = handle
+            .route_list(addr::AddrFamily::V4, foo.attrs().index, RtFilter::Oif)
+            .unwrap();
+        assert_eq!(routes.len(), 1000);
+    }
+
+    #[test]
+    fn test_route_list_all_families() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        handle.link_setup(foo.attrs()).unwrap();
+
+        let dsts = [
+            "10.1.0.0/24".parse().unwrap(),
+            "2001:db8::/64".parse().unwrap(),
+        ];
+        for dst in dsts {
+            let route = Route {
+                oif_index: foo.attrs().index,
+                dst: Some(dst),
+                ..Default::default()
+            };
+            handle.route_handle(RtCmd::Add, &route).unwrap();
+        }
+
+        // Whether or not strict checking is enabled for the whole socket
+        for strict in [false, true] {
+            handle.set_strict_check(strict).unwrap();
+
+            let routes = handle
+                .route_list(addr::AddrFamily::All, foo.attrs().index, RtFilter::Oif)
+                .unwrap();
+
+            for dst in dsts {
+                assert!(
+                    routes.iter().any(|r| r.dst == Some(dst)),
+                    "{dst} not listed"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_request() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let mut req = NetlinkRequest::new(libc::RTM_GETLINK, libc::NLM_F_ACK);
+        let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        msg.index = 1;
+        req.add_data(msg);
+
+        let msgs = handle.request(&mut req, libc::RTM_NEWLINK).unwrap();
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].header.nlmsg_type, libc::RTM_NEWLINK);
+        assert_eq!(InfoMessage::deserialize(&msgs[0].data).unwrap().index, 1);
+
+        let mut req = NetlinkRequest::new(libc::RTM_GETLINK, libc::NLM_F_ACK);
+        let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        msg.index = 1000;
+        req.add_data(msg);
+
+        assert!(handle.request(&mut req, libc::RTM_NEWLINK).is_err());
+    }
+
+    #[test]
+    fn test_link_get_by_index() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let attrs = LinkAttrs {
+            index: 1,
+            ..Default::default()
+        };
+        assert_eq!(handle.link_get(&attrs).unwrap().attrs().name, "lo");
+
+        // The name takes precedence over a stale index
+        let attrs = LinkAttrs {
+            index: 1000,
+            ..LinkAttrs::new("lo")
+        };
+        assert_eq!(handle.link_get(&attrs).unwrap().attrs().index, 1);
+
+        let err = handle.link_get(&LinkAttrs::default()).err().unwrap();
+        assert!(err.to_string().contains("without a name or an index"));
+    }
+
+    #[test]
+    fn test_link_get_not_found() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        for attrs in [
+            LinkAttrs::new("nosuchlink"),
+            LinkAttrs {
+                index: 1000,
+                ..Default::default()
+            },
+        ] {
+            let err = handle.link_get(&attrs).err().unwrap();
+            assert!(matches!(
+                err.downcast_ref::<Error>(),
+                Some(Error::NotFound(_))
+            ));
+        }
+
+        // Other kernel errors keep their errno
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        let err = handle.link_set_mtu(foo.attrs(), 10).err().unwrap();
+        assert_eq!(crate::error::errno(&err), Some(libc::EINVAL));
+    }
+
+    #[test]
+    fn test_neigh_replace() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, _) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        handle.link_setup(foo.attrs()).unwrap();
+
+        let mut neigh = Neighbor::new(
+            foo.attrs().index,
+            "10.0.0.2".parse().unwrap(),
+            &[0x02, 0x00, 0x00, 0x00, 0x00, 0x01],
+        );
+        handle.neigh_handle(NeighCmd::Add, &neigh).unwrap();
+
+        // Adding it again fails, replacing it does not
+        neigh.ll_addr = Some(vec![0x02, 0x00, 0x00, 0x00, 0x00, 0x02]);
+        assert!(handle.neigh_handle(NeighCmd::Add, &neigh).is_err());
+        handle.neigh_handle(NeighCmd::Replace, &neigh).unwrap();
+
+        let neighs = handle
+            .neigh_list(addr::AddrFamily::V4, foo.attrs().index, None)
+            .unwrap();
+        assert_eq!(neighs.len(), 1);
+        assert_eq!(neighs[0].ip, neigh.ip);
+        assert_eq!(neighs[0].ll_addr, neigh.ll_addr);
+        assert_eq!(neighs[0].state, NeighborState::PERMANENT);
+        assert!(neighs[0].cache_info.is_some());
+
+        handle.neigh_handle(NeighCmd::Del, &neigh).unwrap();
+
+        let neighs = handle
+            .neigh_list(addr::AddrFamily::V4, foo.attrs().index, None)
+            .unwrap();
+        assert!(neighs.is_empty());
+    }
+
+    #[test]
+    fn test_neigh_list_filter() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let (foo, bar) = handle.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+        let ll_addr = [0x02, 0x00, 0x00, 0x00, 0x00, 0x01];
+
+        for (link, ip) in [(&foo, "10.0.0.2"), (&foo, "10.0.0.3"), (&bar, "10.0.0.4")] {
+            let neigh = Neighbor::new(link.attrs().index, ip.parse().unwrap(), &ll_addr);
+            handle.neigh_handle(NeighCmd::Add, &neigh).unwrap();
+        }
+
+        let reachable = Neighbor {
+            state: NeighborState::REACHABLE,
+            ..Neighbor::new(foo.attrs().index, "10.0.0.5".parse().unwrap(), &ll_addr)
+        };
+        handle.neigh_handle(NeighCmd::Add, &reachable).unwrap();
+
+        // The kernel applies the link filter, even without strict checking
+        let mut req = neigh::neigh_list(addr::AddrFamily::V4, foo.attrs().index).unwrap();
+        let msgs = handle.request(&mut req, libc::RTM_NEWNEIGH).unwrap();
+        assert_eq!(msgs.len(), 3);
+        assert!(msgs.iter().all(|m| m.is_dump_filtered()));
+
+        let neighs = handle
+            .neigh_list(addr::AddrFamily::V4, bar.attrs().index, None)
+            .unwrap();
+        assert_eq!(neighs.len(), 1);
+        assert_eq!(neighs[0].ip, "10.0.0.4".parse::<IpAddr>().unwrap());
+
+        let neighs = handle
+            .neigh_list(
+                addr::AddrFamily::V4,
+                foo.attrs().index,
+                Some(NeighborState::PERMANENT),
+            )
+            .unwrap();
+        assert_eq!(neighs.len(), 2);
+
+        let neighs = handle
+            .neigh_list(
+                addr::AddrFamily::V4,
+                foo.attrs().index,
+                Some(NeighborState::REACHABLE | NeighborState::STALE),
+            )
+            .unwrap();
+        assert_eq!(neighs.len(), 1);
+        assert_eq!(neighs[0].ip, reachable.ip);
+    }
 }
@@ -3,12 +3,19 @@
 //! `lnwasi` is a netlink library for web assembly.
 
 pub mod addr;
+#[cfg(feature = "async")]
+pub mod async_handle;
 pub mod consts;
+pub mod error;
 pub mod handle;
 pub mod link;
 pub mod message;
+pub mod monitor;
+pub mod neigh;
 pub mod netlink;
 pub mod request;
 pub mod route;
+pub mod rule;
 pub mod socket;
 pub mod utils;
+pub mod wireguard;
@@ -1,12 +1,17 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr},
+    os::fd::RawFd,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 use crate::{
     consts,
+    error::Error,
     message::{InfoMessage, NetlinkRouteAttr},
     request::{NetlinkRequest, NetlinkRequestData},
-    utils::zero_terminated,
+    utils::{vec_to_addr, zero_terminated},
 };
 
 pub enum Namespace {
@@ -17,12 +22,21 @@ pub enum Namespace {
 pub enum Kind {
     Device(LinkAttrs),
     Dummy(LinkAttrs),
+    /// An intermediate functional block device, used to shape ingress traffic
+    /// redirected to it.
+    Ifb(LinkAttrs),
+    /// A WireGuard tunnel, configured through `wireguard::WireguardConfig` once created.
+    Wireguard(LinkAttrs),
     Bridge {
         attrs: LinkAttrs,
         hello_time: Option<u32>,
         ageing_time: Option<u32>,
         multicast_snooping: Option<bool>,
         vlan_filtering: Option<bool>,
+        /// STP state, 0 to disable the spanning tree protocol.
+        stp_state: Option<u32>,
+        /// Bridge priority used in the STP root bridge election.
+        priority: Option<u16>,
     },
     Veth {
         attrs: LinkAttrs,
@@ -30,6 +44,80 @@ pub enum Kind {
         peer_hw_addr: Option<Vec<u8>>,
         peer_ns: Option<Namespace>,
     },
+    /// An 802.1Q VLAN subinterface of the link at `attrs.parent_index`.
+    /// `vlan_protocol` is the ethertype of the tag, e.g. `0x88a8` for 802.1ad,
+    /// and defaults to 802.1Q in the kernel when unset.
+    Vlan {
+        attrs: LinkAttrs,
+        vlan_id: u16,
+        vlan_protocol: Option<u16>,
+    },
+    /// A virtual interface with its own MAC address on top of the link at `attrs.parent_index`.
+    Macvlan {
+        attrs: LinkAttrs,
+        mode: MacvlanMode,
+    },
+    /// A VXLAN tunnel endpoint with the network identifier `vxlan_id`.
+    /// `vtep_dev_index` is the link used to reach the remote endpoints,
+    /// and `port` the UDP destination port, which defaults to 8472 in the kernel when unset.
+    Vxlan {
+        attrs: LinkAttrs,
+        vxlan_id: u32,
+        vtep_dev_index: Option<i32>,
+        group: Option<IpAddr>,
+        local: Option<IpAddr>,
+        port: Option<u16>,
+        learning: Option<bool>,
+    },
+    /// An IPv4 GRE tunnel between `local` and `remote`.
+    /// `ttl` defaults to inheriting the TTL of the inner packet when unset,
+    /// and `ikey`/`okey` are the keys expected on received and sent packets.
+    Gre {
+        attrs: LinkAttrs,
+        local: IpAddr,
+        remote: IpAddr,
+        ttl: Option<u8>,
+        ikey: Option<u32>,
+        okey: Option<u32>,
+    },
+    /// An IPv4-in-IPv4 tunnel between `local` and `remote`.
+    /// `ttl` defaults to inheriting the TTL of the inner packet when unset.
+    Ipip {
+        attrs: LinkAttrs,
+        local: IpAddr,
+        remote: IpAddr,
+        ttl: Option<u8>,
+    },
+    /// An IPv6-in-IPv4 (6in4) tunnel between the IPv4 addresses `local` and `remote`.
+    Sit {
+        attrs: LinkAttrs,
+        local: IpAddr,
+        remote: IpAddr,
+        ttl: Option<u8>,
+    },
+    /// A link aggregating the links whose `master_index` is its index.
+    /// `miimon` is the interval in milliseconds at which the carrier of the slaves is checked.
+    Bond {
+        attrs: LinkAttrs,
+        mode: BondMode,
+        miimon: Option<u32>,
+    },
+    /// A BPF-programmable device pair, available since Linux 6.7.
+    /// `policy` and `mode` default to forward and L3 in the kernel when unset.
+    Netkit {
+        attrs: LinkAttrs,
+        peer_name: String,
+        policy: Option<NetkitPolicy>,
+        mode: Option<NetkitMode>,
+    },
+    /// A link of a kind that is not modeled natively.
+    /// `info_data` holds the raw `IFLA_INFO_DATA` payload,
+    /// which can be parsed with `NetlinkRouteAttr::map`.
+    Generic {
+        attrs: LinkAttrs,
+        kind: String,
+        info_data: Vec<u8>,
+    },
 }
 
 pub trait Link {
@@ -62,6 +150,8 @@ pub struct LinkAttrs {
     pub link_type: String,
     pub index: i32,
     pub name: String,
+    /// The raw hardware address (`IFLA_ADDRESS`), e.g. 6 bytes for Ethernet or 20 for
+    /// InfiniBand. Empty when the link has none; when set, it is assigned at creation.
     pub hw_addr: Vec<u8>,
     pub mtu: u32,
     pub flags: u32,
@@ -69,34 +159,150 @@ pub struct LinkAttrs {
     pub parent_index: i32,
     pub master_index: i32,
     pub tx_queue_len: i32,
-    pub alias: String,
+    /// The alias of the link (`IFLA_IFALIAS`), `None` when it has none.
+    /// It is set when creating or modifying a link, and `Some("")` clears it.
+    pub alias: Option<String>,
+    /// Name of the root qdisc, e.g. `noqueue` or `fq_codel`.
+    pub qdisc: Option<String>,
     pub promisc: i32,
     pub all_multi: i32,
     pub multicast: i32,
     pub xdp: LinkXdp,
     pub encap_type: String,
     pub prot_info: String,
+    /// The raw RFC 2863 operational state (`IFLA_OPERSTATE`), see `oper_state_enum`.
     pub oper_state: u8,
+    /// Whether the link has a carrier (`IFLA_CARRIER`), `None` if the kernel doesn't report it.
+    pub carrier: Option<bool>,
     pub phys_switch_id: i32,
     pub netns_id: i32,
     pub gso_max_size: u32,
     pub gso_max_segs: u32,
     pub gro_max_size: u32,
+    /// Maximum size of IPv4 GSO/GRO packets (BIG TCP), 0 if the kernel doesn't report it.
+    pub gso_ipv4_max_size: u32,
+    pub gro_ipv4_max_size: u32,
+    pub tso_max_size: u32,
+    pub tso_max_segs: u32,
     pub vfs: String,
     pub num_tx_queues: i32,
     pub num_rx_queues: i32,
     pub group: u32,
-    pub statistics: String,
+    /// The counters of the link (`IFLA_STATS64`), as shown by `ip -s link`.
+    pub statistics: Option<LinkStatistics>,
+    pub slave: Option<SlaveInfo>,
+}
+
+/// Builds the `LinkAttrs` of a link to create.
+///
+/// # Examples
+///
+/// ```
+/// use lnwasi::link::{Kind, LinkAttrs};
+///
+/// let link = Kind::Veth {
+///     attrs: LinkAttrs::builder("foo")
+///         .mtu(9000)
+///         .num_tx_queues(4)
+///         .num_rx_queues(4)
+///         .build(),
+///     peer_name: "bar".to_string(),
+///     peer_hw_addr: None,
+///     peer_ns: None,
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct LinkAttrsBuilder {
+    attrs: LinkAttrs,
+}
+
+impl LinkAttrsBuilder {
+    pub fn mtu(mut self, mtu: u32) -> Self {
+        self.attrs.mtu = mtu;
+        self
+    }
+
+    pub fn tx_queue_len(mut self, tx_queue_len: i32) -> Self {
+        self.attrs.tx_queue_len = tx_queue_len;
+        self
+    }
+
+    pub fn num_tx_queues(mut self, num_tx_queues: i32) -> Self {
+        self.attrs.num_tx_queues = num_tx_queues;
+        self
+    }
+
+    pub fn num_rx_queues(mut self, num_rx_queues: i32) -> Self {
+        self.attrs.num_rx_queues = num_rx_queues;
+        self
+    }
+
+    pub fn master_index(mut self, master_index: i32) -> Self {
+        self.attrs.master_index = master_index;
+        self
+    }
+
+    pub fn parent_index(mut self, parent_index: i32) -> Self {
+        self.attrs.parent_index = parent_index;
+        self
+    }
+
+    pub fn hw_addr(mut self, hw_addr: &[u8]) -> Self {
+        self.attrs.hw_addr = hw_addr.to_vec();
+        self
+    }
+
+    pub fn build(self) -> LinkAttrs {
+        self.attrs
+    }
+}
+
+// Operations that only need the name or index of a link take `AsRef<LinkAttrs>`,
+// so plain attributes (e.g. from a config file) can be passed as well as links.
+impl AsRef<LinkAttrs> for LinkAttrs {
+    fn as_ref(&self) -> &LinkAttrs {
+        self
+    }
+}
+
+impl AsRef<LinkAttrs> for Kind {
+    fn as_ref(&self) -> &LinkAttrs {
+        self.attrs()
+    }
+}
+
+impl AsRef<LinkAttrs> for dyn Link {
+    fn as_ref(&self) -> &LinkAttrs {
+        self.attrs()
+    }
+}
+
+impl AsRef<LinkAttrs> for Box<dyn Link> {
+    fn as_ref(&self) -> &LinkAttrs {
+        self.attrs()
+    }
 }
 
 impl LinkAttrs {
     pub fn new(name: &str) -> Self {
-        Self {
-            name: name.to_string(),
-            ..Default::default()
+        Self::builder(name).build()
+    }
+
+    pub fn builder(name: &str) -> LinkAttrsBuilder {
+        LinkAttrsBuilder {
+            attrs: Self {
+                name: name.to_string(),
+                ..Default::default()
+            },
         }
     }
 
+    /// The operational state of the link, i.e. whether it is actually able to carry
+    /// traffic rather than only administratively up.
+    pub fn oper_state_enum(&self) -> OperState {
+        OperState::from(self.oper_state)
+    }
+
     fn from(if_info_msg: InfoMessage) -> Self {
         let mut attrs = Self::default();
         attrs.index = if_info_msg.index;
@@ -143,8 +349,19 @@ impl Link for Kind {
         match self {
             Kind::Device(_) => "device".to_string(),
             Kind::Dummy(_) => "dummy".to_string(),
+            Kind::Ifb(_) => "ifb".to_string(),
+            Kind::Wireguard(_) => "wireguard".to_string(),
             Kind::Bridge { .. } => "bridge".to_string(),
             Kind::Veth { .. } => "veth".to_string(),
+            Kind::Vlan { .. } => "vlan".to_string(),
+            Kind::Macvlan { .. } => "macvlan".to_string(),
+            Kind::Vxlan { .. } => "vxlan".to_string(),
+            Kind::Gre { .. } => "gre".to_string(),
+            Kind::Ipip { .. } => "ipip".to_string(),
+            Kind::Sit { .. } => "sit".to_string(),
+            Kind::Bond { .. } => "bond".to_string(),
+            Kind::Netkit { .. } => "netkit".to_string(),
+            Kind::Generic { kind, .. } => kind.clone(),
         }
     }
 
@@ -152,8 +369,19 @@ impl Link for Kind {
         match self {
             Kind::Device(attrs) => attrs,
             Kind::Dummy(attrs) => attrs,
+            Kind::Ifb(attrs) => attrs,
+            Kind::Wireguard(attrs) => attrs,
             Kind::Bridge { attrs, .. } => attrs,
             Kind::Veth { attrs, .. } => attrs,
+            Kind::Vlan { attrs, .. } => attrs,
+            Kind::Macvlan { attrs, .. } => attrs,
+            Kind::Vxlan { attrs, .. } => attrs,
+            Kind::Gre { attrs, .. } => attrs,
+            Kind::Ipip { attrs, .. } => attrs,
+            Kind::Sit { attrs, .. } => attrs,
+            Kind::Bond { attrs, .. } => attrs,
+            Kind::Netkit { attrs, .. } => attrs,
+            Kind::Generic { attrs, .. } => attrs,
         }
     }
 
@@ -161,8 +389,19 @@ impl Link for Kind {
         match self {
             Kind::Device(attrs) => attrs,
             Kind::Dummy(attrs) => attrs,
+            Kind::Ifb(attrs) => attrs,
+            Kind::Wireguard(attrs) => attrs,
             Kind::Bridge { attrs, .. } => attrs,
             Kind::Veth { attrs, .. } => attrs,
+            Kind::Vlan { attrs, .. } => attrs,
+            Kind::Macvlan { attrs, .. } => attrs,
+            Kind::Vxlan { attrs, .. } => attrs,
+            Kind::Gre { attrs, .. } => attrs,
+            Kind::Ipip { attrs, .. } => attrs,
+            Kind::Sit { attrs, .. } => attrs,
+            Kind::Bond { attrs, .. } => attrs,
+            Kind::Netkit { attrs, .. } => attrs,
+            Kind::Generic { attrs, .. } => attrs,
         }
     }
 
@@ -171,6 +410,371 @@ impl Link for Kind {
     }
 }
 
+/// How a macvlan interface forwards traffic to the other macvlans of its parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacvlanMode {
+    /// No traffic to other macvlans on the same parent.
+    Private,
+    /// Traffic to other macvlans goes through the external switch.
+    Vepa,
+    /// Traffic to other macvlans is forwarded directly.
+    Bridge,
+    /// The single macvlan takes over the parent.
+    Passthru,
+    Unknown(u32),
+}
+
+impl From<u32> for MacvlanMode {
+    fn from(mode: u32) -> Self {
+        match mode {
+            consts::MACVLAN_MODE_PRIVATE => Self::Private,
+            consts::MACVLAN_MODE_VEPA => Self::Vepa,
+            consts::MACVLAN_MODE_BRIDGE => Self::Bridge,
+            consts::MACVLAN_MODE_PASSTHRU => Self::Passthru,
+            _ => Self::Unknown(mode),
+        }
+    }
+}
+
+impl From<MacvlanMode> for u32 {
+    fn from(mode: MacvlanMode) -> Self {
+        match mode {
+            MacvlanMode::Private => consts::MACVLAN_MODE_PRIVATE,
+            MacvlanMode::Vepa => consts::MACVLAN_MODE_VEPA,
+            MacvlanMode::Bridge => consts::MACVLAN_MODE_BRIDGE,
+            MacvlanMode::Passthru => consts::MACVLAN_MODE_PASSTHRU,
+            MacvlanMode::Unknown(mode) => mode,
+        }
+    }
+}
+
+/// How a bond distributes traffic over its slaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BondMode {
+    /// balance-rr: packets are sent on each slave in turn.
+    BalanceRr,
+    /// active-backup: a single slave is used until it fails.
+    ActiveBackup,
+    /// balance-xor: the slave is chosen from a hash of the packet.
+    BalanceXor,
+    /// broadcast: packets are sent on all slaves.
+    Broadcast,
+    /// 802.3ad: dynamic link aggregation with LACP.
+    Lacp,
+    /// balance-tlb: adaptive transmit load balancing.
+    BalanceTlb,
+    /// balance-alb: adaptive transmit and receive load balancing.
+    BalanceAlb,
+    Unknown(u8),
+}
+
+impl From<u8> for BondMode {
+    fn from(mode: u8) -> Self {
+        match mode {
+            consts::BOND_MODE_ROUNDROBIN => Self::BalanceRr,
+            consts::BOND_MODE_ACTIVEBACKUP => Self::ActiveBackup,
+            consts::BOND_MODE_XOR => Self::BalanceXor,
+            consts::BOND_MODE_BROADCAST => Self::Broadcast,
+            consts::BOND_MODE_8023AD => Self::Lacp,
+            consts::BOND_MODE_TLB => Self::BalanceTlb,
+            consts::BOND_MODE_ALB => Self::BalanceAlb,
+            _ => Self::Unknown(mode),
+        }
+    }
+}
+
+impl From<BondMode> for u8 {
+    fn from(mode: BondMode) -> Self {
+        match mode {
+            BondMode::BalanceRr => consts::BOND_MODE_ROUNDROBIN,
+            BondMode::ActiveBackup => consts::BOND_MODE_ACTIVEBACKUP,
+            BondMode::BalanceXor => consts::BOND_MODE_XOR,
+            BondMode::Broadcast => consts::BOND_MODE_BROADCAST,
+            BondMode::Lacp => consts::BOND_MODE_8023AD,
+            BondMode::BalanceTlb => consts::BOND_MODE_TLB,
+            BondMode::BalanceAlb => consts::BOND_MODE_ALB,
+            BondMode::Unknown(mode) => mode,
+        }
+    }
+}
+
+/// What a netkit device does with packets when no BPF program is attached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetkitPolicy {
+    Forward,
+    Blackhole,
+    Unknown(u32),
+}
+
+impl From<u32> for NetkitPolicy {
+    fn from(policy: u32) -> Self {
+        match policy {
+            consts::NETKIT_PASS => Self::Forward,
+            consts::NETKIT_DROP => Self::Blackhole,
+            _ => Self::Unknown(policy),
+        }
+    }
+}
+
+impl From<NetkitPolicy> for u32 {
+    fn from(policy: NetkitPolicy) -> Self {
+        match policy {
+            NetkitPolicy::Forward => consts::NETKIT_PASS,
+            NetkitPolicy::Blackhole => consts::NETKIT_DROP,
+            NetkitPolicy::Unknown(policy) => policy,
+        }
+    }
+}
+
+/// Whether a netkit device operates on L2 (with an Ethernet header) or L3 packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetkitMode {
+    L2,
+    L3,
+    Unknown(u32),
+}
+
+impl From<u32> for NetkitMode {
+    fn from(mode: u32) -> Self {
+        match mode {
+            consts::NETKIT_L2 => Self::L2,
+            consts::NETKIT_L3 => Self::L3,
+            _ => Self::Unknown(mode),
+        }
+    }
+}
+
+impl From<NetkitMode> for u32 {
+    fn from(mode: NetkitMode) -> Self {
+        match mode {
+            NetkitMode::L2 => consts::NETKIT_L2,
+            NetkitMode::L3 => consts::NETKIT_L3,
+            NetkitMode::Unknown(mode) => mode,
+        }
+    }
+}
+
+/// Operational state of a link, as defined by RFC 2863.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperState {
+    Unknown,
+    NotPresent,
+    Down,
+    LowerLayerDown,
+    Testing,
+    Dormant,
+    Up,
+}
+
+impl From<u8> for OperState {
+    fn from(state: u8) -> Self {
+        match state {
+            consts::IF_OPER_NOTPRESENT => Self::NotPresent,
+            consts::IF_OPER_DOWN => Self::Down,
+            consts::IF_OPER_LOWERLAYERDOWN => Self::LowerLayerDown,
+            consts::IF_OPER_TESTING => Self::Testing,
+            consts::IF_OPER_DORMANT => Self::Dormant,
+            consts::IF_OPER_UP => Self::Up,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Spanning tree state of a bridge port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BridgePortState {
+    Disabled,
+    Listening,
+    Learning,
+    Forwarding,
+    Blocking,
+    Unknown(u8),
+}
+
+impl From<u8> for BridgePortState {
+    fn from(state: u8) -> Self {
+        match state {
+            consts::BR_STATE_DISABLED => Self::Disabled,
+            consts::BR_STATE_LISTENING => Self::Listening,
+            consts::BR_STATE_LEARNING => Self::Learning,
+            consts::BR_STATE_FORWARDING => Self::Forwarding,
+            consts::BR_STATE_BLOCKING => Self::Blocking,
+            _ => Self::Unknown(state),
+        }
+    }
+}
+
+/// State of a bond slave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BondSlaveState {
+    Active,
+    Backup,
+    Unknown(u8),
+}
+
+impl From<u8> for BondSlaveState {
+    fn from(state: u8) -> Self {
+        match state {
+            consts::BOND_STATE_ACTIVE => Self::Active,
+            consts::BOND_STATE_BACKUP => Self::Backup,
+            _ => Self::Unknown(state),
+        }
+    }
+}
+
+/// Link state of a bond slave, as seen by the MII monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BondMiiStatus {
+    Up,
+    Fail,
+    Down,
+    Back,
+    Unknown(u8),
+}
+
+impl From<u8> for BondMiiStatus {
+    fn from(status: u8) -> Self {
+        match status {
+            consts::BOND_LINK_UP => Self::Up,
+            consts::BOND_LINK_FAIL => Self::Fail,
+            consts::BOND_LINK_DOWN => Self::Down,
+            consts::BOND_LINK_BACK => Self::Back,
+            _ => Self::Unknown(status),
+        }
+    }
+}
+
+/// Information about a link from the point of view of its master.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlaveInfo {
+    Bridge {
+        state: BridgePortState,
+        priority: Option<u16>,
+        cost: Option<u32>,
+    },
+    Bond {
+        state: BondSlaveState,
+        mii_status: BondMiiStatus,
+        link_failure_count: u32,
+        perm_hw_addr: Vec<u8>,
+        queue_id: u16,
+    },
+}
+
+impl SlaveInfo {
+    fn bridge(data: &[u8]) -> Result<Self> {
+        let mut state = BridgePortState::Disabled;
+        let mut priority = None;
+        let mut cost = None;
+
+        for attr in NetlinkRouteAttr::from(data)? {
+            match attr.rt_attr.rta_type & !consts::NLA_F_NESTED {
+                consts::IFLA_BRPORT_STATE => {
+                    if let Some(&v) = attr.value.first() {
+                        state = v.into();
+                    }
+                }
+                consts::IFLA_BRPORT_PRIORITY => {
+                    priority = ne_u16(&attr.value);
+                }
+                consts::IFLA_BRPORT_COST => {
+                    cost = ne_u32(&attr.value);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self::Bridge {
+            state,
+            priority,
+            cost,
+        })
+    }
+
+    fn bond(data: &[u8]) -> Result<Self> {
+        let mut state = BondSlaveState::Active;
+        let mut mii_status = BondMiiStatus::Up;
+        let mut link_failure_count = 0;
+        let mut perm_hw_addr = Vec::new();
+        let mut queue_id = 0;
+
+        for attr in NetlinkRouteAttr::from(data)? {
+            match attr.rt_attr.rta_type {
+                consts::IFLA_BOND_SLAVE_STATE => {
+                    if let Some(&v) = attr.value.first() {
+                        state = v.into();
+                    }
+                }
+                consts::IFLA_BOND_SLAVE_MII_STATUS => {
+                    if let Some(&v) = attr.value.first() {
+                        mii_status = v.into();
+                    }
+                }
+                consts::IFLA_BOND_SLAVE_LINK_FAILURE_COUNT => {
+                    if let Some(count) = ne_u32(&attr.value) {
+                        link_failure_count = count;
+                    }
+                }
+                consts::IFLA_BOND_SLAVE_PERM_HWADDR => {
+                    perm_hw_addr = attr.value;
+                }
+                consts::IFLA_BOND_SLAVE_QUEUE_ID => {
+                    if let Some(id) = ne_u16(&attr.value) {
+                        queue_id = id;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self::Bond {
+            state,
+            mii_status,
+            link_failure_count,
+            perm_hw_addr,
+            queue_id,
+        })
+    }
+}
+
+/// The main counters of a link, from the leading fields of `struct rtnl_link_stats64`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LinkStatistics {
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+}
+
+impl LinkStatistics {
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        if buf.len() < 8 * 8 {
+            return Err(Error::Parse(format!(
+                "link statistics are too short: {} bytes",
+                buf.len()
+            ))
+            .into());
+        }
+
+        let field = |i: usize| u64::from_ne_bytes(buf[i * 8..(i + 1) * 8].try_into().unwrap());
+
+        Ok(Self {
+            rx_packets: field(0),
+            tx_packets: field(1),
+            rx_bytes: field(2),
+            tx_bytes: field(3),
+            rx_errors: field(4),
+            tx_errors: field(5),
+            rx_dropped: field(6),
+            tx_dropped: field(7),
+        })
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct LinkXdp {
     fd: i32,
@@ -195,7 +799,7 @@ impl LinkXdp {
                     xdp.fd = i32::from_ne_bytes(attr.value[..4].try_into()?);
                 }
                 consts::IFLA_XDP_ATTACHED => {
-                    xdp.attache_mode = attr.value[0].try_into()?;
+                    xdp.attache_mode = attr.value[0].into();
                     xdp.attached = attr.value[0] != 0;
                 }
                 consts::IFLA_XDP_FLAGS => {
@@ -212,23 +816,90 @@ impl LinkXdp {
     }
 }
 
+/// Dumped links, with lookups by name and by index.
+#[derive(Default)]
+pub struct LinkList {
+    links: Vec<Box<dyn Link>>,
+    by_name: HashMap<String, usize>,
+    by_index: HashMap<i32, usize>,
+}
+
+impl LinkList {
+    pub fn by_name(&self, name: &str) -> Option<&dyn Link> {
+        self.by_name.get(name).map(|&i| self.links[i].as_ref())
+    }
+
+    pub fn by_index(&self, index: i32) -> Option<&dyn Link> {
+        self.by_index.get(&index).map(|&i| self.links[i].as_ref())
+    }
+
+    /// The links in the order of the dump.
+    pub fn links(&self) -> &[Box<dyn Link>] {
+        &self.links
+    }
+
+    pub fn into_vec(self) -> Vec<Box<dyn Link>> {
+        self.links
+    }
+
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+}
+
+impl From<Vec<Box<dyn Link>>> for LinkList {
+    fn from(links: Vec<Box<dyn Link>>) -> Self {
+        let by_name = links
+            .iter()
+            .enumerate()
+            .map(|(i, link)| (link.attrs().name.clone(), i))
+            .collect();
+        let by_index = links
+            .iter()
+            .enumerate()
+            .map(|(i, link)| (link.attrs().index, i))
+            .collect();
+
+        Self {
+            links,
+            by_name,
+            by_index,
+        }
+    }
+}
+
+impl IntoIterator for LinkList {
+    type Item = Box<dyn Link>;
+    type IntoIter = std::vec::IntoIter<Box<dyn Link>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.links.into_iter()
+    }
+}
+
 pub fn link_deserialize(buf: &[u8]) -> Result<Box<dyn Link>> {
     let if_info_msg = InfoMessage::deserialize(buf)?;
     let rt_attrs = NetlinkRouteAttr::from(&buf[if_info_msg.len()..])?;
 
+    let family = if_info_msg.family;
     let mut base = LinkAttrs::from(if_info_msg);
-    let mut data = HashMap::new();
+    let mut info_data = Vec::new();
 
     for attr in rt_attrs {
-        match attr.rt_attr.rta_type {
+        match attr.rt_attr.rta_type & !consts::NLA_F_NESTED {
             libc::IFLA_LINKINFO => {
-                data = extract_link_info(&mut base, NetlinkRouteAttr::from(&attr.value)?)?
+                info_data = extract_link_info(&mut base, NetlinkRouteAttr::from(&attr.value)?)?
             }
             libc::IFLA_ADDRESS => {
                 base.hw_addr = attr.value;
             }
             libc::IFLA_IFNAME => {
-                base.name = String::from_utf8(attr.value[..attr.value.len() - 1].to_vec())?;
+                let name = attr.value.strip_suffix(&[0]).unwrap_or(&attr.value);
+                base.name = String::from_utf8(name.to_vec())?;
             }
             libc::IFLA_MTU => {
                 base.mtu = u32::from_ne_bytes(attr.value[..4].try_into()?);
@@ -242,24 +913,34 @@ pub fn link_deserialize(buf: &[u8]) -> Result<Box<dyn Link>> {
             libc::IFLA_TXQLEN => {
                 base.tx_queue_len = i32::from_ne_bytes(attr.value[..4].try_into()?);
             }
+            consts::IFLA_QDISC => {
+                let qdisc = attr.value.strip_suffix(&[0]).unwrap_or(&attr.value);
+                base.qdisc = Some(String::from_utf8(qdisc.to_vec())?);
+            }
             libc::IFLA_IFALIAS => {
-                base.alias = String::from_utf8(attr.value[..attr.value.len() - 1].to_vec())?;
+                let alias = attr.value.strip_suffix(&[0]).unwrap_or(&attr.value);
+                base.alias = Some(String::from_utf8(alias.to_vec())?);
             }
             libc::IFLA_STATS => {
                 // TODO
             }
             libc::IFLA_STATS64 => {
-                // TODO
+                base.statistics = Some(LinkStatistics::deserialize(&attr.value)?);
             }
             libc::IFLA_XDP => {
                 base.xdp = LinkXdp::parse(&attr.value)?;
             }
-            libc::IFLA_PROTINFO | consts::NLA_F_NESTED => {
-                // TODO
+            // Only bridge dumps carry the port state here,
+            // other families use it for their own protocol info.
+            libc::IFLA_PROTINFO if family == libc::AF_BRIDGE as u8 => {
+                base.slave = Some(SlaveInfo::bridge(&attr.value)?);
             }
             libc::IFLA_OPERSTATE => {
                 base.oper_state = attr.value[0];
             }
+            consts::IFLA_CARRIER => {
+                base.carrier = attr.value.first().map(|&carrier| carrier != 0);
+            }
             libc::IFLA_PHYS_SWITCH_ID => {
                 base.phys_switch_id = i32::from_be_bytes(attr.value[..4].try_into()?);
             }
@@ -275,6 +956,18 @@ pub fn link_deserialize(buf: &[u8]) -> Result<Box<dyn Link>> {
             consts::IFLA_GRO_MAX_SIZE => {
                 base.gro_max_size = u32::from_ne_bytes(attr.value[..4].try_into()?);
             }
+            consts::IFLA_GSO_IPV4_MAX_SIZE => {
+                base.gso_ipv4_max_size = u32::from_ne_bytes(attr.value[..4].try_into()?);
+            }
+            consts::IFLA_GRO_IPV4_MAX_SIZE => {
+                base.gro_ipv4_max_size = u32::from_ne_bytes(attr.value[..4].try_into()?);
+            }
+            consts::IFLA_TSO_MAX_SIZE => {
+                base.tso_max_size = u32::from_ne_bytes(attr.value[..4].try_into()?);
+            }
+            consts::IFLA_TSO_MAX_SEGS => {
+                base.tso_max_segs = u32::from_ne_bytes(attr.value[..4].try_into()?);
+            }
             libc::IFLA_VFINFO_LIST => {
                 // TODO
             }
@@ -291,19 +984,29 @@ pub fn link_deserialize(buf: &[u8]) -> Result<Box<dyn Link>> {
         }
     }
 
+    let data = NetlinkRouteAttr::map(&info_data)?;
+
     Ok(match &base.link_type[..] {
-        "device" => Box::new(Kind::Device(base)),
+        "" | "device" => Box::new(Kind::Device(base)),
         "dummy" => Box::new(Kind::Dummy(base)),
+        "ifb" => Box::new(Kind::Ifb(base)),
+        "wireguard" => Box::new(Kind::Wireguard(base)),
         "bridge" => Box::new(Kind::Bridge {
             attrs: base,
             hello_time: data
                 .get(&consts::IFLA_BR_HELLO_TIME)
-                .map(|v| u32::from_ne_bytes(v[..4].try_into().unwrap_or([0; 4]))),
+                .and_then(|v| ne_u32(v)),
             ageing_time: data
                 .get(&consts::IFLA_BR_AGEING_TIME)
-                .map(|v| u32::from_ne_bytes(v[..4].try_into().unwrap_or([0; 4]))),
-            multicast_snooping: data.get(&consts::IFLA_BR_MCAST_SNOOPING).map(|v| v[0] == 1),
-            vlan_filtering: data.get(&consts::IFLA_BR_VLAN_FILTERING).map(|v| v[0] == 1),
+                .and_then(|v| ne_u32(v)),
+            multicast_snooping: data
+                .get(&consts::IFLA_BR_MCAST_SNOOPING)
+                .and_then(|v| v.first().map(|&v| v == 1)),
+            vlan_filtering: data
+                .get(&consts::IFLA_BR_VLAN_FILTERING)
+                .and_then(|v| v.first().map(|&v| v == 1)),
+            stp_state: data.get(&consts::IFLA_BR_STP_STATE).and_then(|v| ne_u32(v)),
+            priority: data.get(&consts::IFLA_BR_PRIORITY).and_then(|v| ne_u16(v)),
         }),
         "veth" => Box::new(Kind::Veth {
             attrs: base,
@@ -311,34 +1014,186 @@ pub fn link_deserialize(buf: &[u8]) -> Result<Box<dyn Link>> {
             peer_hw_addr: None,
             peer_ns: None,
         }),
-        _ => Box::new(Kind::Device(base)),
+        "vlan" => Box::new(Kind::Vlan {
+            attrs: base,
+            vlan_id: data
+                .get(&consts::IFLA_VLAN_ID)
+                .and_then(|v| ne_u16(v))
+                .unwrap_or_default(),
+            vlan_protocol: data
+                .get(&consts::IFLA_VLAN_PROTOCOL)
+                .and_then(|v| be_u16(v)),
+        }),
+        "macvlan" => Box::new(Kind::Macvlan {
+            attrs: base,
+            mode: data
+                .get(&consts::IFLA_MACVLAN_MODE)
+                .and_then(|v| ne_u32(v))
+                .unwrap_or(consts::MACVLAN_MODE_VEPA)
+                .into(),
+        }),
+        "vxlan" => Box::new(Kind::Vxlan {
+            attrs: base,
+            vxlan_id: data
+                .get(&consts::IFLA_VXLAN_ID)
+                .and_then(|v| ne_u32(v))
+                .unwrap_or_default(),
+            vtep_dev_index: data
+                .get(&consts::IFLA_VXLAN_LINK)
+                .and_then(|v| ne_u32(v))
+                .map(|index| index as i32),
+            group: data
+                .get(&consts::IFLA_VXLAN_GROUP)
+                .or_else(|| data.get(&consts::IFLA_VXLAN_GROUP6))
+                .map(|v| vec_to_addr(v.clone()))
+                .transpose()?,
+            local: data
+                .get(&consts::IFLA_VXLAN_LOCAL)
+                .or_else(|| data.get(&consts::IFLA_VXLAN_LOCAL6))
+                .map(|v| vec_to_addr(v.clone()))
+                .transpose()?,
+            port: data.get(&consts::IFLA_VXLAN_PORT).and_then(|v| be_u16(v)),
+            learning: data
+                .get(&consts::IFLA_VXLAN_LEARNING)
+                .and_then(|v| v.first().map(|&v| v == 1)),
+        }),
+        "gre" => Box::new(Kind::Gre {
+            attrs: base,
+            local: data
+                .get(&consts::IFLA_GRE_LOCAL)
+                .map(|v| vec_to_addr(v.clone()))
+                .transpose()?
+                .unwrap_or(Ipv4Addr::UNSPECIFIED.into()),
+            remote: data
+                .get(&consts::IFLA_GRE_REMOTE)
+                .map(|v| vec_to_addr(v.clone()))
+                .transpose()?
+                .unwrap_or(Ipv4Addr::UNSPECIFIED.into()),
+            ttl: data
+                .get(&consts::IFLA_GRE_TTL)
+                .and_then(|v| v.first().copied()),
+            ikey: gre_key(&data, consts::IFLA_GRE_IFLAGS, consts::IFLA_GRE_IKEY),
+            okey: gre_key(&data, consts::IFLA_GRE_OFLAGS, consts::IFLA_GRE_OKEY),
+        }),
+        "ipip" | "sit" => {
+            let local = data
+                .get(&consts::IFLA_IPTUN_LOCAL)
+                .map(|v| vec_to_addr(v.clone()))
+                .transpose()?
+                .unwrap_or(Ipv4Addr::UNSPECIFIED.into());
+            let remote = data
+                .get(&consts::IFLA_IPTUN_REMOTE)
+                .map(|v| vec_to_addr(v.clone()))
+                .transpose()?
+                .unwrap_or(Ipv4Addr::UNSPECIFIED.into());
+            let ttl = data
+                .get(&consts::IFLA_IPTUN_TTL)
+                .and_then(|v| v.first().copied());
+
+            match &base.link_type[..] {
+                "ipip" => Box::new(Kind::Ipip {
+                    attrs: base,
+                    local,
+                    remote,
+                    ttl,
+                }),
+                _ => Box::new(Kind::Sit {
+                    attrs: base,
+                    local,
+                    remote,
+                    ttl,
+                }),
+            }
+        }
+        "bond" => Box::new(Kind::Bond {
+            attrs: base,
+            mode: data
+                .get(&consts::IFLA_BOND_MODE)
+                .and_then(|v| v.first().copied())
+                .unwrap_or(consts::BOND_MODE_ROUNDROBIN)
+                .into(),
+            miimon: data.get(&consts::IFLA_BOND_MIIMON).and_then(|v| ne_u32(v)),
+        }),
+        "netkit" => Box::new(Kind::Netkit {
+            attrs: base,
+            peer_name: Default::default(),
+            policy: data
+                .get(&consts::IFLA_NETKIT_POLICY)
+                .and_then(|v| ne_u32(v))
+                .map(NetkitPolicy::from),
+            mode: data
+                .get(&consts::IFLA_NETKIT_MODE)
+                .and_then(|v| ne_u32(v))
+                .map(NetkitMode::from),
+        }),
+        _ => Box::new(Kind::Generic {
+            kind: base.link_type.clone(),
+            attrs: base,
+            info_data,
+        }),
     })
 }
 
-fn extract_link_info(
-    base: &mut LinkAttrs,
-    infos: Vec<NetlinkRouteAttr>,
-) -> Result<HashMap<u16, Vec<u8>>> {
-    let mut data = HashMap::new();
+// An attribute value shorter than its type, e.g. from a buggy or newer kernel,
+// reads as absent instead of panicking on the slice
+fn ne_u16(v: &[u8]) -> Option<u16> {
+    v.get(..2)?.try_into().ok().map(u16::from_ne_bytes)
+}
+
+fn ne_u32(v: &[u8]) -> Option<u32> {
+    v.get(..4)?.try_into().ok().map(u32::from_ne_bytes)
+}
+
+fn be_u16(v: &[u8]) -> Option<u16> {
+    v.get(..2)?.try_into().ok().map(u16::from_be_bytes)
+}
+
+fn be_u32(v: &[u8]) -> Option<u32> {
+    v.get(..4)?.try_into().ok().map(u32::from_be_bytes)
+}
+
+// The kernel always reports the keys of a GRE tunnel, which are only used with GRE_KEY set
+fn gre_key(data: &HashMap<u16, Vec<u8>>, flags: u16, key: u16) -> Option<u32> {
+    let flags = data.get(&flags).and_then(|v| be_u16(v))?;
+
+    if flags & consts::GRE_KEY == 0 {
+        return None;
+    }
+
+    data.get(&key).and_then(|v| be_u32(v))
+}
+
+fn extract_link_info(base: &mut LinkAttrs, infos: Vec<NetlinkRouteAttr>) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut slave_kind = String::new();
+    let mut slave_data = Vec::new();
 
     for info in infos {
-        match info.rt_attr.rta_type {
+        match info.rt_attr.rta_type & !consts::NLA_F_NESTED {
             libc::IFLA_INFO_KIND => {
-                base.link_type = String::from_utf8(info.value[..info.value.len() - 1].to_vec())?;
+                let kind = info.value.strip_suffix(&[0]).unwrap_or(&info.value);
+                base.link_type = String::from_utf8(kind.to_vec())?;
             }
             libc::IFLA_INFO_DATA => {
-                data = NetlinkRouteAttr::map(&info.value)?;
+                data = info.value;
             }
             libc::IFLA_INFO_SLAVE_KIND => {
-                // TODO
+                let kind = info.value.strip_suffix(&[0]).unwrap_or(&info.value);
+                slave_kind = String::from_utf8(kind.to_vec())?;
             }
             libc::IFLA_INFO_SLAVE_DATA => {
-                // TODO
+                slave_data = info.value;
             }
             _ => {}
         }
     }
 
+    match &slave_kind[..] {
+        "bridge" => base.slave = Some(SlaveInfo::bridge(&slave_data)?),
+        "bond" => base.slave = Some(SlaveInfo::bond(&slave_data)?),
+        _ => {}
+    }
+
     Ok(data)
 }
 
@@ -380,14 +1235,28 @@ pub fn link_new(link: &(impl Link + ?Sized), flags: i32) -> Result<NetlinkReques
 
     req.add_data(name);
 
-    // TODO
-    // if base.hw_addr.len() > 0 {
-    //     let hw_addr = Box::new(NetlinkRouteAttr::new(
-    //         libc::IFLA_ADDRESS,
-    //         base.hw_addr.clone(),
-    //     ));
-    //     req.add_data(hw_addr);
-    // }
+    // Only at creation: a link read back and modified carries its current address,
+    // which devices that can't change it (e.g. lo) would refuse even if unchanged
+    if !base.hw_addr.is_empty() && flags & libc::NLM_F_CREATE != 0 {
+        let hw_addr = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_ADDRESS,
+            base.hw_addr.clone(),
+        ));
+        req.add_data(hw_addr);
+    }
+
+    // The kernel ignores the alias at creation, `SocketHandle::link_new` sets it afterwards
+    if let Some(alias) = base
+        .alias
+        .as_ref()
+        .filter(|_| flags & libc::NLM_F_CREATE == 0)
+    {
+        let alias = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_IFALIAS,
+            alias.as_bytes().to_vec(),
+        ));
+        req.add_data(alias);
+    }
 
     if base.mtu > 0 {
         let mtu = Box::new(NetlinkRouteAttr::new(
@@ -421,9 +1290,25 @@ pub fn link_new(link: &(impl Link + ?Sized), flags: i32) -> Result<NetlinkReques
         req.add_data(num_rx_queues);
     }
 
+    if base.parent_index != 0 {
+        let parent = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_LINK,
+            base.parent_index.to_ne_bytes().to_vec(),
+        ));
+        req.add_data(parent);
+    }
+
+    if base.master_index != 0 {
+        let master = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_MASTER,
+            base.master_index.to_ne_bytes().to_vec(),
+        ));
+        req.add_data(master);
+    }
+
     let mut link_info = Box::new(NetlinkRouteAttr::new(libc::IFLA_LINKINFO, vec![]));
 
-    link_info.add_child(libc::IFLA_INFO_KIND, link.link_type().as_bytes().to_vec());
+    link_info.add_child(libc::IFLA_INFO_KIND, zero_terminated(&link.link_type()));
 
     match link.kind() {
         Kind::Bridge {
@@ -432,6 +1317,8 @@ pub fn link_new(link: &(impl Link + ?Sized), flags: i32) -> Result<NetlinkReques
             ageing_time,
             multicast_snooping,
             vlan_filtering,
+            stp_state,
+            priority,
         } => {
             let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
 
@@ -463,6 +1350,14 @@ pub fn link_new(link: &(impl Link + ?Sized), flags: i32) -> Result<NetlinkReques
                 );
             }
 
+            if let Some(stp_state) = stp_state {
+                data.add_child(consts::IFLA_BR_STP_STATE, stp_state.to_ne_bytes().to_vec());
+            }
+
+            if let Some(priority) = priority {
+                data.add_child(consts::IFLA_BR_PRIORITY, priority.to_ne_bytes().to_vec());
+            }
+
             link_info.add_child_from_attr(data);
         }
         Kind::Veth {
@@ -503,23 +1398,229 @@ pub fn link_new(link: &(impl Link + ?Sized), flags: i32) -> Result<NetlinkReques
                 peer_info.add_child(libc::IFLA_ADDRESS, hw_addr.to_vec());
             }
 
-            match peer_ns {
-                Some(ns) => match ns {
+            if let Some(ns) = peer_ns {
+                match ns {
                     Namespace::Pid(pid) => {
                         peer_info.add_child(libc::IFLA_NET_NS_PID, pid.to_ne_bytes().to_vec());
                     }
                     Namespace::Fd(fd) => {
                         peer_info.add_child(libc::IFLA_NET_NS_FD, fd.to_ne_bytes().to_vec());
                     }
-                },
-                None => {}
+                }
             }
 
             data.add_child_from_attr(peer_info);
             link_info.add_child_from_attr(data);
         }
-        _ => {}
-    }
+        Kind::Vlan {
+            attrs: _,
+            vlan_id,
+            vlan_protocol,
+        } => {
+            let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+
+            data.add_child(consts::IFLA_VLAN_ID, vlan_id.to_ne_bytes().to_vec());
+
+            // The protocol is in network byte order
+            if let Some(vlan_protocol) = vlan_protocol {
+                data.add_child(
+                    consts::IFLA_VLAN_PROTOCOL,
+                    vlan_protocol.to_be_bytes().to_vec(),
+                );
+            }
+
+            link_info.add_child_from_attr(data);
+        }
+        Kind::Macvlan { attrs: _, mode } => {
+            let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+
+            data.add_child(
+                consts::IFLA_MACVLAN_MODE,
+                u32::from(*mode).to_ne_bytes().to_vec(),
+            );
+
+            link_info.add_child_from_attr(data);
+        }
+        Kind::Bond {
+            attrs: _,
+            mode,
+            miimon,
+        } => {
+            let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+
+            data.add_child(consts::IFLA_BOND_MODE, vec![u8::from(*mode)]);
+
+            if let Some(miimon) = miimon {
+                data.add_child(consts::IFLA_BOND_MIIMON, miimon.to_ne_bytes().to_vec());
+            }
+
+            link_info.add_child_from_attr(data);
+        }
+        Kind::Vxlan {
+            attrs: _,
+            vxlan_id,
+            vtep_dev_index,
+            group,
+            local,
+            port,
+            learning,
+        } => {
+            let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+
+            data.add_child(consts::IFLA_VXLAN_ID, vxlan_id.to_ne_bytes().to_vec());
+
+            if let Some(vtep_dev_index) = vtep_dev_index {
+                data.add_child(
+                    consts::IFLA_VXLAN_LINK,
+                    vtep_dev_index.to_ne_bytes().to_vec(),
+                );
+            }
+
+            match group {
+                Some(IpAddr::V4(ip)) => {
+                    data.add_child(consts::IFLA_VXLAN_GROUP, ip.octets().to_vec())
+                }
+                Some(IpAddr::V6(ip)) => {
+                    data.add_child(consts::IFLA_VXLAN_GROUP6, ip.octets().to_vec())
+                }
+                None => {}
+            }
+
+            match local {
+                Some(IpAddr::V4(ip)) => {
+                    data.add_child(consts::IFLA_VXLAN_LOCAL, ip.octets().to_vec())
+                }
+                Some(IpAddr::V6(ip)) => {
+                    data.add_child(consts::IFLA_VXLAN_LOCAL6, ip.octets().to_vec())
+                }
+                None => {}
+            }
+
+            // The port is in network byte order
+            if let Some(port) = port {
+                data.add_child(consts::IFLA_VXLAN_PORT, port.to_be_bytes().to_vec());
+            }
+
+            if let Some(learning) = learning {
+                data.add_child(consts::IFLA_VXLAN_LEARNING, vec![*learning as u8]);
+            }
+
+            link_info.add_child_from_attr(data);
+        }
+        Kind::Gre {
+            attrs: _,
+            local,
+            remote,
+            ttl,
+            ikey,
+            okey,
+        } => {
+            let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+
+            for (rta_type, addr) in [
+                (consts::IFLA_GRE_LOCAL, local),
+                (consts::IFLA_GRE_REMOTE, remote),
+            ] {
+                match addr {
+                    IpAddr::V4(ip) => data.add_child(rta_type, ip.octets().to_vec()),
+                    IpAddr::V6(_) => {
+                        return Err(Error::FamilyMismatch(
+                            "gre tunnel endpoints must be IPv4 addresses".to_string(),
+                        )
+                        .into())
+                    }
+                }
+            }
+
+            if let Some(ttl) = ttl {
+                data.add_child(consts::IFLA_GRE_TTL, vec![*ttl]);
+            }
+
+            // Keys and flags are in network byte order, a key is only used with GRE_KEY set
+            for (flags_type, key_type, key) in [
+                (consts::IFLA_GRE_IFLAGS, consts::IFLA_GRE_IKEY, ikey),
+                (consts::IFLA_GRE_OFLAGS, consts::IFLA_GRE_OKEY, okey),
+            ] {
+                if let Some(key) = key {
+                    data.add_child(flags_type, consts::GRE_KEY.to_be_bytes().to_vec());
+                    data.add_child(key_type, key.to_be_bytes().to_vec());
+                }
+            }
+
+            link_info.add_child_from_attr(data);
+        }
+        Kind::Ipip {
+            attrs: _,
+            local,
+            remote,
+            ttl,
+        }
+        | Kind::Sit {
+            attrs: _,
+            local,
+            remote,
+            ttl,
+        } => {
+            let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+
+            for (rta_type, addr) in [
+                (consts::IFLA_IPTUN_LOCAL, local),
+                (consts::IFLA_IPTUN_REMOTE, remote),
+            ] {
+                match addr {
+                    IpAddr::V4(ip) => data.add_child(rta_type, ip.octets().to_vec()),
+                    IpAddr::V6(_) => {
+                        return Err(Error::FamilyMismatch(format!(
+                            "{} tunnel endpoints must be IPv4 addresses",
+                            link.link_type()
+                        ))
+                        .into())
+                    }
+                }
+            }
+
+            if let Some(ttl) = ttl {
+                data.add_child(consts::IFLA_IPTUN_TTL, vec![*ttl]);
+            }
+
+            link_info.add_child_from_attr(data);
+        }
+        Kind::Netkit {
+            attrs: _,
+            peer_name,
+            policy,
+            mode,
+        } => {
+            let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+            let mut peer_info =
+                Box::new(NetlinkRouteAttr::new(consts::IFLA_NETKIT_PEER_INFO, vec![]));
+
+            peer_info.add_child_from_attr(Box::new(InfoMessage::new(libc::AF_UNSPEC)));
+            peer_info.add_child(libc::IFLA_IFNAME, zero_terminated(peer_name));
+
+            data.add_child_from_attr(peer_info);
+
+            if let Some(policy) = policy {
+                data.add_child(
+                    consts::IFLA_NETKIT_POLICY,
+                    u32::from(*policy).to_ne_bytes().to_vec(),
+                );
+            }
+
+            if let Some(mode) = mode {
+                data.add_child(
+                    consts::IFLA_NETKIT_MODE,
+                    u32::from(*mode).to_ne_bytes().to_vec(),
+                );
+            }
+
+            link_info.add_child_from_attr(data);
+        }
+        Kind::Generic { info_data, .. } if !info_data.is_empty() => {
+            link_info.add_child(libc::IFLA_INFO_DATA, info_data.clone());
+        }
+        _ => {}
+    }
 
     req.add_data(link_info);
 
@@ -536,11 +1637,18 @@ pub fn link_del(index: i32) -> Result<NetlinkRequest> {
     Ok(req)
 }
 
+/// Build an `RTM_GETLINK` request for a single link.
+/// The link is looked up by name if `attr.name` is set and by index otherwise,
+/// so the kernel returns exactly one link.
 pub fn link_get(attr: &LinkAttrs) -> Result<NetlinkRequest> {
+    if attr.name.is_empty() && attr.index == 0 {
+        bail!("cannot get a link without a name or an index");
+    }
+
     let mut req = NetlinkRequest::new(libc::RTM_GETLINK, libc::NLM_F_ACK);
     let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
 
-    if attr.index != 0 {
+    if attr.name.is_empty() {
         msg.index = attr.index;
     }
 
@@ -549,7 +1657,7 @@ pub fn link_get(attr: &LinkAttrs) -> Result<NetlinkRequest> {
     if !attr.name.is_empty() {
         let name = Box::new(NetlinkRouteAttr::new(
             libc::IFLA_IFNAME,
-            attr.name.as_bytes().to_vec(),
+            zero_terminated(&attr.name),
         ));
         req.add_data(name);
     }
@@ -564,14 +1672,145 @@ pub fn link_get(attr: &LinkAttrs) -> Result<NetlinkRequest> {
     Ok(req)
 }
 
+/// Build an `RTM_GETLINK` dump request.
+/// A non-zero `master_index` limits the dump to the links enslaved to that master,
+/// which the kernel only honors when strict checking is enabled.
+pub fn link_list(master_index: i32) -> Result<NetlinkRequest> {
+    let mut req = NetlinkRequest::new(libc::RTM_GETLINK, libc::NLM_F_DUMP);
+    let msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+
+    req.add_data(msg);
+
+    if master_index != 0 {
+        let master = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_MASTER,
+            master_index.to_ne_bytes().to_vec(),
+        ));
+        req.add_data(master);
+    }
+
+    let ext_mask = Box::new(NetlinkRouteAttr::new(
+        libc::IFLA_EXT_MASK,
+        1u32.to_ne_bytes().to_vec(),
+    ));
+
+    req.add_data(ext_mask);
+
+    Ok(req)
+}
+
 pub fn link_setup(index: i32) -> Result<NetlinkRequest> {
+    link_change_flags(index, libc::IFF_UP as u32, libc::IFF_UP as u32)
+}
+
+pub fn link_setdown(index: i32) -> Result<NetlinkRequest> {
+    link_change_flags(index, 0, libc::IFF_UP as u32)
+}
+
+/// Build an `RTM_NEWLINK` request changing the flags selected by `change` to `flags`.
+/// Attributes (e.g. `IFLA_MTU`) can be added to the returned request
+/// so that flags and attributes are changed in a single message.
+pub fn link_change_flags(index: i32, flags: u32, change: u32) -> Result<NetlinkRequest> {
     let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
     let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
     msg.index = index;
-    msg.flags = libc::IFF_UP as u32;
-    msg.change = libc::IFF_UP as u32;
+    msg.flags = flags;
+    msg.change = change;
+
+    req.add_data(msg);
+
+    Ok(req)
+}
+
+pub fn link_set_mtu(index: i32, mtu: u32) -> Result<NetlinkRequest> {
+    link_set_attr(index, libc::IFLA_MTU, mtu.to_ne_bytes().to_vec())
+}
+
+pub fn link_set_txqlen(index: i32, qlen: u32) -> Result<NetlinkRequest> {
+    link_set_attr(index, libc::IFLA_TXQLEN, qlen.to_ne_bytes().to_vec())
+}
+
+pub fn link_set_gso_max_size(index: i32, size: u32) -> Result<NetlinkRequest> {
+    link_set_attr(index, libc::IFLA_GSO_MAX_SIZE, size.to_ne_bytes().to_vec())
+}
+
+pub fn link_set_gso_ipv4_max_size(index: i32, size: u32) -> Result<NetlinkRequest> {
+    link_set_attr(
+        index,
+        consts::IFLA_GSO_IPV4_MAX_SIZE,
+        size.to_ne_bytes().to_vec(),
+    )
+}
+
+pub fn link_set_gro_ipv4_max_size(index: i32, size: u32) -> Result<NetlinkRequest> {
+    link_set_attr(
+        index,
+        consts::IFLA_GRO_IPV4_MAX_SIZE,
+        size.to_ne_bytes().to_vec(),
+    )
+}
+
+pub fn link_set_gso_max_segs(index: i32, segs: u32) -> Result<NetlinkRequest> {
+    link_set_attr(index, libc::IFLA_GSO_MAX_SEGS, segs.to_ne_bytes().to_vec())
+}
+
+/// Rename a link. Most drivers refuse to rename a link that is up.
+pub fn link_set_name(index: i32, name: &str) -> Result<NetlinkRequest> {
+    // IFNAMSIZ includes the terminating NUL
+    if name.is_empty() || name.len() >= libc::IFNAMSIZ {
+        bail!(
+            "invalid name {name:?}: must be 1 to {} bytes",
+            libc::IFNAMSIZ - 1
+        );
+    }
+
+    link_set_attr(index, libc::IFLA_IFNAME, zero_terminated(name))
+}
+
+/// Set the alias of a link.
+/// An empty alias is sent as a zero-length `IFLA_IFALIAS`, which clears it.
+pub fn link_set_alias(index: i32, alias: &str) -> Result<NetlinkRequest> {
+    // IFALIASZ includes the terminating NUL
+    if alias.len() >= consts::IFALIASZ {
+        bail!(
+            "alias is {} bytes, the maximum is {}",
+            alias.len(),
+            consts::IFALIASZ - 1
+        );
+    }
+
+    link_set_attr(index, libc::IFLA_IFALIAS, alias.as_bytes().to_vec())
+}
+
+/// Set the hardware address of a link.
+pub fn link_set_hw_addr(index: i32, hw_addr: &[u8]) -> Result<NetlinkRequest> {
+    if hw_addr.is_empty() {
+        bail!("hardware address must not be empty");
+    }
+
+    link_set_attr(index, libc::IFLA_ADDRESS, hw_addr.to_vec())
+}
+
+/// Move a link into the network namespace referred to by `fd`,
+/// e.g. an open `/proc/$pid/ns/net` or `/run/netns/$name`.
+pub fn link_set_ns_fd(index: i32, fd: RawFd) -> Result<NetlinkRequest> {
+    link_set_attr(index, libc::IFLA_NET_NS_FD, fd.to_ne_bytes().to_vec())
+}
+
+/// Move a link into the network namespace of the process `pid`.
+pub fn link_set_ns_pid(index: i32, pid: i32) -> Result<NetlinkRequest> {
+    link_set_attr(index, libc::IFLA_NET_NS_PID, pid.to_ne_bytes().to_vec())
+}
+
+fn link_set_attr(index: i32, rta_type: u16, value: Vec<u8>) -> Result<NetlinkRequest> {
+    let mut req = NetlinkRequest::new(libc::RTM_SETLINK, libc::NLM_F_ACK);
+    let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+    msg.index = index;
+
+    let data = Box::new(NetlinkRouteAttr::new(rta_type, value));
 
     req.add_data(msg);
+    req.add_data(data);
 
     Ok(req)
 }
@@ -716,6 +1955,618 @@ mod tests {
         0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00,
     ];
 
+    #[test]
+    fn test_link_change_flags_with_attrs() {
+        let mut req = link_change_flags(7, libc::IFF_UP as u32, libc::IFF_UP as u32).unwrap();
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_MTU,
+            1400u32.to_ne_bytes().to_vec(),
+        )));
+
+        let buf = req.serialize().unwrap();
+        assert_eq!(buf.len(), 40);
+        assert_eq!(
+            u16::from_ne_bytes(buf[4..6].try_into().unwrap()),
+            libc::RTM_NEWLINK
+        );
+
+        let msg = InfoMessage::deserialize(&buf[16..]).unwrap();
+        assert_eq!(msg.index, 7);
+        assert_eq!(msg.flags, libc::IFF_UP as u32);
+        assert_eq!(msg.change, libc::IFF_UP as u32);
+
+        let attrs = NetlinkRouteAttr::map(&buf[32..]).unwrap();
+        assert_eq!(attrs[&libc::IFLA_MTU], 1400u32.to_ne_bytes().to_vec());
+    }
+
+    #[test]
+    fn test_link_info_kind() {
+        let buf = link_new(&Kind::Dummy(LinkAttrs::new("foo")), libc::NLM_F_CREATE)
+            .unwrap()
+            .serialize()
+            .unwrap();
+
+        let attrs = NetlinkRouteAttr::map(&buf[32..]).unwrap();
+        let info = NetlinkRouteAttr::map(&attrs[&libc::IFLA_LINKINFO]).unwrap();
+        assert_eq!(info[&libc::IFLA_INFO_KIND], b"dummy\0");
+    }
+
+    #[test]
+    fn test_link_deserialize_bridge_protinfo() {
+        let mut msg = InfoMessage::new(libc::AF_BRIDGE);
+        msg.index = 5;
+
+        let mut prot_info =
+            NetlinkRouteAttr::new(libc::IFLA_PROTINFO | consts::NLA_F_NESTED, vec![]);
+        prot_info.add_child(consts::IFLA_BRPORT_STATE, vec![consts::BR_STATE_FORWARDING]);
+        prot_info.add_child(consts::IFLA_BRPORT_PRIORITY, 32u16.to_ne_bytes().to_vec());
+        prot_info.add_child(consts::IFLA_BRPORT_COST, 2u32.to_ne_bytes().to_vec());
+
+        let mut buf = msg.serialize().unwrap();
+        buf.extend(
+            NetlinkRouteAttr::new(libc::IFLA_IFNAME, zero_terminated("foo"))
+                .serialize()
+                .unwrap(),
+        );
+        buf.extend(prot_info.serialize().unwrap());
+
+        let link = link_deserialize(&buf).unwrap();
+        assert_eq!(link.attrs().index, 5);
+        assert_eq!(link.attrs().name, "foo");
+        assert_eq!(
+            link.attrs().slave,
+            Some(SlaveInfo::Bridge {
+                state: BridgePortState::Forwarding,
+                priority: Some(32),
+                cost: Some(2),
+            })
+        );
+
+        // Truncated values read as absent
+        let mut prot_info =
+            NetlinkRouteAttr::new(libc::IFLA_PROTINFO | consts::NLA_F_NESTED, vec![]);
+        prot_info.add_child(consts::IFLA_BRPORT_STATE, vec![]);
+        prot_info.add_child(consts::IFLA_BRPORT_PRIORITY, vec![32]);
+        prot_info.add_child(consts::IFLA_BRPORT_COST, vec![2, 0]);
+
+        let mut buf = msg.serialize().unwrap();
+        buf.extend(prot_info.serialize().unwrap());
+
+        let link = link_deserialize(&buf).unwrap();
+        assert_eq!(
+            link.attrs().slave,
+            Some(SlaveInfo::Bridge {
+                state: BridgePortState::Disabled,
+                priority: None,
+                cost: None,
+            })
+        );
+    }
+
+    #[rustfmt::skip]
+    static BOND_SLAVE_LINK_INFO: [u8; 84] = [
+        0x09, 0x00, 0x04, 0x00, 0x62, 0x6f, 0x6e, 0x64, 0x00, // SlaveKind L=9,T=4,V=bond
+        0x00, 0x00, 0x00, // padding
+        0x48, 0x00, 0x05, 0x00, // SlaveData L=72,T=5
+        0x05, 0x00, 0x01, 0x00, 0x01, // State L=5,T=1,V=1 (backup)
+        0x00, 0x00, 0x00, // padding
+        0x05, 0x00, 0x02, 0x00, 0x02, // MiiStatus L=5,T=2,V=2 (down)
+        0x00, 0x00, 0x00, // padding
+        0x08, 0x00, 0x03, 0x00, 0x03, 0x00, 0x00, 0x00, // LinkFailureCount L=8,T=3,V=3
+        0x0a, 0x00, 0x04, 0x00, 0x02, 0x42, 0xac, 0x11, 0x00, 0x02, // PermHwaddr L=10,T=4
+        0x00, 0x00, // padding
+        0x06, 0x00, 0x05, 0x00, 0x00, 0x00, // QueueId L=6,T=5,V=0
+        0x00, 0x00, // padding
+        0x06, 0x00, 0x06, 0x00, 0x01, 0x00, // AdAggregatorId L=6,T=6,V=1
+        0x00, 0x00, // padding
+        0x05, 0x00, 0x07, 0x00, 0x01, // AdActorOperPortState L=5,T=7,V=1
+        0x00, 0x00, 0x00, // padding
+        0x06, 0x00, 0x08, 0x00, 0x01, 0x00, // AdPartnerOperPortState L=6,T=8,V=1
+        0x00, 0x00, // padding
+    ];
+
+    #[test]
+    fn test_link_deserialize_bond_slave() {
+        let mut msg = InfoMessage::new(libc::AF_UNSPEC);
+        msg.index = 5;
+
+        let mut buf = msg.serialize().unwrap();
+        buf.extend(
+            NetlinkRouteAttr::new(libc::IFLA_IFNAME, zero_terminated("foo"))
+                .serialize()
+                .unwrap(),
+        );
+        buf.extend(
+            NetlinkRouteAttr::new(libc::IFLA_LINKINFO, BOND_SLAVE_LINK_INFO.to_vec())
+                .serialize()
+                .unwrap(),
+        );
+
+        let link = link_deserialize(&buf).unwrap();
+        assert_eq!(link.link_type(), "device");
+        assert_eq!(
+            link.attrs().slave,
+            Some(SlaveInfo::Bond {
+                state: BondSlaveState::Backup,
+                mii_status: BondMiiStatus::Down,
+                link_failure_count: 3,
+                perm_hw_addr: vec![0x02, 0x42, 0xac, 0x11, 0x00, 0x02],
+                queue_id: 0,
+            })
+        );
+
+        // Truncated values read as absent
+        let slave_data = [
+            consts::IFLA_BOND_SLAVE_STATE,
+            consts::IFLA_BOND_SLAVE_MII_STATUS,
+            consts::IFLA_BOND_SLAVE_LINK_FAILURE_COUNT,
+            consts::IFLA_BOND_SLAVE_QUEUE_ID,
+        ]
+        .into_iter()
+        .flat_map(|t| NetlinkRouteAttr::new(t, vec![]).serialize().unwrap())
+        .collect();
+
+        let mut link_info = NetlinkRouteAttr::new(libc::IFLA_LINKINFO, vec![]);
+        link_info.add_child(libc::IFLA_INFO_SLAVE_KIND, zero_terminated("bond"));
+        link_info.add_child(libc::IFLA_INFO_SLAVE_DATA, slave_data);
+
+        let mut buf = msg.serialize().unwrap();
+        buf.extend(link_info.serialize().unwrap());
+
+        let link = link_deserialize(&buf).unwrap();
+        assert_eq!(
+            link.attrs().slave,
+            Some(SlaveInfo::Bond {
+                state: BondSlaveState::Active,
+                mii_status: BondMiiStatus::Up,
+                link_failure_count: 0,
+                perm_hw_addr: vec![],
+                queue_id: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_link_generic() {
+        let mut info_data = NetlinkRouteAttr::new(1, 4u32.to_ne_bytes().to_vec())
+            .serialize()
+            .unwrap();
+        info_data.extend(
+            NetlinkRouteAttr::new(2, 1u32.to_ne_bytes().to_vec())
+                .serialize()
+                .unwrap(),
+        );
+
+        let link = Kind::Generic {
+            attrs: LinkAttrs::new("foo"),
+            kind: "geneve".to_string(),
+            info_data: info_data.clone(),
+        };
+
+        // The request is the header followed by the link itself
+        let buf = link_new(&link, 0).unwrap().serialize().unwrap();
+        let link = link_deserialize(&buf[16..]).unwrap();
+        assert_eq!(link.link_type(), "geneve");
+        assert_eq!(link.attrs().name, "foo");
+
+        match link.kind() {
+            Kind::Generic {
+                kind,
+                info_data: data,
+                ..
+            } => {
+                assert_eq!(kind, "geneve");
+                assert_eq!(data, &info_data);
+
+                let data = NetlinkRouteAttr::map(data).unwrap();
+                assert_eq!(data[&1], 4u32.to_ne_bytes());
+                assert_eq!(data[&2], 1u32.to_ne_bytes());
+            }
+            _ => panic!("Expected generic link"),
+        }
+    }
+
+    #[test]
+    fn test_link_netkit() {
+        let link = Kind::Netkit {
+            attrs: LinkAttrs::new("foo"),
+            peer_name: "bar".to_string(),
+            policy: Some(NetkitPolicy::Blackhole),
+            mode: Some(NetkitMode::L2),
+        };
+
+        // The request is the header followed by the link itself
+        let buf = link_new(&link, 0).unwrap().serialize().unwrap();
+        let link = link_deserialize(&buf[16..]).unwrap();
+        assert_eq!(link.link_type(), "netkit");
+
+        match link.kind() {
+            Kind::Netkit { policy, mode, .. } => {
+                assert_eq!(*policy, Some(NetkitPolicy::Blackhole));
+                assert_eq!(*mode, Some(NetkitMode::L2));
+            }
+            _ => panic!("Expected netkit link"),
+        }
+    }
+
+    #[test]
+    fn test_link_deserialize_short_info_data() {
+        for len in [0, 1] {
+            let info_data = (1..=20)
+                .flat_map(|t| NetlinkRouteAttr::new(t, vec![1; len]).serialize().unwrap())
+                .collect::<Vec<_>>();
+
+            for kind in [
+                "bridge", "netkit", "vlan", "macvlan", "vxlan", "bond", "gre",
+            ] {
+                let link = Kind::Generic {
+                    attrs: LinkAttrs::new("foo"),
+                    kind: kind.to_string(),
+                    info_data: info_data.clone(),
+                };
+                let buf = link_new(&link, 0).unwrap().serialize().unwrap();
+
+                // Truncated values are an error or read as absent, never a panic
+                match link_deserialize(&buf[16..]) {
+                    Ok(link) => assert_eq!(link.link_type(), kind),
+                    Err(e) => assert!(matches!(e.downcast_ref(), Some(Error::Parse(_)))),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_link_hw_addr() {
+        let hw_addr = (0..20).collect::<Vec<u8>>();
+        let link = Kind::Device(LinkAttrs {
+            hw_addr: hw_addr.clone(),
+            ..LinkAttrs::new("foo")
+        });
+
+        // The address is only sent when creating the link
+        let buf = link_new(&link, libc::NLM_F_CREATE)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let link = link_deserialize(&buf[16..]).unwrap();
+        assert_eq!(link.attrs().hw_addr, hw_addr);
+
+        let buf = link_new(&link, 0).unwrap().serialize().unwrap();
+        let link = link_deserialize(&buf[16..]).unwrap();
+        assert!(link.attrs().hw_addr.is_empty());
+    }
+
+    #[test]
+    fn test_link_set_ns() {
+        let buf = link_set_ns_fd(4, 7).unwrap().serialize().unwrap();
+        assert_eq!(u16::from_ne_bytes([buf[4], buf[5]]), libc::RTM_SETLINK);
+        assert_eq!(i32::from_ne_bytes(buf[20..24].try_into().unwrap()), 4);
+        let attrs = NetlinkRouteAttr::map(&buf[32..]).unwrap();
+        assert_eq!(attrs[&libc::IFLA_NET_NS_FD], 7i32.to_ne_bytes());
+
+        let buf = link_set_ns_pid(4, 1234).unwrap().serialize().unwrap();
+        let attrs = NetlinkRouteAttr::map(&buf[32..]).unwrap();
+        assert_eq!(attrs[&libc::IFLA_NET_NS_PID], 1234i32.to_ne_bytes());
+        assert!(!attrs.contains_key(&libc::IFLA_NET_NS_FD));
+
+        // The peer of a veth is created directly in its namespace
+        let link = Kind::Veth {
+            attrs: LinkAttrs::new("foo"),
+            peer_name: "bar".to_string(),
+            peer_hw_addr: None,
+            peer_ns: Some(Namespace::Fd(7)),
+        };
+        let buf = link_new(&link, libc::NLM_F_CREATE)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let attrs = NetlinkRouteAttr::map(&buf[32..]).unwrap();
+        let info = NetlinkRouteAttr::map(&attrs[&libc::IFLA_LINKINFO]).unwrap();
+        let data = NetlinkRouteAttr::map(&info[&libc::IFLA_INFO_DATA]).unwrap();
+        let peer = NetlinkRouteAttr::map(&data[&consts::VETH_INFO_PEER][16..]).unwrap();
+        assert_eq!(peer[&libc::IFLA_NET_NS_FD], 7i32.to_ne_bytes());
+        assert!(!attrs.contains_key(&libc::IFLA_NET_NS_FD));
+    }
+
+    #[test]
+    fn test_link_statistics() {
+        let buf = (1..=24u64).flat_map(u64::to_ne_bytes).collect::<Vec<_>>();
+        let stats = LinkStatistics::deserialize(&buf).unwrap();
+
+        assert_eq!(stats.rx_packets, 1);
+        assert_eq!(stats.tx_packets, 2);
+        assert_eq!(stats.rx_bytes, 3);
+        assert_eq!(stats.tx_bytes, 4);
+        assert_eq!(stats.rx_errors, 5);
+        assert_eq!(stats.tx_errors, 6);
+        assert_eq!(stats.rx_dropped, 7);
+        assert_eq!(stats.tx_dropped, 8);
+
+        assert!(LinkStatistics::deserialize(&buf[..60]).is_err());
+    }
+
+    #[test]
+    fn test_macvlan_mode() {
+        for mode in [
+            MacvlanMode::Private,
+            MacvlanMode::Vepa,
+            MacvlanMode::Bridge,
+            MacvlanMode::Passthru,
+            MacvlanMode::Unknown(16),
+        ] {
+            assert_eq!(MacvlanMode::from(u32::from(mode)), mode);
+        }
+        assert_eq!(u32::from(MacvlanMode::Bridge), 4);
+    }
+
+    #[test]
+    fn test_link_vlan() {
+        let link = Kind::Vlan {
+            attrs: LinkAttrs {
+                parent_index: 2,
+                ..LinkAttrs::new("foo.100")
+            },
+            vlan_id: 100,
+            vlan_protocol: Some(0x88a8),
+        };
+
+        // The request is the header followed by the link itself
+        let buf = link_new(&link, libc::NLM_F_CREATE)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let data = NetlinkRouteAttr::map(&buf[32..]).unwrap();
+        assert_eq!(data[&libc::IFLA_LINK], 2i32.to_ne_bytes());
+
+        let link = link_deserialize(&buf[16..]).unwrap();
+        assert_eq!(link.link_type(), "vlan");
+        assert_eq!(link.attrs().parent_index, 2);
+
+        match link.kind() {
+            Kind::Vlan {
+                vlan_id,
+                vlan_protocol,
+                ..
+            } => {
+                assert_eq!(*vlan_id, 100);
+                assert_eq!(*vlan_protocol, Some(0x88a8));
+            }
+            _ => panic!("Expected vlan link"),
+        }
+    }
+
+    #[test]
+    fn test_link_attrs_builder() {
+        let attrs = LinkAttrs::builder("foo")
+            .mtu(9000)
+            .tx_queue_len(500)
+            .num_tx_queues(4)
+            .num_rx_queues(2)
+            .master_index(3)
+            .parent_index(5)
+            .hw_addr(&[0x02, 0, 0, 0, 0, 1])
+            .build();
+
+        assert_eq!(attrs.name, "foo");
+        assert_eq!(attrs.mtu, 9000);
+        assert_eq!(attrs.tx_queue_len, 500);
+        assert_eq!(attrs.num_tx_queues, 4);
+        assert_eq!(attrs.num_rx_queues, 2);
+        assert_eq!(attrs.master_index, 3);
+        assert_eq!(attrs.parent_index, 5);
+        assert_eq!(attrs.hw_addr, [0x02, 0, 0, 0, 0, 1]);
+
+        let attrs = LinkAttrs::new("bar");
+        assert_eq!(attrs.name, "bar");
+        assert_eq!(attrs.mtu, 0);
+        assert!(attrs.hw_addr.is_empty());
+    }
+
+    #[test]
+    fn test_link_wireguard() {
+        let link = Kind::Wireguard(LinkAttrs::new("wg0"));
+
+        let buf = link_new(&link, libc::NLM_F_CREATE)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let link = link_deserialize(&buf[16..]).unwrap();
+        assert_eq!(link.link_type(), "wireguard");
+        assert_eq!(link.attrs().name, "wg0");
+        assert!(matches!(link.kind(), Kind::Wireguard(_)));
+    }
+
+    #[test]
+    fn test_bond_mode() {
+        for mode in 0..=7 {
+            assert_eq!(u8::from(BondMode::from(mode)), mode);
+        }
+        assert_eq!(BondMode::from(1), BondMode::ActiveBackup);
+        assert_eq!(BondMode::from(4), BondMode::Lacp);
+        assert_eq!(BondMode::from(7), BondMode::Unknown(7));
+    }
+
+    #[test]
+    fn test_link_bond() {
+        let link = Kind::Bond {
+            attrs: LinkAttrs::new("bond0"),
+            mode: BondMode::ActiveBackup,
+            miimon: Some(100),
+        };
+
+        let buf = link_new(&link, libc::NLM_F_CREATE)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let link = link_deserialize(&buf[16..]).unwrap();
+        assert_eq!(link.link_type(), "bond");
+
+        match link.kind() {
+            Kind::Bond { mode, miimon, .. } => {
+                assert_eq!(*mode, BondMode::ActiveBackup);
+                assert_eq!(*miimon, Some(100));
+            }
+            _ => panic!("Expected bond link"),
+        }
+
+        // Slaves are enslaved at creation through their master index
+        let slave = Kind::Dummy(LinkAttrs {
+            master_index: 5,
+            ..LinkAttrs::new("foo")
+        });
+        let buf = link_new(&slave, libc::NLM_F_CREATE)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let slave = link_deserialize(&buf[16..]).unwrap();
+        assert_eq!(slave.attrs().master_index, 5);
+    }
+
+    #[test]
+    fn test_link_vxlan() {
+        let link = Kind::Vxlan {
+            attrs: LinkAttrs::new("vxlan100"),
+            vxlan_id: 100,
+            vtep_dev_index: Some(2),
+            group: Some("239.1.1.1".parse().unwrap()),
+            local: Some("2001:db8::1".parse().unwrap()),
+            port: Some(4789),
+            learning: Some(false),
+        };
+
+        let buf = link_new(&link, libc::NLM_F_CREATE)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let link = link_deserialize(&buf[16..]).unwrap();
+        assert_eq!(link.link_type(), "vxlan");
+
+        match link.kind() {
+            Kind::Vxlan {
+                vxlan_id,
+                vtep_dev_index,
+                group,
+                local,
+                port,
+                learning,
+                ..
+            } => {
+                assert_eq!(*vxlan_id, 100);
+                assert_eq!(*vtep_dev_index, Some(2));
+                assert_eq!(*group, Some("239.1.1.1".parse().unwrap()));
+                assert_eq!(*local, Some("2001:db8::1".parse().unwrap()));
+                assert_eq!(*port, Some(4789));
+                assert_eq!(*learning, Some(false));
+            }
+            _ => panic!("Expected vxlan link"),
+        }
+    }
+
+    #[test]
+    fn test_link_gre() {
+        let link = Kind::Gre {
+            attrs: LinkAttrs::new("gre1"),
+            local: "127.0.0.1".parse().unwrap(),
+            remote: "127.0.0.2".parse().unwrap(),
+            ttl: Some(64),
+            ikey: Some(42),
+            okey: None,
+        };
+
+        let buf = link_new(&link, libc::NLM_F_CREATE)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let link = link_deserialize(&buf[16..]).unwrap();
+        assert_eq!(link.link_type(), "gre");
+
+        match link.kind() {
+            Kind::Gre {
+                local,
+                remote,
+                ttl,
+                ikey,
+                okey,
+                ..
+            } => {
+                assert_eq!(*local, "127.0.0.1".parse::<IpAddr>().unwrap());
+                assert_eq!(*remote, "127.0.0.2".parse::<IpAddr>().unwrap());
+                assert_eq!(*ttl, Some(64));
+                assert_eq!(*ikey, Some(42));
+                assert_eq!(*okey, None);
+            }
+            _ => panic!("Expected gre link"),
+        }
+
+        let link = Kind::Gre {
+            attrs: LinkAttrs::new("gre1"),
+            local: "::1".parse().unwrap(),
+            remote: "127.0.0.2".parse().unwrap(),
+            ttl: None,
+            ikey: None,
+            okey: None,
+        };
+        assert!(link_new(&link, libc::NLM_F_CREATE).is_err());
+    }
+
+    #[test]
+    fn test_link_iptun() {
+        let link = Kind::Ipip {
+            attrs: LinkAttrs::new("ipip1"),
+            local: "127.0.0.1".parse().unwrap(),
+            remote: "127.0.0.2".parse().unwrap(),
+            ttl: Some(64),
+        };
+
+        let buf = link_new(&link, libc::NLM_F_CREATE)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let link = link_deserialize(&buf[16..]).unwrap();
+        assert_eq!(link.link_type(), "ipip");
+
+        match link.kind() {
+            Kind::Ipip {
+                local, remote, ttl, ..
+            } => {
+                assert_eq!(*local, "127.0.0.1".parse::<IpAddr>().unwrap());
+                assert_eq!(*remote, "127.0.0.2".parse::<IpAddr>().unwrap());
+                assert_eq!(*ttl, Some(64));
+            }
+            _ => panic!("Expected ipip link"),
+        }
+
+        let link = Kind::Sit {
+            attrs: LinkAttrs::new("sit1"),
+            local: "192.0.2.1".parse().unwrap(),
+            remote: "198.51.100.1".parse().unwrap(),
+            ttl: None,
+        };
+
+        let buf = link_new(&link, libc::NLM_F_CREATE)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let link = link_deserialize(&buf[16..]).unwrap();
+        assert_eq!(link.link_type(), "sit");
+
+        match link.kind() {
+            Kind::Sit { remote, ttl, .. } => {
+                assert_eq!(*remote, "198.51.100.1".parse::<IpAddr>().unwrap());
+                assert_eq!(*ttl, None);
+            }
+            _ => panic!("Expected sit link"),
+        }
+
+        let link = Kind::Sit {
+            attrs: LinkAttrs::new("sit1"),
+            local: "127.0.0.1".parse().unwrap(),
+            remote: "2001:db8::1".parse().unwrap(),
+            ttl: None,
+        };
+        assert!(link_new(&link, libc::NLM_F_CREATE).is_err());
+    }
+
     #[test]
     fn test_link_deserialize() {
         let link = link_deserialize(&NETLINK_MSG).unwrap();
@@ -726,6 +2577,7 @@ mod tests {
         assert_eq!(attrs.name, "docker0");
         assert_eq!(attrs.mtu, 1500);
         assert_eq!(attrs.raw_flags, 0x1003);
+        assert_eq!(attrs.slave, None);
 
         match link.kind() {
             Kind::Bridge {
@@ -734,6 +2586,7 @@ mod tests {
                 ageing_time,
                 multicast_snooping,
                 vlan_filtering,
+                ..
             } => {
                 assert_eq!(hello_time.unwrap(), 200);
                 assert_eq!(ageing_time.unwrap(), 30000);
@@ -743,4 +2596,110 @@ mod tests {
             _ => panic!("Expected bridge link"),
         }
     }
+
+    // wlp2s0: <BROADCAST,MULTICAST,UP,LOWER_UP> mtu 1500 state UP mode DORMANT,
+    // as sent on a wireless event (with IFLA_WIRELESS)
+    #[rustfmt::skip]
+    static WIRELESS_NEW_LINK_MSG: [u8; 112] = [
+        0x00, // family = AF_UNSPEC
+        0x00, // padding
+        0x01, 0x00, // type = ARPHRD_ETHER
+        0x03, 0x00, 0x00, 0x00, // index = 3
+        0x43, 0x10, 0x00, 0x00, // flags = UP|BROADCAST|RUNNING|MULTICAST
+        0x00, 0x00, 0x00, 0x00, // change
+
+        // nlas
+        0x0b, 0x00, 0x03, 0x00, 0x77, 0x6c, 0x70, 0x32, 0x73, 0x30, 0x00, // IfName L=11,T=3,V=wlp2s0
+        0x00, // padding
+        0x08, 0x00, 0x0d, 0x00, 0xe8, 0x03, 0x00, 0x00, // TxQLen L=8,T=13,V=1000
+        0x05, 0x00, 0x10, 0x00, 0x06, // OperState L=5,T=16,V=IF_OPER_UP
+        0x00, 0x00, 0x00, // padding
+        0x05, 0x00, 0x11, 0x00, 0x01, // LinkMode L=5,T=17,V=IF_LINK_MODE_DORMANT
+        0x00, 0x00, 0x00, // padding
+        0x08, 0x00, 0x04, 0x00, 0xdc, 0x05, 0x00, 0x00, // Mtu L=8,T=4,V=1500
+        0x0a, 0x00, 0x01, 0x00, 0x3c, 0xa9, 0xf4, 0x12, 0x34, 0x56, // Address L=10,T=1
+        0x00, 0x00, // padding
+        0x0c, 0x00, 0x0b, 0x00, // Wireless L=12,T=11
+        0x08, 0x00, 0x19, 0x8b, 0x00, 0x00, 0x00, 0x00, // iw_event len = 8, cmd = SIOCGIWSCAN
+        0x11, 0x00, 0x38, 0x00, // ParentDevName L=17,T=56,V=0000:02:00.0
+        0x30, 0x30, 0x30, 0x30, 0x3a, 0x30, 0x32, 0x3a, 0x30, 0x30, 0x2e, 0x30, 0x00,
+        0x00, 0x00, 0x00, // padding
+        0x08, 0x00, 0x39, 0x00, 0x70, 0x63, 0x69, 0x00, // ParentDevBusName L=8,T=57,V=pci
+    ];
+
+    #[test]
+    fn test_oper_state() {
+        assert_eq!(OperState::from(consts::IF_OPER_UP), OperState::Up);
+        assert_eq!(
+            OperState::from(consts::IF_OPER_LOWERLAYERDOWN),
+            OperState::LowerLayerDown
+        );
+        assert_eq!(OperState::from(42), OperState::Unknown);
+
+        let mut buf = vec![0; consts::IF_INFO_MSG_SIZE];
+        buf.extend([0x05, 0x00, 0x21, 0x00, 0x01, 0x00, 0x00, 0x00]); // Carrier L=5,T=33,V=1
+        let link = link_deserialize(&buf).unwrap();
+        assert_eq!(link.attrs().carrier, Some(true));
+    }
+
+    #[test]
+    fn test_link_deserialize_wireless() {
+        // Wireless devices are not rtnl link kinds, they have no IFLA_LINKINFO
+        let link = link_deserialize(&WIRELESS_NEW_LINK_MSG).unwrap();
+        assert!(matches!(link.kind(), Kind::Device(_)));
+        assert_eq!(link.link_type(), "device");
+        assert_eq!(link.attrs().name, "wlp2s0");
+        assert_eq!(link.attrs().index, 3);
+        assert_eq!(link.attrs().mtu, 1500);
+        assert_eq!(link.attrs().oper_state, 6);
+        assert_eq!(link.attrs().oper_state_enum(), OperState::Up);
+        assert_eq!(link.attrs().carrier, None);
+        assert_eq!(link.attrs().hw_addr, [0x3c, 0xa9, 0xf4, 0x12, 0x34, 0x56]);
+
+        // A truncated message is an error rather than a panic
+        assert!(link_deserialize(&WIRELESS_NEW_LINK_MSG[..100]).is_err());
+    }
+
+    #[test]
+    fn test_link_deserialize_unknown_kind() {
+        let mut buf = InfoMessage::new(libc::AF_UNSPEC).serialize().unwrap();
+        let mut link_info = NetlinkRouteAttr::new(libc::IFLA_LINKINFO, vec![]);
+        link_info.add_child(libc::IFLA_INFO_KIND, zero_terminated("geneve"));
+        buf.extend(link_info.serialize().unwrap());
+
+        let link = link_deserialize(&buf).unwrap();
+        match link.kind() {
+            Kind::Generic {
+                kind, info_data, ..
+            } => {
+                assert_eq!(kind, "geneve");
+                assert!(info_data.is_empty());
+            }
+            _ => panic!("Expected generic link"),
+        }
+    }
+
+    #[test]
+    fn test_link_list_lookup() {
+        let links: Vec<Box<dyn Link>> = vec![
+            Box::new(Kind::Device(LinkAttrs {
+                index: 1,
+                ..LinkAttrs::new("lo")
+            })),
+            Box::new(Kind::Ifb(LinkAttrs {
+                index: 4,
+                ..LinkAttrs::new("foo")
+            })),
+        ];
+
+        let links = LinkList::from(links);
+        assert_eq!(links.len(), 2);
+        assert_eq!(links.by_name("foo").unwrap().attrs().index, 4);
+        assert_eq!(links.by_index(1).unwrap().attrs().name, "lo");
+        assert!(links.by_name("bar").is_none());
+        assert!(links.by_index(2).is_none());
+
+        let names: Vec<_> = links.into_iter().map(|l| l.attrs().name.clone()).collect();
+        assert_eq!(names, ["lo", "foo"]);
+    }
 }
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use anyhow::Result;
 use serde::Serialize;
 
-use crate::{consts, request::NetlinkRequestData, utils::align_of};
+use crate::{consts, error::Error, request::NetlinkRequestData, utils::align_of};
 
 pub struct NetlinkMessage {
     pub header: NetlinkMessageHeader,
@@ -11,22 +11,162 @@ pub struct NetlinkMessage {
 }
 
 impl NetlinkMessage {
-    pub fn from(mut buf: &[u8]) -> std::io::Result<Vec<Self>> {
-        let mut msgs = Vec::new();
+    pub fn from(buf: &[u8]) -> std::io::Result<Vec<Self>> {
+        Self::iter(buf).collect()
+    }
 
-        while buf.len() >= consts::NLMSG_HDRLEN {
-            let header = unsafe { *(buf.as_ptr() as *const NetlinkMessageHeader) };
-            let len = align_of(header.nlmsg_len as usize, consts::NLMSG_ALIGNTO);
-            let data = buf[consts::NLMSG_HDRLEN..header.nlmsg_len as usize].to_vec();
+    /// Iterate over the messages of a datagram, checking each header against
+    /// the bytes left. A truncated or malformed message yields an error and ends
+    /// the iteration.
+    pub fn iter(buf: &[u8]) -> NetlinkMessageIter<'_> {
+        NetlinkMessageIter { buf }
+    }
 
-            msgs.push(Self { header, data });
-            buf = &buf[len..];
+    /// Whether the kernel applied the filter requested for this dump message.
+    /// Kernels without strict checking ignore dump filters and do not set
+    /// `NLM_F_DUMP_FILTERED`, so the results must be filtered by the caller.
+    pub fn is_dump_filtered(&self) -> bool {
+        self.header.nlmsg_flags & consts::NLM_F_DUMP_FILTERED != 0
+    }
+}
+
+pub struct NetlinkMessageIter<'a> {
+    buf: &'a [u8],
+}
+
+impl Iterator for NetlinkMessageIter<'_> {
+    type Item = std::io::Result<NetlinkMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
         }
 
-        Ok(msgs)
+        let buf = std::mem::take(&mut self.buf);
+        if buf.len() < consts::NLMSG_HDRLEN {
+            return Some(Err(invalid_data(format!(
+                "truncated netlink message header: {} bytes left",
+                buf.len()
+            ))));
+        }
+
+        let header = match NetlinkMessageHeader::deserialize(buf) {
+            Ok(header) => header,
+            Err(e) => return Some(Err(invalid_data(e.to_string()))),
+        };
+        let len = header.nlmsg_len as usize;
+        if len < consts::NLMSG_HDRLEN || len > buf.len() {
+            return Some(Err(invalid_data(format!(
+                "invalid netlink message length {len}: {} bytes left",
+                buf.len()
+            ))));
+        }
+
+        let data = buf[consts::NLMSG_HDRLEN..len].to_vec();
+        // The last message of a datagram may come without its padding
+        self.buf = &buf[align_of(len, consts::NLMSG_ALIGNTO).min(buf.len())..];
+
+        Some(Ok(NetlinkMessage { header, data }))
     }
 }
 
+fn invalid_data(msg: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
+
+/// The payload of an `NLMSG_ERROR` message: the errno, the header of the request
+/// it answers and, with extended ACKs, the reason and the offending attribute.
+#[derive(Debug)]
+pub struct ErrorMessage {
+    pub errno: i32,
+    pub request: Option<NetlinkMessageHeader>,
+    pub ext_msg: Option<String>,
+    /// Offset of the rejected attribute from the start of the request message.
+    pub offset: Option<u32>,
+    /// Type of the rejected attribute, if the request was echoed back in full.
+    pub attr_type: Option<u16>,
+}
+
+impl ErrorMessage {
+    pub fn deserialize(msg: &NetlinkMessage) -> Result<Self> {
+        let buf = &msg.data;
+        let errno = i32::from_ne_bytes(read_bytes(buf, "error message")?);
+
+        let mut err = Self {
+            errno,
+            request: None,
+            ext_msg: None,
+            offset: None,
+            attr_type: None,
+        };
+
+        // NLMSG_DONE only carries the errno of the dump
+        let buf = &buf[4..];
+        if msg.header.nlmsg_type != consts::NLMSG_ERROR || buf.len() < consts::NLMSG_HDRLEN {
+            return Ok(err);
+        }
+
+        let request = NetlinkMessageHeader::deserialize(buf)?;
+        err.request = Some(request);
+
+        // The request is echoed back in full unless the kernel capped it to its header
+        let capped = msg.header.nlmsg_flags & consts::NLM_F_CAPPED != 0;
+        let request_len = match capped {
+            true => consts::NLMSG_HDRLEN,
+            false => (request.nlmsg_len as usize).min(buf.len()),
+        };
+
+        if msg.header.nlmsg_flags & consts::NLM_F_ACK_TLVS == 0 {
+            return Ok(err);
+        }
+
+        let tlvs = &buf[align_of(request_len, consts::NLMSG_ALIGNTO).min(buf.len())..];
+        let attrs = NetlinkRouteAttr::map(tlvs)?;
+
+        if let Some(value) = attrs.get(&consts::NLMSGERR_ATTR_MSG) {
+            let value = value.strip_suffix(&[0]).unwrap_or(value);
+            err.ext_msg = Some(String::from_utf8_lossy(value).into_owned());
+        }
+
+        // A malformed offset is left out rather than hiding the error it comes with
+        if let Some(value) = attrs
+            .get(&consts::NLMSGERR_ATTR_OFFS)
+            .and_then(|v| v.get(..4))
+        {
+            let offset = u32::from_ne_bytes(value.try_into()?);
+            err.offset = Some(offset);
+
+            let at = offset as usize;
+            if !capped && at + consts::RT_ATTR_SIZE <= request_len {
+                err.attr_type = Some(u16::from_ne_bytes(buf[at + 2..at + 4].try_into()?));
+            }
+        }
+
+        Ok(err)
+    }
+}
+
+impl std::fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let err_msg = unsafe { std::ffi::CStr::from_ptr(libc::strerror(-self.errno)) };
+        write!(f, "{} ({})", err_msg.to_string_lossy(), -self.errno)?;
+
+        if let Some(ext_msg) = &self.ext_msg {
+            write!(f, ": {ext_msg}")?;
+        }
+
+        match (self.attr_type, self.offset) {
+            (Some(attr_type), Some(offset)) => {
+                write!(f, " (attribute type {attr_type} at offset {offset})")
+            }
+            (None, Some(offset)) => write!(f, " (attribute at offset {offset})"),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for ErrorMessage {}
+
 #[repr(C)]
 #[derive(Clone, Copy, Serialize, Debug)]
 pub struct NetlinkMessageHeader {
@@ -47,6 +187,18 @@ impl NetlinkMessageHeader {
             nlmsg_pid: 0,
         }
     }
+
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        let b: [u8; consts::NLMSG_HDRLEN] = read_bytes(buf, "netlink message header")?;
+
+        Ok(Self {
+            nlmsg_len: u32_at(&b, 0),
+            nlmsg_type: u16_at(&b, 4),
+            nlmsg_flags: u16_at(&b, 6),
+            nlmsg_seq: u32_at(&b, 8),
+            nlmsg_pid: u32_at(&b, 12),
+        })
+    }
 }
 
 pub struct NetlinkRouteAttr {
@@ -81,7 +233,12 @@ impl NetlinkRequestData for NetlinkRouteAttr {
             }
         }
 
-        let len = buf.len();
+        // rta_len of a leaf attribute excludes its trailing padding,
+        // e.g. a 6-byte NDA_LLADDR must not be read back as 8 bytes
+        let len = match &self.children {
+            Some(_) => buf.len(),
+            None => consts::RT_ATTR_SIZE + self.value.len(),
+        };
         buf[..2].copy_from_slice(&(len as u16).to_ne_bytes());
 
         Ok(buf)
@@ -104,8 +261,9 @@ impl NetlinkRouteAttr {
         let mut attrs = HashMap::new();
 
         while buf.len() >= consts::RT_ATTR_SIZE {
-            let rt_attr = unsafe { *(buf.as_ptr() as *const RtAttr) };
-            let len = align_of(rt_attr.rta_len as usize, consts::RTA_ALIGNTO);
+            let rt_attr = RtAttr::deserialize(buf)?;
+            check_attr_len(&rt_attr, buf.len())?;
+            let len = align_of(rt_attr.rta_len as usize, consts::RTA_ALIGNTO).min(buf.len());
             let value = buf[consts::RT_ATTR_SIZE..rt_attr.rta_len as usize].to_vec();
 
             attrs.insert(rt_attr.rta_type, value);
@@ -119,8 +277,9 @@ impl NetlinkRouteAttr {
         let mut attrs = Vec::new();
 
         while buf.len() >= consts::RT_ATTR_SIZE {
-            let rt_attr = unsafe { *(buf.as_ptr() as *const RtAttr) };
-            let len = align_of(rt_attr.rta_len as usize, consts::RTA_ALIGNTO);
+            let rt_attr = RtAttr::deserialize(buf)?;
+            check_attr_len(&rt_attr, buf.len())?;
+            let len = align_of(rt_attr.rta_len as usize, consts::RTA_ALIGNTO).min(buf.len());
             let value = buf[consts::RT_ATTR_SIZE..rt_attr.rta_len as usize].to_vec();
 
             attrs.push(Self {
@@ -144,7 +303,7 @@ impl NetlinkRouteAttr {
         }
     }
 
-    pub fn add_child_from_attr(&mut self, attr: Box<(impl NetlinkRequestData + 'static)>) {
+    pub fn add_child_from_attr(&mut self, attr: Box<impl NetlinkRequestData + 'static>) {
         self.rt_attr.rta_len += attr.len() as u16;
 
         match &mut self.children {
@@ -154,6 +313,19 @@ impl NetlinkRouteAttr {
     }
 }
 
+// A malformed or truncated attribute must not make the parsers read out of bounds
+fn check_attr_len(rt_attr: &RtAttr, remaining: usize) -> Result<()> {
+    let len = rt_attr.rta_len as usize;
+    if len < consts::RT_ATTR_SIZE || len > remaining {
+        return Err(Error::Parse(format!(
+            "invalid length {len} of attribute type {} with {remaining} bytes left",
+            rt_attr.rta_type
+        ))
+        .into());
+    }
+    Ok(())
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct RtAttr {
@@ -161,6 +333,17 @@ pub struct RtAttr {
     pub rta_type: u16,
 }
 
+impl RtAttr {
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        let b: [u8; consts::RT_ATTR_SIZE] = read_bytes(buf, "attribute header")?;
+
+        Ok(Self {
+            rta_len: u16_at(&b, 0),
+            rta_type: u16_at(&b, 2),
+        })
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Default, Debug, Serialize)]
 pub struct InfoMessage {
@@ -195,7 +378,16 @@ impl InfoMessage {
     }
 
     pub fn deserialize(buf: &[u8]) -> Result<Self> {
-        Ok(unsafe { *(buf[..consts::IF_INFO_MSG_SIZE].as_ptr() as *const Self) })
+        let b: [u8; consts::IF_INFO_MSG_SIZE] = read_bytes(buf, "link message")?;
+
+        Ok(Self {
+            family: b[0],
+            _pad: b[1],
+            ifi_type: u16_at(&b, 2),
+            index: u32_at(&b, 4) as i32,
+            flags: u32_at(&b, 8),
+            change: u32_at(&b, 12),
+        })
     }
 }
 
@@ -232,7 +424,118 @@ impl AddressMessage {
     }
 
     pub fn deserialize(buf: &[u8]) -> Result<Self> {
-        Ok(unsafe { *(buf[..consts::IF_ADDR_MSG_SIZE].as_ptr() as *const Self) })
+        let b: [u8; consts::IF_ADDR_MSG_SIZE] = read_bytes(buf, "address message")?;
+
+        Ok(Self {
+            family: b[0],
+            prefix_len: b[1],
+            flags: b[2],
+            scope: b[3],
+            index: u32_at(&b, 4) as i32,
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Serialize)]
+pub struct NdMsg {
+    pub family: u8,
+    pub _pad1: u8,
+    pub _pad2: u16,
+    pub index: i32,
+    pub state: u16,
+    pub flags: u8,
+    pub neigh_type: u8,
+}
+
+impl NetlinkRequestData for NdMsg {
+    fn len(&self) -> usize {
+        consts::NEIGH_MSG_SIZE
+    }
+
+    fn is_empty(&self) -> bool {
+        self.family == 0
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| e.into())
+    }
+}
+
+impl NdMsg {
+    pub fn new(family: i32) -> Self {
+        Self {
+            family: family as u8,
+            ..Default::default()
+        }
+    }
+
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        let b: [u8; consts::NEIGH_MSG_SIZE] = read_bytes(buf, "neighbor message")?;
+
+        Ok(Self {
+            family: b[0],
+            _pad1: b[1],
+            _pad2: u16_at(&b, 2),
+            index: u32_at(&b, 4) as i32,
+            state: u16_at(&b, 8),
+            flags: b[10],
+            neigh_type: b[11],
+        })
+    }
+}
+
+/// The header of a routing policy rule message (`struct fib_rule_hdr`).
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Serialize)]
+pub struct RuleMessage {
+    pub family: u8,
+    pub dst_len: u8,
+    pub src_len: u8,
+    pub tos: u8,
+    pub table: u8,
+    pub _res1: u8,
+    pub _res2: u8,
+    pub action: u8,
+    pub flags: u32,
+}
+
+impl NetlinkRequestData for RuleMessage {
+    fn len(&self) -> usize {
+        consts::RULE_MSG_SIZE
+    }
+
+    fn is_empty(&self) -> bool {
+        self.family == 0
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| e.into())
+    }
+}
+
+impl RuleMessage {
+    pub fn new(family: u8) -> Self {
+        Self {
+            family,
+            ..Default::default()
+        }
+    }
+
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        let b: [u8; consts::RULE_MSG_SIZE] = read_bytes(buf, "rule message")?;
+
+        Ok(Self {
+            family: b[0],
+            dst_len: b[1],
+            src_len: b[2],
+            tos: b[3],
+            table: b[4],
+            _res1: b[5],
+            _res2: b[6],
+            action: b[7],
+            flags: u32_at(&b, 8),
+        })
     }
 }
 
@@ -291,6 +594,275 @@ impl RouteMessage {
     }
 
     pub fn deserialize(buf: &[u8]) -> Result<Self> {
-        Ok(unsafe { *(buf[..consts::ROUTE_MSG_SIZE].as_ptr() as *const Self) })
+        let b: [u8; consts::ROUTE_MSG_SIZE] = read_bytes(buf, "route message")?;
+
+        Ok(Self {
+            family: b[0],
+            dst_len: b[1],
+            src_len: b[2],
+            tos: b[3],
+            table: b[4],
+            protocol: b[5],
+            scope: b[6],
+            rtm_type: b[7],
+            flags: u32_at(&b, 8),
+        })
+    }
+}
+
+/// Header of a generic netlink message (`struct genlmsghdr`), followed by its attributes.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Serialize)]
+pub struct GenlMessage {
+    pub cmd: u8,
+    pub version: u8,
+    pub reserved: u16,
+}
+
+impl NetlinkRequestData for GenlMessage {
+    fn len(&self) -> usize {
+        consts::GENL_HDR_SIZE
+    }
+
+    fn is_empty(&self) -> bool {
+        self.cmd == 0
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| e.into())
+    }
+}
+
+impl GenlMessage {
+    pub fn new(cmd: u8, version: u8) -> Self {
+        Self {
+            cmd,
+            version,
+            ..Default::default()
+        }
+    }
+
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        let b: [u8; consts::GENL_HDR_SIZE] = read_bytes(buf, "generic netlink header")?;
+
+        Ok(Self {
+            cmd: b[0],
+            version: b[1],
+            reserved: u16_at(&b, 2),
+        })
+    }
+}
+
+/// Header of a next-hop of a multipath route (`struct rtnexthop`), followed by its attributes.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Serialize)]
+pub struct RtNextHop {
+    pub len: u16,
+    pub flags: u8,
+    pub hops: u8,
+    pub ifindex: i32,
+}
+
+impl NetlinkRequestData for RtNextHop {
+    fn len(&self) -> usize {
+        consts::RT_NEXTHOP_SIZE
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| e.into())
+    }
+}
+
+impl RtNextHop {
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        let b: [u8; consts::RT_NEXTHOP_SIZE] = read_bytes(buf, "next-hop")?;
+
+        Ok(Self {
+            len: u16_at(&b, 0),
+            flags: b[2],
+            hops: b[3],
+            ifindex: u32_at(&b, 4) as i32,
+        })
+    }
+}
+
+// Headers are copied out of a bounds-checked slice and read field by field, so a
+// short buffer is an error rather than an out-of-bounds or misaligned read
+fn read_bytes<const N: usize>(buf: &[u8], what: &str) -> Result<[u8; N]> {
+    buf.get(..N)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| Error::Parse(format!("truncated {what}: {} of {N} bytes", buf.len())).into())
+}
+
+fn u16_at<const N: usize>(b: &[u8; N], at: usize) -> u16 {
+    u16::from_ne_bytes([b[at], b[at + 1]])
+}
+
+fn u32_at<const N: usize>(b: &[u8; N], at: usize) -> u32 {
+    u32::from_ne_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_truncated() {
+        assert!(InfoMessage::deserialize(&[0; consts::IF_INFO_MSG_SIZE - 1]).is_err());
+        assert!(AddressMessage::deserialize(&[0; consts::IF_ADDR_MSG_SIZE - 1]).is_err());
+        assert!(NdMsg::deserialize(&[0; consts::NEIGH_MSG_SIZE - 1]).is_err());
+        assert!(RuleMessage::deserialize(&[0; consts::RULE_MSG_SIZE - 1]).is_err());
+        assert!(RouteMessage::deserialize(&[0; consts::ROUTE_MSG_SIZE - 1]).is_err());
+        assert!(GenlMessage::deserialize(&[0; consts::GENL_HDR_SIZE - 1]).is_err());
+        assert!(RtNextHop::deserialize(&[0; consts::RT_NEXTHOP_SIZE - 1]).is_err());
+        assert!(NetlinkMessageHeader::deserialize(&NETLINK_MSGS[..15]).is_err());
+        assert!(RtAttr::deserialize(&[0x08, 0x00, 0x03]).is_err());
+        assert!(InfoMessage::deserialize(&[]).is_err());
+
+        // Unaligned buffers are read as well
+        let mut msg = InfoMessage::new(libc::AF_UNSPEC);
+        msg.index = 1;
+        let buf = [vec![0], NetlinkRequestData::serialize(&msg).unwrap()].concat();
+        let msg = InfoMessage::deserialize(&buf[1..]).unwrap();
+        assert_eq!(msg.index, 1);
+
+        // An error message too short for its errno
+        let msg = NetlinkMessage {
+            header: NetlinkMessageHeader::new(consts::NLMSG_ERROR, 0),
+            data: vec![0xff, 0xff],
+        };
+        assert!(ErrorMessage::deserialize(&msg).is_err());
+    }
+
+    #[rustfmt::skip]
+    static NETLINK_MSGS: [u8; 40] = [
+        // RTM_NEWLINK
+        0x14, 0x00, 0x00, 0x00, // length = 20
+        0x10, 0x00, // type = RTM_NEWLINK
+        0x02, 0x00, // flags = NLM_F_MULTI
+        0x01, 0x00, 0x00, 0x00, // sequence number = 1
+        0x64, 0x00, 0x00, 0x00, // port id = 100
+        0x08, 0x00, 0x03, 0x00, // payload
+
+        // NLMSG_DONE
+        0x14, 0x00, 0x00, 0x00, // length = 20
+        0x03, 0x00, // type = NLMSG_DONE
+        0x02, 0x00, // flags = NLM_F_MULTI
+        0x01, 0x00, 0x00, 0x00, // sequence number = 1
+        0x64, 0x00, 0x00, 0x00, // port id = 100
+        0x00, 0x00, 0x00, 0x00, // payload
+    ];
+
+    #[test]
+    fn test_netlink_message_from() {
+        let msgs = NetlinkMessage::from(&NETLINK_MSGS).unwrap();
+
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[0].header.nlmsg_type, libc::RTM_NEWLINK);
+        assert_eq!(msgs[0].header.nlmsg_flags, libc::NLM_F_MULTI as u16);
+        assert_eq!(msgs[0].header.nlmsg_seq, 1);
+        assert_eq!(msgs[0].header.nlmsg_pid, 100);
+        assert_eq!(msgs[0].data, vec![0x08, 0x00, 0x03, 0x00]);
+        assert_eq!(msgs[1].header.nlmsg_type, consts::NLMSG_DONE);
+    }
+
+    #[test]
+    fn test_netlink_message_iter_truncated() {
+        // The second message claims more bytes than are left
+        let mut buf = NETLINK_MSGS.to_vec();
+        buf.truncate(NETLINK_MSGS.len() - 4);
+
+        let mut iter = NetlinkMessage::iter(&buf);
+        assert_eq!(
+            iter.next().unwrap().unwrap().header.nlmsg_type,
+            libc::RTM_NEWLINK
+        );
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+        assert!(NetlinkMessage::from(&buf).is_err());
+
+        // A trailer shorter than a header
+        let buf = &NETLINK_MSGS[..NETLINK_MSGS.len() - 10];
+        let msgs = NetlinkMessage::iter(buf).collect::<Vec<_>>();
+        assert_eq!(msgs.len(), 2);
+        assert!(msgs[1].is_err());
+
+        // A length below the size of the header
+        let mut buf = NETLINK_MSGS.to_vec();
+        buf[20] = 0x08;
+        let mut iter = NetlinkMessage::iter(&buf);
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[rustfmt::skip]
+    static RT_ATTRS: [u8; 28] = [
+        0x07, 0x00, 0x03, 0x00, 0x6c, 0x6f, 0x00, // L=7,T=3,V=lo
+        0x00, // padding
+        0x0c, 0x00, 0x12, 0x80, // L=12,T=18 (nested)
+        0x08, 0x00, 0x01, 0x00, 0x76, 0x65, 0x74, 0x68, // L=8,T=1,V=veth
+        0x05, 0x00, 0x10, 0x00, 0x06, // L=5,T=16,V=6
+        0x00, 0x00, 0x00, // padding
+    ];
+
+    #[test]
+    fn test_route_attr_from() {
+        let attrs = NetlinkRouteAttr::from(&RT_ATTRS).unwrap();
+
+        assert_eq!(attrs.len(), 3);
+        assert_eq!(attrs[0].rt_attr.rta_type, libc::IFLA_IFNAME);
+        assert_eq!(attrs[0].value, b"lo\0");
+        assert_eq!(
+            attrs[1].rt_attr.rta_type,
+            libc::IFLA_LINKINFO | consts::NLA_F_NESTED
+        );
+
+        let children = NetlinkRouteAttr::map(&attrs[1].value).unwrap();
+        assert_eq!(children[&libc::IFLA_INFO_KIND], b"veth");
+
+        assert_eq!(attrs[2].rt_attr.rta_type, libc::IFLA_OPERSTATE);
+        assert_eq!(attrs[2].value, vec![6]);
+    }
+
+    #[test]
+    fn test_route_attr_serialize_len() {
+        // The padding of a leaf attribute is emitted but not counted in its rta_len
+        let buf = NetlinkRouteAttr::new(libc::NDA_LLADDR, vec![0; 6])
+            .serialize()
+            .unwrap();
+        assert_eq!(buf.len(), 12);
+        assert_eq!(u16::from_ne_bytes([buf[0], buf[1]]), 10);
+
+        let attrs = NetlinkRouteAttr::from(&buf).unwrap();
+        assert_eq!(attrs[0].value.len(), 6);
+
+        // whereas a nested attribute covers its children with their padding
+        let mut attr = NetlinkRouteAttr::new(libc::IFLA_LINKINFO, vec![]);
+        attr.add_child(libc::IFLA_INFO_KIND, b"veth\0".to_vec());
+        let buf = attr.serialize().unwrap();
+        assert_eq!(buf.len(), 16);
+        assert_eq!(u16::from_ne_bytes([buf[0], buf[1]]), 16);
+    }
+
+    #[test]
+    fn test_is_dump_filtered() {
+        let mut header = NetlinkMessageHeader::new(libc::RTM_NEWROUTE, libc::NLM_F_MULTI);
+        let msg = NetlinkMessage {
+            header,
+            data: vec![],
+        };
+        assert!(!msg.is_dump_filtered());
+
+        header.nlmsg_flags |= consts::NLM_F_DUMP_FILTERED;
+        let msg = NetlinkMessage {
+            header,
+            data: vec![],
+        };
+        assert!(msg.is_dump_filtered());
     }
 }
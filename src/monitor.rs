@@ -0,0 +1,203 @@
+use std::{collections::VecDeque, time::Duration};
+
+use anyhow::Result;
+
+use crate::{
+    addr::{self, Address},
+    consts,
+    error::{self, Error},
+    link::{self, Link},
+    message::{ErrorMessage, NetlinkMessage},
+    route::{self, Route},
+    socket::NetlinkSocket,
+};
+
+/// The groups `Netlink::monitor` subscribes to: links, and IPv4 and IPv6 addresses and routes.
+pub const DEFAULT_GROUPS: [u32; 5] = [
+    libc::RTNLGRP_LINK,
+    libc::RTNLGRP_IPV4_IFADDR,
+    libc::RTNLGRP_IPV6_IFADDR,
+    libc::RTNLGRP_IPV4_ROUTE,
+    libc::RTNLGRP_IPV6_ROUTE,
+];
+
+pub enum LinkUpdate {
+    New(Box<dyn Link>),
+    Del(Box<dyn Link>),
+}
+
+#[derive(Debug)]
+pub enum AddrUpdate {
+    New(Address),
+    Del(Address),
+}
+
+#[derive(Debug)]
+pub enum RouteUpdate {
+    New(Route),
+    Del(Route),
+}
+
+pub enum Change {
+    Link(LinkUpdate),
+    Addr(AddrUpdate),
+    Route(RouteUpdate),
+}
+
+/// A change notified by the kernel, along with the multicast group it was
+/// notified to, e.g. `libc::RTNLGRP_IPV4_ROUTE` or `libc::RTNLGRP_IPV6_ROUTE` for a route.
+pub struct Update {
+    pub group: u32,
+    pub change: Change,
+}
+
+/// A socket subscribed to multicast groups, iterating over the changes the kernel
+/// notifies, like `ip monitor`. Notifications of other kinds are skipped.
+///
+/// The iteration blocks until a notification arrives, or fails with `Error::Timeout`
+/// once the timeout set with `set_timeout` expires. An error, e.g. `ENOBUFS` when
+/// notifications were dropped because they weren't read fast enough, doesn't end it.
+pub struct Monitor {
+    socket: NetlinkSocket,
+    pending: VecDeque<(NetlinkMessage, Option<u32>)>,
+}
+
+impl Monitor {
+    pub fn new(groups: &[u32]) -> Result<Self> {
+        let socket = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0)?;
+        socket.subscribe(groups)?;
+        // Report the group of each notification, even one beyond the 32 of the `nl_groups` mask
+        socket.set_pktinfo(true)?;
+
+        Ok(Self {
+            socket,
+            pending: VecDeque::new(),
+        })
+    }
+
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        Ok(self.socket.set_recv_timeout(timeout)?)
+    }
+
+    fn parse(msg: &NetlinkMessage, group: Option<u32>) -> Result<Option<Update>> {
+        if msg.header.nlmsg_type == consts::NLMSG_ERROR {
+            return Err(Error::from(ErrorMessage::deserialize(msg)?).into());
+        }
+
+        // Notifications are multicast, anything else sent to the socket isn't one
+        let Some(group) = group else {
+            return Ok(None);
+        };
+
+        let change = match msg.header.nlmsg_type {
+            libc::RTM_NEWLINK => Change::Link(LinkUpdate::New(link::link_deserialize(&msg.data)?)),
+            libc::RTM_DELLINK => Change::Link(LinkUpdate::Del(link::link_deserialize(&msg.data)?)),
+            libc::RTM_NEWADDR => Change::Addr(AddrUpdate::New(addr::addr_deserialize(&msg.data)?)),
+            libc::RTM_DELADDR => Change::Addr(AddrUpdate::Del(addr::addr_deserialize(&msg.data)?)),
+            libc::RTM_NEWROUTE => {
+                Change::Route(RouteUpdate::New(route::route_deserialize(&msg.data)?))
+            }
+            libc::RTM_DELROUTE => {
+                Change::Route(RouteUpdate::Del(route::route_deserialize(&msg.data)?))
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(Update { group, change }))
+    }
+}
+
+impl Iterator for Monitor {
+    type Item = Result<Update>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while let Some((msg, group)) = self.pending.pop_front() {
+                match Self::parse(&msg, group) {
+                    Ok(Some(update)) => return Some(Ok(update)),
+                    Ok(None) => continue,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+
+            match self.socket.recv_with_group() {
+                // Only the kernel sends notifications
+                Ok((msgs, from, group)) if from.nl_pid == consts::PID_KERNEL => self
+                    .pending
+                    .extend(msgs.into_iter().map(|msg| (msg, group))),
+                Ok(_) => continue,
+                Err(e) => return Some(Err(error::recv_error(e).into())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::Error, netlink::Netlink, test_setup};
+
+    #[test]
+    fn test_monitor() {
+        test_setup!();
+        let mut monitor = Monitor::new(&DEFAULT_GROUPS).unwrap();
+        monitor.set_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        // Changes made by another thread, in the same namespace
+        std::thread::spawn(|| {
+            let mut nl = Netlink::new().unwrap();
+            let (foo, _) = nl
+                .veth_add_pair(&link::LinkAttrs::new("foo"), "bar")
+                .unwrap();
+            nl.addr_add(&foo, &Address::new("10.0.0.1/24".parse().unwrap()))
+                .unwrap();
+        })
+        .join()
+        .unwrap();
+
+        let mut link = false;
+        let mut addr = false;
+        let mut route = false;
+
+        while !(link && addr && route) {
+            let update = monitor.next().unwrap().unwrap();
+            match update.change {
+                Change::Link(LinkUpdate::New(l)) if l.attrs().name == "foo" => {
+                    assert_eq!(update.group, libc::RTNLGRP_LINK);
+                    link = true;
+                }
+                Change::Addr(AddrUpdate::New(a)) => {
+                    assert_eq!(a.address, "10.0.0.1/24".parse().unwrap());
+                    assert_eq!(update.group, libc::RTNLGRP_IPV4_IFADDR);
+                    addr = true;
+                }
+                // The local route of the address
+                Change::Route(RouteUpdate::New(r)) => {
+                    assert_eq!(update.group, libc::RTNLGRP_IPV4_ROUTE);
+                    route |= r.dst == Some("10.0.0.1/32".parse().unwrap());
+                }
+                _ => {}
+            }
+        }
+
+        monitor
+            .set_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        let mut nl = Netlink::new().unwrap();
+        nl.link_del_by_name("foo").unwrap();
+
+        // Deleting the link deletes its address and routes as well
+        let mut deleted = false;
+        loop {
+            match monitor.next().unwrap().map(|update| update.change) {
+                Ok(Change::Link(LinkUpdate::Del(l))) if l.attrs().name == "foo" => deleted = true,
+                Ok(_) => {}
+                Err(e) => {
+                    assert_eq!(e.downcast_ref::<Error>(), Some(&Error::Timeout));
+                    break;
+                }
+            }
+        }
+        assert!(deleted);
+    }
+}
@@ -0,0 +1,351 @@
+use std::net::IpAddr;
+
+use anyhow::Result;
+
+use crate::{
+    addr::AddrFamily,
+    consts,
+    error::Error,
+    message::{NdMsg, NetlinkRouteAttr},
+    request::{NetlinkRequest, NetlinkRequestData},
+    utils::vec_to_addr,
+};
+
+pub enum NeighCmd {
+    Add,
+    Replace,
+    Del,
+}
+
+/// The state of a neighbor entry, a set of `NUD_*` flags.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NeighborState(pub u16);
+
+impl NeighborState {
+    pub const NONE: Self = Self(libc::NUD_NONE);
+    pub const INCOMPLETE: Self = Self(libc::NUD_INCOMPLETE);
+    pub const REACHABLE: Self = Self(libc::NUD_REACHABLE);
+    pub const STALE: Self = Self(libc::NUD_STALE);
+    pub const DELAY: Self = Self(libc::NUD_DELAY);
+    pub const PROBE: Self = Self(libc::NUD_PROBE);
+    pub const FAILED: Self = Self(libc::NUD_FAILED);
+    pub const NOARP: Self = Self(libc::NUD_NOARP);
+    pub const PERMANENT: Self = Self(libc::NUD_PERMANENT);
+
+    /// Whether all the flags of `other` are set.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether the neighbor is known to be reachable, either confirmed recently
+    /// or configured statically.
+    pub fn is_reachable(&self) -> bool {
+        self.0 & (libc::NUD_REACHABLE | libc::NUD_PERMANENT | libc::NUD_NOARP) != 0
+    }
+
+    /// Whether the resolution of the neighbor failed.
+    pub fn is_failed(&self) -> bool {
+        self.contains(Self::FAILED)
+    }
+
+    /// Whether the state is one of the states of `filter`. As `NONE` has no flag
+    /// set, it only matches a state that has none either.
+    pub fn matches(&self, filter: Self) -> bool {
+        if filter == Self::NONE {
+            *self == Self::NONE
+        } else {
+            self.0 & filter.0 != 0
+        }
+    }
+}
+
+impl From<u16> for NeighborState {
+    fn from(state: u16) -> Self {
+        Self(state)
+    }
+}
+
+impl From<NeighborState> for u16 {
+    fn from(state: NeighborState) -> Self {
+        state.0
+    }
+}
+
+impl std::ops::BitOr for NeighborState {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+#[derive(Debug)]
+pub struct Neighbor {
+    pub index: i32,
+    pub family: u8,
+    pub state: NeighborState,
+    pub neigh_type: u8,
+    pub flags: u8,
+    pub ip: IpAddr,
+    /// The link-layer address, `None` while it is unresolved (e.g. `INCOMPLETE` or `FAILED`).
+    pub ll_addr: Option<Vec<u8>>,
+    pub cache_info: Option<NeighCacheInfo>,
+}
+
+/// The cache statistics of a neighbor entry (`NDA_CACHEINFO`), as shown by `ip -s neigh`.
+/// The ages are in clock ticks (`USER_HZ`) elapsed since the event.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NeighCacheInfo {
+    /// Time since the entry was last confirmed reachable.
+    pub confirmed: u32,
+    /// Time since the entry was last used.
+    pub used: u32,
+    /// Time since the entry was last updated.
+    pub updated: u32,
+    pub refcnt: u32,
+}
+
+impl NeighCacheInfo {
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        if buf.len() < consts::NDA_CACHEINFO_SIZE {
+            return Err(Error::Parse(format!(
+                "neighbor cache info is too short: {} bytes",
+                buf.len()
+            ))
+            .into());
+        }
+
+        let field = |i: usize| u32::from_ne_bytes(buf[i * 4..(i + 1) * 4].try_into().unwrap());
+
+        Ok(Self {
+            confirmed: field(0),
+            used: field(1),
+            updated: field(2),
+            refcnt: field(3),
+        })
+    }
+}
+
+impl Neighbor {
+    /// Create a permanent neighbor entry, like `ip neigh add` does by default.
+    pub fn new(index: i32, ip: IpAddr, ll_addr: &[u8]) -> Self {
+        Self {
+            index,
+            family: 0,
+            state: NeighborState::PERMANENT,
+            neigh_type: 0,
+            flags: 0,
+            ip,
+            ll_addr: Some(ll_addr.to_vec()),
+            cache_info: None,
+        }
+    }
+}
+
+pub fn neigh_deserialize(buf: &[u8]) -> Result<Neighbor> {
+    let nd_msg = NdMsg::deserialize(buf)?;
+    let rt_attrs = NetlinkRouteAttr::from(&buf[nd_msg.len()..])?;
+
+    let mut ip = None;
+    let mut ll_addr = None;
+    let mut cache_info = None;
+
+    for attr in rt_attrs {
+        match attr.rt_attr.rta_type {
+            libc::NDA_DST => {
+                ip = Some(vec_to_addr(attr.value)?);
+            }
+            libc::NDA_LLADDR => {
+                ll_addr = Some(attr.value);
+            }
+            libc::NDA_CACHEINFO => {
+                cache_info = Some(NeighCacheInfo::deserialize(&attr.value)?);
+            }
+            // TODO: more types
+            _ => {}
+        }
+    }
+
+    // e.g. the bridge forwarding entries of an AF_UNSPEC dump, which aren't neighbors
+    let Some(ip) = ip else {
+        return Err(Error::Parse("neighbor without a destination address".to_string()).into());
+    };
+
+    Ok(Neighbor {
+        index: nd_msg.index,
+        family: nd_msg.family,
+        state: nd_msg.state.into(),
+        neigh_type: nd_msg.neigh_type,
+        flags: nd_msg.flags,
+        ip,
+        ll_addr,
+        cache_info,
+    })
+}
+
+pub fn neigh_handle(cmd: NeighCmd, neigh: &Neighbor) -> Result<NetlinkRequest> {
+    let (proto, flags) = match cmd {
+        NeighCmd::Add => (
+            libc::RTM_NEWNEIGH,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        ),
+        NeighCmd::Replace => (
+            libc::RTM_NEWNEIGH,
+            libc::NLM_F_CREATE | libc::NLM_F_REPLACE | libc::NLM_F_ACK,
+        ),
+        NeighCmd::Del => (libc::RTM_DELNEIGH, libc::NLM_F_ACK),
+    };
+
+    let (family, dst_data) = match neigh.ip {
+        IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec()),
+        IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec()),
+    };
+
+    let mut req = NetlinkRequest::new(proto, flags);
+
+    let mut msg = Box::new(NdMsg::new(family));
+    msg.index = neigh.index;
+    msg.state = neigh.state.into();
+    msg.flags = neigh.flags;
+    msg.neigh_type = neigh.neigh_type;
+
+    req.add_data(msg);
+    req.add_data(Box::new(NetlinkRouteAttr::new(libc::NDA_DST, dst_data)));
+
+    if let Some(ll_addr) = &neigh.ll_addr {
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            libc::NDA_LLADDR,
+            ll_addr.clone(),
+        )));
+    }
+
+    Ok(req)
+}
+
+pub fn neigh_list(family: AddrFamily, index: i32) -> Result<NetlinkRequest> {
+    let mut req = NetlinkRequest::new(libc::RTM_GETNEIGH, libc::NLM_F_DUMP);
+    let msg = Box::new(NdMsg::new(family as i32));
+    req.add_data(msg);
+
+    // The kernel filters the dump by link with NDA_IFINDEX, not with the index in the header
+    if index != 0 {
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            libc::NDA_IFINDEX,
+            index.to_ne_bytes().to_vec(),
+        )));
+    }
+
+    Ok(req)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 10.0.0.2 lladdr 02:42:ac:11:00:02 REACHABLE
+    #[rustfmt::skip]
+    static ND_NEW_NEIGH_MSG: [u8; 52] = [
+        0x02, // family = AF_INET
+        0x00, 0x00, 0x00, // padding
+        0x02, 0x00, 0x00, 0x00, // interface index = 2
+        0x02, 0x00, // state = NUD_REACHABLE
+        0x00, // flags
+        0x01, // type = RTN_UNICAST
+
+        // nlas
+        0x08, 0x00, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x02, // Dst L=8,T=1,V=10.0.0.2
+        0x0a, 0x00, 0x02, 0x00, 0x02, 0x42, 0xac, 0x11, 0x00, 0x02, // LLAddr L=10,T=2
+        0x00, 0x00, // padding
+        0x14, 0x00, 0x03, 0x00, // CacheInfo L=20,T=3
+        0x64, 0x00, 0x00, 0x00, // confirmed = 100
+        0xc8, 0x00, 0x00, 0x00, // used = 200
+        0x2c, 0x01, 0x00, 0x00, // updated = 300
+        0x01, 0x00, 0x00, 0x00, // refcnt = 1
+    ];
+
+    #[test]
+    fn test_neigh_deserialize() {
+        let neigh = neigh_deserialize(&ND_NEW_NEIGH_MSG).unwrap();
+        assert_eq!(neigh.index, 2);
+        assert_eq!(neigh.state, NeighborState::REACHABLE);
+        assert!(neigh.state.is_reachable());
+        assert!(!neigh.state.is_failed());
+        assert_eq!(neigh.ip, "10.0.0.2".parse::<IpAddr>().unwrap());
+        assert_eq!(
+            neigh.ll_addr,
+            Some(vec![0x02, 0x42, 0xac, 0x11, 0x00, 0x02])
+        );
+        assert_eq!(
+            neigh.cache_info,
+            Some(NeighCacheInfo {
+                confirmed: 100,
+                used: 200,
+                updated: 300,
+                refcnt: 1,
+            })
+        );
+
+        // An unresolved neighbor has no link-layer address
+        let neigh = neigh_deserialize(&ND_NEW_NEIGH_MSG[..20]).unwrap();
+        assert_eq!(neigh.ip, "10.0.0.2".parse::<IpAddr>().unwrap());
+        assert_eq!(neigh.ll_addr, None);
+        assert_eq!(neigh.cache_info, None);
+
+        assert!(neigh_deserialize(&ND_NEW_NEIGH_MSG[..12]).is_err());
+    }
+
+    #[test]
+    fn test_neighbor_state() {
+        let state = NeighborState::from(libc::NUD_FAILED);
+        assert!(state.is_failed());
+        assert!(!state.is_reachable());
+
+        for state in [
+            NeighborState::STALE,
+            NeighborState::DELAY,
+            NeighborState::PROBE,
+        ] {
+            assert!(!state.is_reachable());
+            assert!(!state.is_failed());
+        }
+
+        let state = NeighborState::PERMANENT | NeighborState::NOARP;
+        assert!(state.contains(NeighborState::NOARP));
+        assert!(!state.contains(NeighborState::STALE));
+        assert!(state.is_reachable());
+        assert_eq!(u16::from(state), libc::NUD_PERMANENT | libc::NUD_NOARP);
+
+        assert!(state.matches(NeighborState::NOARP | NeighborState::STALE));
+        assert!(!state.matches(NeighborState::STALE));
+        assert!(!state.matches(NeighborState::NONE));
+        assert!(NeighborState::NONE.matches(NeighborState::NONE));
+        assert!(!NeighborState::NONE.matches(NeighborState::STALE));
+    }
+
+    #[test]
+    fn test_neigh_handle() {
+        let neigh = Neighbor::new(
+            2,
+            "10.0.0.2".parse().unwrap(),
+            &[0x02, 0x42, 0xac, 0x11, 0x00, 0x02],
+        );
+
+        let buf = neigh_handle(NeighCmd::Replace, &neigh)
+            .unwrap()
+            .serialize()
+            .unwrap();
+
+        assert_eq!(
+            u16::from_ne_bytes(buf[4..6].try_into().unwrap()),
+            libc::RTM_NEWNEIGH
+        );
+
+        // The request is the header followed by the neighbor itself
+        let res = neigh_deserialize(&buf[16..]).unwrap();
+        assert_eq!(res.index, 2);
+        assert_eq!(res.family, libc::AF_INET as u8);
+        assert_eq!(res.state, NeighborState::PERMANENT);
+        assert_eq!(res.ip, neigh.ip);
+        assert_eq!(res.ll_addr, neigh.ll_addr);
+    }
+}
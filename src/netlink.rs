@@ -1,12 +1,24 @@
-use std::{collections::HashMap, net::IpAddr};
+use std::{
+    collections::HashMap,
+    fs::File,
+    net::IpAddr,
+    os::fd::{AsRawFd, RawFd},
+    path::Path,
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use nix::sched::{setns, CloneFlags};
 
 use crate::{
-    addr::{AddrCmd, AddrFamily, Address},
+    addr::{AddrCmd, AddrFamily, AddrFilter, Address},
     handle::SocketHandle,
-    link::{Link, LinkAttrs},
+    link::{Link, LinkAttrs, LinkList},
+    monitor::{self, Monitor},
+    neigh::{NeighCmd, Neighbor, NeighborState},
     route::{Route, RtCmd, RtFilter},
+    rule::{Rule, RuleCmd},
+    wireguard::WireguardConfig,
 };
 
 const SUPPORTED_PROTOCOLS: [i32; 1] = [libc::NETLINK_ROUTE];
@@ -16,6 +28,8 @@ const SUPPORTED_PROTOCOLS: [i32; 1] = [libc::NETLINK_ROUTE];
 pub struct Netlink {
     /// A map of protocol to socket.
     pub sockets: HashMap<i32, SocketHandle>,
+    /// Link indexes by name, see `link_index_cached`.
+    link_cache: HashMap<String, i32>,
 }
 
 impl Netlink {
@@ -23,6 +37,9 @@ impl Netlink {
     /// This function creates a new socket for each supported protocol.
     /// Currently, only `NETLINK_ROUTE` is supported.
     ///
+    /// If netlink is not available on this system, the returned error
+    /// downcasts to [`Error::Unsupported`](crate::error::Error::Unsupported).
+    ///
     /// # Examples
     ///
     /// ```
@@ -39,11 +56,156 @@ impl Netlink {
             .map(|proto| Ok((*proto, SocketHandle::new(*proto)?)))
             .collect::<Result<HashMap<i32, SocketHandle>>>()?;
 
-        Ok(Self { sockets })
+        Ok(Self {
+            sockets,
+            link_cache: HashMap::new(),
+        })
+    }
+
+    /// Create a new Netlink instance operating in the network namespace at `path`,
+    /// e.g. `/var/run/netns/$name` or `/proc/$pid/ns/net`.
+    ///
+    /// The calling thread enters the namespace to create the sockets and is moved back
+    /// to its previous namespace right after: the sockets keep operating in the namespace
+    /// they were created in, so the thread doesn't have to stay there.
+    ///
+    /// `setns(2)` only affects the calling thread, and the thread is in the target
+    /// namespace while the sockets are created, so other code on that thread (e.g. tasks
+    /// of an async runtime) should not open sockets or files concurrently.
+    /// This requires `CAP_SYS_ADMIN`.
+    ///
+    /// Equivalent to: `ip netns exec $name ...`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::{fs::File, os::fd::AsRawFd};
+    ///
+    /// use lnwasi::{link::LinkAttrs, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let ns = File::open("/proc/thread-self/ns/net").unwrap();
+    /// let mut nl = Netlink::new().unwrap();
+    /// nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// // Move to another namespace, which doesn't have foo
+    /// nix::sched::unshare(nix::sched::CloneFlags::CLONE_NEWNET).unwrap();
+    ///
+    /// let path = format!("/proc/self/fd/{}", ns.as_raw_fd());
+    /// let mut nl = Netlink::new_in_namespace(path.as_ref()).unwrap();
+    /// assert!(nl.link_get(&LinkAttrs::new("foo")).is_ok());
+    ///
+    /// let mut nl = Netlink::new().unwrap();
+    /// assert!(nl.link_get(&LinkAttrs::new("foo")).is_err());
+    /// ```
+    pub fn new_in_namespace(path: &Path) -> Result<Self> {
+        let current = File::open("/proc/thread-self/ns/net")
+            .context("failed to open the current network namespace")?;
+        let target = File::open(path)
+            .with_context(|| format!("failed to open network namespace {}", path.display()))?;
+
+        setns(target.as_raw_fd(), CloneFlags::CLONE_NEWNET)
+            .with_context(|| format!("failed to enter network namespace {}", path.display()))?;
+
+        let nl = Self::new();
+
+        setns(current.as_raw_fd(), CloneFlags::CLONE_NEWNET)
+            .context("failed to restore the previous network namespace")?;
+
+        nl
+    }
+
+    /// Enable or disable strict checking of requests (`NETLINK_GET_STRICT_CHK`).
+    /// With strict checking, the kernel validates requests more thoroughly
+    /// and applies the filters of dump requests (e.g. listing the routes or
+    /// addresses of a single link) itself instead of returning everything.
+    ///
+    /// `route_list` and `addr_list` enable it for their own dumps anyway, so this
+    /// is only needed for the stricter validation of the other requests.
+    ///
+    /// This requires Linux 4.20 or later.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{addr::AddrFamily, link::LinkAttrs, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// nl.set_strict_check(true).unwrap();
+    ///
+    /// let lo = nl.link_get(&LinkAttrs::new("lo")).unwrap();
+    /// nl.link_setup(&lo).unwrap();
+    ///
+    /// let routes = nl.route_list(&lo, AddrFamily::V4).unwrap();
+    /// assert!(routes.len() > 0);
+    /// ```
+    pub fn set_strict_check(&mut self, on: bool) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .set_strict_check(on)
+    }
+
+    /// Set how long a request may wait for its response.
+    /// When the timeout expires, the request fails with `Error::Timeout`
+    /// instead of blocking forever. By default, there is no timeout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use lnwasi::{link::LinkAttrs, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// nl.set_timeout(Some(Duration::from_secs(1))).unwrap();
+    ///
+    /// let lo = nl.link_get(&LinkAttrs::new("lo")).unwrap();
+    /// ```
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .set_timeout(timeout);
+        Ok(())
+    }
+
+    /// Subscribe to the changes of links, addresses and routes.
+    /// The returned monitor iterates over the changes as the kernel notifies them,
+    /// from its own socket in the network namespace of the calling thread.
+    /// Use `Monitor::new` to choose the groups to subscribe to.
+    ///
+    /// Equivalent to: `ip monitor link address route`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::LinkAttrs, monitor::{Change, LinkUpdate}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let mut monitor = nl.monitor().unwrap();
+    ///
+    /// nl.link_setup(&LinkAttrs::new("lo")).unwrap();
+    ///
+    /// match monitor.next().unwrap().unwrap().change {
+    ///     Change::Link(LinkUpdate::New(link)) => assert_eq!(link.attrs().name, "lo"),
+    ///     _ => panic!("expected a link update"),
+    /// }
+    /// ```
+    pub fn monitor(&self) -> Result<Monitor> {
+        Monitor::new(&monitor::DEFAULT_GROUPS)
     }
 
     /// Get a link device from the system.
     /// This function returns a boxed link.
+    /// The link is looked up by `attr.name` if set and by `attr.index` otherwise;
+    /// it is an error to set neither.
     ///
     /// Equivalent to: `ip link show $link`
     ///
@@ -60,6 +222,11 @@ impl Netlink {
     /// let link = nl.link_get(&attr).unwrap();
     /// assert_eq!(link.attrs().name, "lo");
     /// assert_eq!(link.attrs().index, 1);
+    ///
+    /// let attr = LinkAttrs { index: 1, ..Default::default() };
+    /// assert_eq!(nl.link_get(&attr).unwrap().attrs().name, "lo");
+    ///
+    /// assert!(nl.link_get(&LinkAttrs::default()).is_err());
     /// ```
     pub fn link_get(&mut self, attr: &LinkAttrs) -> Result<Box<dyn Link>> {
         self.sockets
@@ -68,9 +235,88 @@ impl Netlink {
             .link_get(attr)
     }
 
+    /// Get the index of a link by its name, looking it up only on the first call.
+    ///
+    /// The cache is cleared when links are modified or deleted through this instance,
+    /// or by `clear_link_cache`. Links changed by other processes (e.g. `ip`) are not
+    /// noticed, so the index can be stale then: use `link_get_cached` to check it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// assert_eq!(nl.link_index_cached("foo").unwrap(), foo.attrs().index);
+    ///
+    /// nl.link_del(&foo).unwrap();
+    /// assert!(nl.link_index_cached("foo").is_err());
+    /// ```
+    pub fn link_index_cached(&mut self, name: &str) -> Result<i32> {
+        if let Some(&index) = self.link_cache.get(name) {
+            return Ok(index);
+        }
+
+        let link = self.link_get(&LinkAttrs::new(name))?;
+        self.link_cache.insert(name.to_string(), link.attrs().index);
+        Ok(link.attrs().index)
+    }
+
+    /// Get a link by its name, looking it up by the index cached by `link_index_cached`.
+    /// If the link at that index has another name, e.g. because it was renamed or
+    /// deleted by another process, the cache entry is refreshed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// let link = nl.link_get_cached("foo").unwrap();
+    /// assert_eq!(link.attrs().index, foo.attrs().index);
+    /// ```
+    pub fn link_get_cached(&mut self, name: &str) -> Result<Box<dyn Link>> {
+        if let Some(&index) = self.link_cache.get(name) {
+            let attrs = LinkAttrs {
+                index,
+                ..Default::default()
+            };
+
+            match self.link_get(&attrs) {
+                Ok(link) if link.attrs().name == name => return Ok(link),
+                _ => {
+                    self.link_cache.remove(name);
+                }
+            }
+        }
+
+        let link = self.link_get(&LinkAttrs::new(name))?;
+        self.link_cache.insert(name.to_string(), link.attrs().index);
+        Ok(link)
+    }
+
+    /// Forget the link indexes cached by `link_index_cached` and `link_get_cached`.
+    pub fn clear_link_cache(&mut self) {
+        self.link_cache.clear();
+    }
+
     /// Add a new link device to the system.
+    /// If `index` is set in the link attributes, the kernel creates the link
+    /// with that interface index, or fails with `EEXIST` if it is already taken.
+    /// If `flags` contains `IFF_UP`, the link is brought up as part of its creation,
+    /// without a separate `link_setup`. The peer of a veth pair is still created down.
+    /// If the kernel doesn't support the kind of the link, e.g. because its module
+    /// is not loaded, it fails with `Error::LinkTypeUnsupported`.
     ///
-    /// Equivalent to: `ip link add $link`
+    /// Equivalent to: `ip link add $link [index $index]`
     ///
     /// # Examples
     ///
@@ -97,7 +343,38 @@ impl Netlink {
             .link_new(link, flags)
     }
 
+    /// Add a new link device to the system and verify its kind.
+    /// The created link is read back and its type compared with the requested one,
+    /// which catches kernels that silently create a different kind of device
+    /// (e.g. when the module for the requested kind is not loaded).
+    /// On a mismatch, `Error::KindMismatch` is returned and the created link is left in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let attr = LinkAttrs::new("foo");
+    /// let dummy = Kind::Dummy(attr);
+    ///
+    /// let link = nl.link_add_verify(&dummy).unwrap();
+    /// assert_eq!(link.attrs().name, "foo");
+    /// assert_eq!(link.link_type(), "dummy");
+    /// ```
+    pub fn link_add_verify(&mut self, link: &(impl Link + ?Sized)) -> Result<Box<dyn Link>> {
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_new_verify(link, flags)
+    }
+
     /// Update a link in the system.
+    /// Options of the link type, such as the `ageing_time` of a bridge, are changed
+    /// in place; those left as `None` keep their current value.
     ///
     /// # Examples
     ///
@@ -124,7 +401,42 @@ impl Netlink {
         self.sockets
             .entry(libc::NETLINK_ROUTE)
             .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .link_new(link, libc::NLM_F_ACK)
+            .link_new(link, libc::NLM_F_ACK)?;
+
+        // The link may have been renamed
+        self.clear_link_cache();
+        Ok(())
+    }
+
+    /// Add a new veth pair to the system and return both of its ends.
+    /// The first link is created with the given attributes and the second one
+    /// is named `peer_name`.
+    ///
+    /// Equivalent to: `ip link add $link type veth peer name $peer_name`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    ///
+    /// let (foo, bar) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    /// assert_eq!(foo.attrs().name, "foo");
+    /// assert_eq!(bar.attrs().name, "bar");
+    /// assert_eq!(foo.attrs().parent_index, bar.attrs().index);
+    /// ```
+    pub fn veth_add_pair(
+        &mut self,
+        attrs: &LinkAttrs,
+        peer_name: &str,
+    ) -> Result<(Box<dyn Link>, Box<dyn Link>)> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .veth_add_pair(attrs, peer_name)
     }
 
     /// Delete a link from the system.
@@ -154,184 +466,1182 @@ impl Netlink {
     /// nl.link_del(&link).unwrap();
     /// assert!(nl.link_get(dummy_bar.attrs()).is_err());
     /// ```
-    pub fn link_del(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
+    pub fn link_del(&mut self, link: &(impl AsRef<LinkAttrs> + ?Sized)) -> Result<()> {
         self.sockets
             .entry(libc::NETLINK_ROUTE)
             .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .link_del(link.attrs())
+            .link_del(link.as_ref())?;
+
+        self.clear_link_cache();
+        Ok(())
     }
 
-    /// Set up a link in the system.
+    /// Bring a link down, then delete it, retrying once if the driver reports it as busy.
+    /// Some drivers refuse to delete an interface that is still up.
     ///
-    /// Equivalent to: `ip link set $link up`
+    /// Equivalent to: `ip link set $link down && ip link del $link`
     ///
     /// # Examples
     ///
     /// ```
-    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink};
+    /// use lnwasi::{link::LinkAttrs, netlink::Netlink};
     /// # use lnwasi::test_setup;
     ///
     /// # test_setup!();
     /// let mut nl = Netlink::new().unwrap();
-    /// let br = Kind::Bridge {
-    ///     attrs: LinkAttrs::new("foo"),
-    ///     hello_time: None,
-    ///     ageing_time: None,
-    ///     multicast_snooping: None,
-    ///     vlan_filtering: None,
-    /// };
     ///
-    /// nl.link_add(&br).unwrap();
-    /// nl.link_setup(&br).unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    /// nl.link_setup(&foo).unwrap();
+    /// nl.link_del_force(&foo).unwrap();
+    /// assert!(nl.link_get(&LinkAttrs::new("foo")).is_err());
+    /// ```
+    pub fn link_del_force(&mut self, link: &(impl AsRef<LinkAttrs> + ?Sized)) -> Result<()> {
+        let res = self
+            .sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_del_force(link.as_ref());
+
+        // The link may be gone even if a retry failed
+        self.clear_link_cache();
+        res
+    }
+
+    /// Delete a link from the system by its name.
+    ///
+    /// Equivalent to: `ip link del $name`
+    ///
+    /// # Examples
     ///
-    /// let br = nl.link_get(br.attrs()).unwrap();
-    /// assert_eq!(br.attrs().flags & libc::IFF_UP as u32, 1);
-    /// assert_ne!(br.attrs().oper_state, 2);
     /// ```
-    pub fn link_setup(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
+    /// use lnwasi::{link::LinkAttrs, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    ///
+    /// nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    /// nl.link_del_by_name("foo").unwrap();
+    /// assert!(nl.link_get(&LinkAttrs::new("foo")).is_err());
+    /// ```
+    pub fn link_del_by_name(&mut self, name: &str) -> Result<()> {
         self.sockets
             .entry(libc::NETLINK_ROUTE)
             .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .link_setup(link.attrs())
+            .link_del(&LinkAttrs::new(name))?;
+
+        self.clear_link_cache();
+        Ok(())
     }
 
-    /// Get a list of IP addresses in the system.
-    /// The list can be filtered by link and address family.
+    /// Delete a link from the system by its index.
     ///
-    /// Equivalent to: `ip addr show $link`
+    /// Equivalent to: `ip link del $link`
     ///
     /// # Examples
     ///
     /// ```
-    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::{Address, AddrFamily}};
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
     /// # use lnwasi::test_setup;
     ///
     /// # test_setup!();
     /// let mut nl = Netlink::new().unwrap();
-    /// let attr = LinkAttrs::new("lo");
-    /// let lo = nl.link_get(&attr).unwrap();
-    /// let address = "127.0.0.2/32".parse().unwrap();
-    /// let addr = Address::new(address);
     ///
-    /// nl.addr_add(&lo, &addr).unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    /// nl.link_del_by_index(foo.attrs().index).unwrap();
+    /// assert!(nl.link_get(&LinkAttrs::new("foo")).is_err());
+    /// ```
+    pub fn link_del_by_index(&mut self, index: i32) -> Result<()> {
+        let attrs = LinkAttrs {
+            index,
+            ..Default::default()
+        };
+
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_del(&attrs)?;
+
+        self.clear_link_cache();
+        Ok(())
+    }
+
+    /// Get a list of all link devices in the system.
     ///
-    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
-    /// assert_eq!(addrs.len(), 1);
+    /// Equivalent to: `ip link show`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    ///
+    /// let links = nl.link_list().unwrap();
+    /// assert_eq!(links.len(), 1);
+    /// assert_eq!(links[0].attrs().name, "lo");
+    /// ```
+    pub fn link_list(&mut self) -> Result<Vec<Box<dyn Link>>> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_list(0)
+    }
+
+    /// Get all link devices in the system as a `LinkList`,
+    /// which can look links up by name and by index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// let links = nl.link_list_indexed().unwrap();
+    /// assert_eq!(links.len(), 3);
+    ///
+    /// let foo = links.by_name("foo").unwrap();
+    /// let bar = links.by_index(foo.attrs().parent_index).unwrap();
+    /// assert_eq!(bar.attrs().name, "bar");
+    /// ```
+    pub fn link_list_indexed(&mut self) -> Result<LinkList> {
+        Ok(self.link_list()?.into())
+    }
+
+    /// Get a list of the link devices that are administratively up.
+    /// The kernel can't filter a link dump by flags, so the links are filtered here.
+    ///
+    /// Equivalent to: `ip link show up`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// let lo = nl.link_get(&LinkAttrs::new("lo")).unwrap();
+    /// nl.link_setup(&lo).unwrap();
+    ///
+    /// let links = nl.link_list_up().unwrap();
+    /// assert_eq!(links.len(), 1);
+    /// assert_eq!(links[0].attrs().name, "lo");
+    /// ```
+    pub fn link_list_up(&mut self) -> Result<Vec<Box<dyn Link>>> {
+        Ok(self
+            .link_list()?
+            .into_iter()
+            .filter(|link| link.attrs().raw_flags & libc::IFF_UP as u32 != 0)
+            .collect())
+    }
+
+    /// Get a list of the link devices enslaved to a master (e.g. a bridge or a bond).
+    ///
+    /// Equivalent to: `ip link show master $master`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let br = Kind::Bridge {
+    ///     attrs: LinkAttrs::new("br0"),
+    ///     hello_time: None,
+    ///     ageing_time: None,
+    ///     multicast_snooping: None,
+    ///     vlan_filtering: None,
+    ///     stp_state: None,
+    ///     priority: None,
+    /// };
+    ///
+    /// nl.link_add(&br).unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    /// nl.link_set_master(&foo, &br).unwrap();
+    ///
+    /// let links = nl.link_list_by_master(&br).unwrap();
+    /// assert_eq!(links.len(), 1);
+    /// assert_eq!(links[0].attrs().name, "foo");
+    /// ```
+    pub fn link_list_by_master(
+        &mut self,
+        master: &(impl AsRef<LinkAttrs> + ?Sized),
+    ) -> Result<Vec<Box<dyn Link>>> {
+        let handle = self
+            .sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?);
+
+        let master = handle.link_get(master.as_ref())?;
+        handle.link_list(master.attrs().index)
+    }
+
+    /// Enslave a link to a master device, such as a bridge or a bond.
+    /// A link that is up is brought down first when the master is a bond,
+    /// since bonds only enslave links that are down.
+    ///
+    /// Equivalent to: `ip link set $link master $master`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let br = Kind::Bridge {
+    ///     attrs: LinkAttrs::new("br0"),
+    ///     hello_time: None,
+    ///     ageing_time: None,
+    ///     multicast_snooping: None,
+    ///     vlan_filtering: None,
+    ///     stp_state: None,
+    ///     priority: None,
+    /// };
+    ///
+    /// nl.link_add(&br).unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    /// nl.link_set_master(&foo, &br).unwrap();
+    ///
+    /// let br = nl.link_get(br.attrs()).unwrap();
+    /// let foo = nl.link_get(foo.attrs()).unwrap();
+    /// assert_eq!(foo.attrs().master_index, br.attrs().index);
+    /// ```
+    pub fn link_set_master(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        master: &(impl AsRef<LinkAttrs> + ?Sized),
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_master(link.as_ref(), master.as_ref())
+    }
+
+    /// Set up a link in the system.
+    /// Like the other operations that only need the name or index of a link,
+    /// it also accepts plain `LinkAttrs`.
+    ///
+    /// Equivalent to: `ip link set $link up`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs, OperState}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let br = Kind::Bridge {
+    ///     attrs: LinkAttrs::new("foo"),
+    ///     hello_time: None,
+    ///     ageing_time: None,
+    ///     multicast_snooping: None,
+    ///     vlan_filtering: None,
+    ///     stp_state: None,
+    ///     priority: None,
+    /// };
+    ///
+    /// nl.link_add(&br).unwrap();
+    /// nl.link_setup(&br).unwrap();
+    ///
+    /// let br = nl.link_get(br.attrs()).unwrap();
+    /// assert_eq!(br.attrs().flags & libc::IFF_UP as u32, 1);
+    /// assert_ne!(br.attrs().oper_state_enum(), OperState::Down);
+    ///
+    /// nl.link_setup(&LinkAttrs::new("lo")).unwrap();
+    /// ```
+    pub fn link_setup(&mut self, link: &(impl AsRef<LinkAttrs> + ?Sized)) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_setup(link.as_ref())
+    }
+
+    /// Set down a link in the system.
+    ///
+    /// Equivalent to: `ip link set $link down`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    ///
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    /// nl.link_setup(&foo).unwrap();
+    /// nl.link_setdown(&foo).unwrap();
+    ///
+    /// let foo = nl.link_get(foo.attrs()).unwrap();
+    /// assert_eq!(foo.attrs().flags & libc::IFF_UP as u32, 0);
+    /// ```
+    pub fn link_setdown(&mut self, link: &(impl AsRef<LinkAttrs> + ?Sized)) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_setdown(link.as_ref())
+    }
+
+    /// Set down a link in the system, the counterpart of `link_setup`.
+    /// This is the same as `link_setdown`.
+    ///
+    /// Equivalent to: `ip link set $link down`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    ///
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    /// nl.link_setup(&foo).unwrap();
+    /// nl.link_down(&foo).unwrap();
+    ///
+    /// let foo = nl.link_get(foo.attrs()).unwrap();
+    /// assert_eq!(foo.attrs().flags & libc::IFF_UP as u32, 0);
+    /// ```
+    pub fn link_down(&mut self, link: &(impl AsRef<LinkAttrs> + ?Sized)) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_down(link.as_ref())
+    }
+
+    /// Set the MTU of a link device.
+    ///
+    /// Equivalent to: `ip link set $link mtu $mtu`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// nl.link_set_mtu(&foo, 1400).unwrap();
+    /// assert_eq!(nl.link_get(foo.attrs()).unwrap().attrs().mtu, 1400);
+    /// ```
+    pub fn link_set_mtu(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        mtu: u32,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_mtu(link.as_ref(), mtu)
+    }
+
+    /// Set the MTU of a link device and return the previous one, e.g. to roll back later.
+    /// This reads the link first, which costs an extra round trip over `link_set_mtu`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// let old = nl.link_set_mtu_returning_old(&foo, 1400).unwrap();
+    /// assert_eq!(old, foo.attrs().mtu);
+    /// ```
+    pub fn link_set_mtu_returning_old(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        mtu: u32,
+    ) -> Result<u32> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_mtu_returning_old(link.as_ref(), mtu)
+    }
+
+    /// Set the transmit queue length of a link device.
+    ///
+    /// Equivalent to: `ip link set $link txqueuelen $qlen`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// nl.link_set_txqlen(&foo, 100).unwrap();
+    /// assert_eq!(nl.link_get(foo.attrs()).unwrap().attrs().tx_queue_len, 100);
+    /// ```
+    pub fn link_set_txqlen(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        qlen: u32,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_txqlen(link.as_ref(), qlen)
+    }
+
+    /// Set the transmit queue length of a link device and return the previous one.
+    /// This reads the link first, which costs an extra round trip over `link_set_txqlen`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// let old = nl.link_set_txqlen_returning_old(&foo, 100).unwrap();
+    /// assert_eq!(old, foo.attrs().tx_queue_len as u32);
+    /// ```
+    pub fn link_set_txqlen_returning_old(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        qlen: u32,
+    ) -> Result<u32> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_txqlen_returning_old(link.as_ref(), qlen)
+    }
+
+    /// Rename a link device. Most drivers refuse to rename a link that is up.
+    ///
+    /// Equivalent to: `ip link set $link name $name`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// nl.link_set_name(&foo, "baz").unwrap();
+    /// let baz = nl.link_get(&LinkAttrs::new("baz")).unwrap();
+    /// assert_eq!(baz.attrs().index, foo.attrs().index);
+    /// ```
+    pub fn link_set_name(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        name: &str,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_name(link.as_ref(), name)?;
+
+        self.clear_link_cache();
+        Ok(())
+    }
+
+    /// Set the hardware address of a link device.
+    /// The address must have the length of the current one, e.g. 6 bytes for Ethernet devices.
+    ///
+    /// Equivalent to: `ip link set $link address $hw_addr`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// let hw_addr = [0x02, 0x42, 0xac, 0x11, 0x00, 0x02];
+    /// nl.link_set_hw_addr(&foo, &hw_addr).unwrap();
+    /// assert_eq!(nl.link_get(foo.attrs()).unwrap().attrs().hw_addr, hw_addr);
+    /// ```
+    pub fn link_set_hw_addr(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        hw_addr: &[u8],
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_hw_addr(link.as_ref(), hw_addr)
+    }
+
+    /// Move a link device into the network namespace referred to by `fd`,
+    /// e.g. an open `/proc/$pid/ns/net` or `/run/netns/$name`.
+    ///
+    /// Equivalent to: `ip link set $link netns $ns`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::{fs::File, os::fd::AsRawFd};
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// let ns = File::open("/proc/self/ns/net").unwrap();
+    /// nl.link_set_ns_fd(&foo, ns.as_raw_fd()).unwrap();
+    /// ```
+    pub fn link_set_ns_fd(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        fd: RawFd,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_ns_fd(link.as_ref(), fd)?;
+
+        self.clear_link_cache();
+        Ok(())
+    }
+
+    /// Move a link device into the network namespace of the process `pid`.
+    ///
+    /// Equivalent to: `ip link set $link netns $pid`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// nl.link_set_ns_pid(&foo, std::process::id() as i32).unwrap();
+    /// ```
+    pub fn link_set_ns_pid(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        pid: i32,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_ns_pid(link.as_ref(), pid)?;
+
+        self.clear_link_cache();
+        Ok(())
+    }
+
+    /// Set the alias of a link device.
+    /// An empty alias clears it: the kernel treats a present but zero-length
+    /// `IFLA_IFALIAS` as a removal, whereas omitting the attribute changes nothing.
+    ///
+    /// Equivalent to: `ip link set $link alias $alias`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let lo = nl.link_get(&LinkAttrs::new("lo")).unwrap();
+    ///
+    /// nl.link_set_alias(&lo, "loopback").unwrap();
+    /// assert_eq!(nl.link_get(lo.attrs()).unwrap().attrs().alias.as_deref(), Some("loopback"));
+    ///
+    /// nl.link_set_alias(&lo, "").unwrap();
+    /// assert_eq!(nl.link_get(lo.attrs()).unwrap().attrs().alias, None);
+    /// ```
+    pub fn link_set_alias(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        alias: &str,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_alias(link.as_ref(), alias)
+    }
+
+    /// Set the maximum size of a GSO packet the link should accept.
+    /// The size is validated against the TSO limit reported by the device,
+    /// so an unsupported value fails with a clear error instead of `EINVAL`.
+    ///
+    /// Equivalent to: `ip link set $link gso_max_size $size`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    ///
+    /// let lo = nl.link_get(&LinkAttrs::new("lo")).unwrap();
+    /// let max = lo.attrs().tso_max_size;
+    ///
+    /// nl.link_set_gso_max_size(&lo, max).unwrap();
+    /// assert!(nl.link_set_gso_max_size(&lo, max + 1).is_err());
+    ///
+    /// let lo = nl.link_get(lo.attrs()).unwrap();
+    /// assert_eq!(lo.attrs().gso_max_size, max);
+    /// ```
+    pub fn link_set_gso_max_size(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        size: u32,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_gso_max_size(link.as_ref(), size)
+    }
+
+    /// Set the maximum number of segments of a GSO packet the link should accept.
+    /// The count is validated against the TSO limit reported by the device.
+    ///
+    /// Equivalent to: `ip link set $link gso_max_segs $segs`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    ///
+    /// let lo = nl.link_get(&LinkAttrs::new("lo")).unwrap();
+    /// let max = lo.attrs().tso_max_segs;
+    ///
+    /// nl.link_set_gso_max_segs(&lo, max).unwrap();
+    /// assert!(nl.link_set_gso_max_segs(&lo, max + 1).is_err());
+    ///
+    /// let lo = nl.link_get(lo.attrs()).unwrap();
+    /// assert_eq!(lo.attrs().gso_max_segs, max);
+    /// ```
+    pub fn link_set_gso_max_segs(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        segs: u32,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_gso_max_segs(link.as_ref(), segs)
+    }
+
+    /// Set the maximum size of an IPv4 GSO packet the link should accept (BIG TCP for IPv4).
+    /// Fails with a clear error on kernels older than 6.3, which don't know this setting.
+    ///
+    /// Equivalent to: `ip link set $link gso_ipv4_max_size $size`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// nl.link_set_gso_ipv4_max_size(&foo, 32768).unwrap();
+    ///
+    /// let foo = nl.link_get(foo.attrs()).unwrap();
+    /// assert_eq!(foo.attrs().gso_ipv4_max_size, 32768);
+    /// ```
+    pub fn link_set_gso_ipv4_max_size(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        size: u32,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_gso_ipv4_max_size(link.as_ref(), size)
+    }
+
+    /// Set the maximum size of an IPv4 packet built by GRO (BIG TCP for IPv4).
+    /// Fails with a clear error on kernels older than 6.3, which don't know this setting.
+    ///
+    /// Equivalent to: `ip link set $link gro_ipv4_max_size $size`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    ///
+    /// nl.link_set_gro_ipv4_max_size(&foo, 32768).unwrap();
+    ///
+    /// let foo = nl.link_get(foo.attrs()).unwrap();
+    /// assert_eq!(foo.attrs().gro_ipv4_max_size, 32768);
+    /// ```
+    pub fn link_set_gro_ipv4_max_size(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        size: u32,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .link_set_gro_ipv4_max_size(link.as_ref(), size)
+    }
+
+    /// Get a list of IP addresses in the system.
+    /// The list can be filtered by link and address family.
+    /// Addresses are sorted by address, prefix length and scope, without duplicates.
+    ///
+    /// Equivalent to: `ip addr show $link`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::{Address, AddrFamily}};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let attr = LinkAttrs::new("lo");
+    /// let lo = nl.link_get(&attr).unwrap();
+    /// let address = "127.0.0.2/32".parse().unwrap();
+    /// let addr = Address::new(address);
+    ///
+    /// nl.addr_add(&lo, &addr).unwrap();
+    ///
+    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
+    /// assert_eq!(addrs.len(), 1);
+    /// ```
+    pub fn addr_list(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        family: AddrFamily,
+    ) -> Result<Vec<Address>> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .addr_list(link.as_ref(), family)
+    }
+
+    /// Get the IP addresses of a link device, optionally only those of a given scope.
+    /// With `Some(RT_SCOPE_UNIVERSE)`, the addresses the kernel configures by itself,
+    /// such as `127.0.0.1/8` and `::1` on `lo` or IPv6 link-local addresses, are left out.
+    ///
+    /// Equivalent to: `ip addr show $link scope $scope`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::{Address, AddrFamily}};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let lo = nl.link_get(&LinkAttrs::new("lo")).unwrap();
+    /// nl.link_setup(&lo).unwrap();
+    ///
+    /// let addr = Address::new("127.0.0.2/32".parse().unwrap());
+    /// nl.addr_add(&lo, &addr).unwrap();
+    ///
+    /// let addrs = nl.addr_list_exact(&lo, AddrFamily::All, Some(libc::RT_SCOPE_UNIVERSE)).unwrap();
+    /// assert_eq!(addrs.len(), 1);
+    /// ```
+    pub fn addr_list_exact(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        family: AddrFamily,
+        scope: Option<u8>,
+    ) -> Result<Vec<Address>> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .addr_list_exact(link.as_ref(), family, scope)
+    }
+
+    /// Get the IP addresses of a link device that match a filter on their scope and flags,
+    /// e.g. only the permanent global addresses.
+    ///
+    /// Equivalent to: `ip addr show $link scope $scope permanent`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::LinkAttrs, netlink::Netlink, addr::{Address, AddrFamily, AddrFilter}};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let lo = nl.link_get(&LinkAttrs::new("lo")).unwrap();
+    /// nl.link_setup(&lo).unwrap();
+    ///
+    /// let addr = Address::new("127.0.0.2/32".parse().unwrap());
+    /// nl.addr_add(&lo, &addr).unwrap();
+    ///
+    /// let filter = AddrFilter {
+    ///     scope: Some(libc::RT_SCOPE_UNIVERSE),
+    ///     ..Default::default()
+    /// };
+    /// let addrs = nl.addr_list_filtered(&lo, AddrFamily::All, &filter).unwrap();
+    /// assert_eq!(addrs.len(), 1);
+    /// ```
+    pub fn addr_list_filtered(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        family: AddrFamily,
+        filter: &AddrFilter,
+    ) -> Result<Vec<Address>> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .addr_list_filtered(link.as_ref(), family, filter)
+    }
+
+    /// Add an IP address to a link device.
+    ///
+    /// Equivalent to: `ip addr add $addr dev $link`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::{Address, AddrFamily}};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let attr = LinkAttrs::new("lo");
+    /// let lo = nl.link_get(&attr).unwrap();
+    /// let address = "127.0.0.2/32".parse().unwrap();
+    /// let addr = Address::new(address);
+    ///
+    /// nl.addr_add(&lo, &addr).unwrap();
+    ///
+    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
+    /// assert_eq!(addrs.len(), 1);
+    /// assert_eq!(addrs[0].address, addr.address);
+    /// ```
+    pub fn addr_add(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        addr: &Address,
+    ) -> Result<()> {
+        self.addr_handle(AddrCmd::Add, link, addr)
+    }
+
+    /// Add an IP address to a link device unless it is already there.
+    /// Succeeds without changes if the link already has the exact address,
+    /// and fails with `Error::AlreadyExists` if it has the same address
+    /// with a different prefix length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink, addr::{Address, AddrFamily}, error::Error};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let lo = nl.link_get(&LinkAttrs::new("lo")).unwrap();
+    /// let addr = Address::new("127.0.0.2/32".parse().unwrap());
+    ///
+    /// nl.addr_ensure(&lo, &addr).unwrap();
+    /// nl.addr_ensure(&lo, &addr).unwrap();
+    ///
+    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
+    /// assert_eq!(addrs.len(), 1);
+    ///
+    /// let addr = Address::new("127.0.0.2/8".parse().unwrap());
+    /// let err = nl.addr_ensure(&lo, &addr).unwrap_err();
+    /// assert!(matches!(err.downcast_ref::<Error>(), Some(Error::AlreadyExists { .. })));
+    /// ```
+    pub fn addr_ensure(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        addr: &Address,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .addr_ensure(link.as_ref(), addr)
+    }
+
+    /// Replace an IP address on a link device.
+    /// If the address does not exist, it will be added.
+    ///
+    /// Equivalent to: `ip addr replace $addr dev $link`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::{Address, AddrFamily}};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let attr = LinkAttrs::new("lo");
+    /// let lo = nl.link_get(&attr).unwrap();
+    /// let address = "127.0.0.2/32".parse().unwrap();
+    /// let addr = Address::new(address);
+    ///
+    /// nl.addr_add(&lo, &addr).unwrap();
+    ///
+    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
+    /// assert_eq!(addrs.len(), 1);
+    ///
+    /// nl.addr_replace(&lo, &addr).unwrap();
+    ///
+    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
+    /// assert_eq!(addrs.len(), 1);
+    /// ```
+    pub fn addr_replace(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        addr: &Address,
+    ) -> Result<()> {
+        self.addr_handle(AddrCmd::Replace, link, addr)
+    }
+
+    /// Delete an IP address from a link device.
+    ///
+    /// Equivalent to: `ip addr del $addr dev $link`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::{Address, AddrFamily}};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let attrs = LinkAttrs::new("lo");
+    /// let lo = nl.link_get(&attrs).unwrap();
+    /// let address = "127.0.0.2/32".parse().unwrap();
+    /// let addr = Address::new(address);
+    ///
+    /// nl.addr_add(&lo, &addr).unwrap();
+    ///
+    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
+    /// assert_eq!(addrs.len(), 1);
+    ///
+    /// nl.addr_del(&lo, &addr).unwrap();
+    ///
+    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
+    /// assert_eq!(addrs.len(), 0);
+    /// ```
+    pub fn addr_del(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        addr: &Address,
+    ) -> Result<()> {
+        self.addr_handle(AddrCmd::Del, link, addr)
+    }
+
+    fn addr_handle(
+        &mut self,
+        cmd: AddrCmd,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        addr: &Address,
+    ) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .addr_handle(cmd, link.as_ref(), addr)
+    }
+
+    /// Get a list of neighbor entries of a link device, optionally only those in one
+    /// of the given states (e.g. `NeighborState::REACHABLE | NeighborState::STALE`).
+    /// `NeighborState::NONE` selects the entries without any state flag.
+    /// The kernel only returns the entries of the link, while the states are filtered here.
+    /// The `cache_info` of each entry tells how long ago it was confirmed, used and updated.
+    ///
+    /// Equivalent to: `ip -s neigh show dev $link [nud $state]`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::AddrFamily, neigh::{Neighbor, NeighborState}};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let veth = Kind::Veth {
+    ///     attrs: LinkAttrs::new("foo"),
+    ///     peer_name: "bar".to_string(),
+    ///     peer_hw_addr: None,
+    ///     peer_ns: None,
+    /// };
+    /// nl.link_add(&veth).unwrap();
+    /// let link = nl.link_get(veth.attrs()).unwrap();
+    /// let ip = "10.0.0.2".parse().unwrap();
+    ///
+    /// nl.neigh_add(&Neighbor::new(link.attrs().index, ip, &[0x02, 0, 0, 0, 0, 0x02])).unwrap();
+    ///
+    /// let neighs = nl.neigh_list(&link, AddrFamily::V4, None).unwrap();
+    /// assert_eq!(neighs.len(), 1);
+    /// assert_eq!(neighs[0].ip, ip);
+    ///
+    /// let neighs = nl.neigh_list(&link, AddrFamily::V4, Some(NeighborState::FAILED)).unwrap();
+    /// assert!(neighs.is_empty());
     /// ```
-    pub fn addr_list(
+    pub fn neigh_list(
         &mut self,
-        link: &(impl Link + ?Sized),
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
         family: AddrFamily,
-    ) -> Result<Vec<Address>> {
-        self.sockets
+        state: Option<NeighborState>,
+    ) -> Result<Vec<Neighbor>> {
+        let handle = self
+            .sockets
             .entry(libc::NETLINK_ROUTE)
-            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .addr_list(link, family)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?);
+
+        // An index of 0 would list the neighbors of every link
+        let index = handle.ensure_index(link.as_ref())?;
+        handle.neigh_list(family, index, state)
     }
 
-    /// Add an IP address to a link device.
+    /// Add a neighbor entry.
+    /// Fails if an entry for the same address already exists.
     ///
-    /// Equivalent to: `ip addr add $addr dev $link`
+    /// Equivalent to: `ip neigh add $ip lladdr $ll_addr dev $link`
     ///
     /// # Examples
     ///
     /// ```
-    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::{Address, AddrFamily}};
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::AddrFamily, neigh::Neighbor};
     /// # use lnwasi::test_setup;
     ///
     /// # test_setup!();
     /// let mut nl = Netlink::new().unwrap();
-    /// let attr = LinkAttrs::new("lo");
-    /// let lo = nl.link_get(&attr).unwrap();
-    /// let address = "127.0.0.2/32".parse().unwrap();
-    /// let addr = Address::new(address);
-    ///
-    /// nl.addr_add(&lo, &addr).unwrap();
+    /// let veth = Kind::Veth {
+    ///     attrs: LinkAttrs::new("foo"),
+    ///     peer_name: "bar".to_string(),
+    ///     peer_hw_addr: None,
+    ///     peer_ns: None,
+    /// };
+    /// nl.link_add(&veth).unwrap();
+    /// let link = nl.link_get(veth.attrs()).unwrap();
+    /// let ip = "10.0.0.2".parse().unwrap();
+    /// let neigh = Neighbor::new(link.attrs().index, ip, &[0x02, 0, 0, 0, 0, 0x02]);
     ///
-    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
-    /// assert_eq!(addrs.len(), 1);
-    /// assert_eq!(addrs[0].address, addr.address);
+    /// nl.neigh_add(&neigh).unwrap();
+    /// assert!(nl.neigh_add(&neigh).is_err());
     /// ```
-    pub fn addr_add(&mut self, link: &(impl Link + ?Sized), addr: &Address) -> Result<()> {
-        self.addr_handle(AddrCmd::Add, link, addr)
+    pub fn neigh_add(&mut self, neigh: &Neighbor) -> Result<()> {
+        self.neigh_handle(NeighCmd::Add, neigh)
     }
 
-    /// Replace an IP address on a link device.
-    /// If the address does not exist, it will be added.
+    /// Replace a neighbor entry.
+    /// If the entry does not exist, it will be added.
     ///
-    /// Equivalent to: `ip addr replace $addr dev $link`
+    /// Equivalent to: `ip neigh replace $ip lladdr $ll_addr dev $link`
     ///
     /// # Examples
     ///
     /// ```
-    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::{Address, AddrFamily}};
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::AddrFamily, neigh::Neighbor};
     /// # use lnwasi::test_setup;
     ///
     /// # test_setup!();
     /// let mut nl = Netlink::new().unwrap();
-    /// let attr = LinkAttrs::new("lo");
-    /// let lo = nl.link_get(&attr).unwrap();
-    /// let address = "127.0.0.2/32".parse().unwrap();
-    /// let addr = Address::new(address);
-    ///
-    /// nl.addr_add(&lo, &addr).unwrap();
-    ///
-    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
-    /// assert_eq!(addrs.len(), 1);
+    /// let veth = Kind::Veth {
+    ///     attrs: LinkAttrs::new("foo"),
+    ///     peer_name: "bar".to_string(),
+    ///     peer_hw_addr: None,
+    ///     peer_ns: None,
+    /// };
+    /// nl.link_add(&veth).unwrap();
+    /// let link = nl.link_get(veth.attrs()).unwrap();
+    /// let ip = "10.0.0.2".parse().unwrap();
     ///
-    /// nl.addr_replace(&lo, &addr).unwrap();
+    /// nl.neigh_add(&Neighbor::new(link.attrs().index, ip, &[0x02, 0, 0, 0, 0, 0x02])).unwrap();
+    /// nl.neigh_replace(&Neighbor::new(link.attrs().index, ip, &[0x02, 0, 0, 0, 0, 0x03])).unwrap();
     ///
-    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
-    /// assert_eq!(addrs.len(), 1);
+    /// let neighs = nl.neigh_list(&link, AddrFamily::V4, None).unwrap();
+    /// assert_eq!(neighs.len(), 1);
+    /// assert_eq!(neighs[0].ll_addr, Some(vec![0x02, 0, 0, 0, 0, 0x03]));
     /// ```
-    pub fn addr_replace(&mut self, link: &(impl Link + ?Sized), addr: &Address) -> Result<()> {
-        self.addr_handle(AddrCmd::Replace, link, addr)
+    pub fn neigh_replace(&mut self, neigh: &Neighbor) -> Result<()> {
+        self.neigh_handle(NeighCmd::Replace, neigh)
     }
 
-    /// Delete an IP address from a link device.
+    /// Delete a neighbor entry.
     ///
-    /// Equivalent to: `ip addr del $addr dev $link`
+    /// Equivalent to: `ip neigh del $ip dev $link`
     ///
     /// # Examples
     ///
     /// ```
-    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::{Address, AddrFamily}};
+    /// use lnwasi::{link::{Kind, Link, LinkAttrs}, netlink::Netlink, addr::AddrFamily, neigh::Neighbor};
     /// # use lnwasi::test_setup;
     ///
     /// # test_setup!();
     /// let mut nl = Netlink::new().unwrap();
-    /// let attrs = LinkAttrs::new("lo");
-    /// let lo = nl.link_get(&attrs).unwrap();
-    /// let address = "127.0.0.2/32".parse().unwrap();
-    /// let addr = Address::new(address);
-    ///
-    /// nl.addr_add(&lo, &addr).unwrap();
-    ///
-    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
-    /// assert_eq!(addrs.len(), 1);
+    /// let veth = Kind::Veth {
+    ///     attrs: LinkAttrs::new("foo"),
+    ///     peer_name: "bar".to_string(),
+    ///     peer_hw_addr: None,
+    ///     peer_ns: None,
+    /// };
+    /// nl.link_add(&veth).unwrap();
+    /// let link = nl.link_get(veth.attrs()).unwrap();
+    /// let ip = "10.0.0.2".parse().unwrap();
+    /// let neigh = Neighbor::new(link.attrs().index, ip, &[0x02, 0, 0, 0, 0, 0x02]);
     ///
-    /// nl.addr_del(&lo, &addr).unwrap();
+    /// nl.neigh_add(&neigh).unwrap();
+    /// nl.neigh_del(&neigh).unwrap();
     ///
-    /// let addrs = nl.addr_list(&lo, AddrFamily::All).unwrap();
-    /// assert_eq!(addrs.len(), 0);
+    /// let neighs = nl.neigh_list(&link, AddrFamily::V4, None).unwrap();
+    /// assert!(neighs.is_empty());
     /// ```
-    pub fn addr_del(&mut self, link: &(impl Link + ?Sized), addr: &Address) -> Result<()> {
-        self.addr_handle(AddrCmd::Del, link, addr)
+    pub fn neigh_del(&mut self, neigh: &Neighbor) -> Result<()> {
+        self.neigh_handle(NeighCmd::Del, neigh)
     }
 
-    fn addr_handle(
-        &mut self,
-        cmd: AddrCmd,
-        link: &(impl Link + ?Sized),
-        addr: &Address,
-    ) -> Result<()> {
+    fn neigh_handle(&mut self, cmd: NeighCmd, neigh: &Neighbor) -> Result<()> {
         self.sockets
             .entry(libc::NETLINK_ROUTE)
             .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .addr_handle(cmd, link.attrs(), addr)
+            .neigh_handle(cmd, neigh)
     }
 
     /// Get a list of routes for a given destination.
+    /// The `src` of the returned routes is the source address the kernel
+    /// would select for traffic to `dst`.
     ///
     /// Equivalent to: `ip route get $dst`
     ///
@@ -351,6 +1661,7 @@ impl Netlink {
     /// let dst = "127.0.0.1".parse().unwrap();
     /// let routes = nl.route_get(&dst).unwrap();
     /// assert_eq!(routes.len(), 1);
+    /// assert_eq!(routes[0].src, Some("127.0.0.1".parse().unwrap()));
     /// ```
     pub fn route_get(&mut self, dst: &IpAddr) -> Result<Vec<Route>> {
         self.sockets
@@ -359,9 +1670,44 @@ impl Netlink {
             .route_get(dst)
     }
 
+    /// Get the routes to many destinations at once, in the order of `dsts`.
+    /// The lookups are sent to the kernel in batches rather than one by one,
+    /// and a destination that can't be reached only fails its own result.
+    ///
+    /// Equivalent to: `ip route get $dst` for each of `dsts`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::LinkAttrs, netlink::Netlink};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// nl.link_setup(&LinkAttrs::new("lo")).unwrap();
+    ///
+    /// let dsts = ["127.0.0.1".parse().unwrap(), "192.0.2.1".parse().unwrap()];
+    /// let res = nl.route_get_many(&dsts).unwrap();
+    /// assert_eq!(res[0].1.as_ref().unwrap().len(), 1);
+    /// assert!(res[1].1.is_err());
+    /// ```
+    pub fn route_get_many(&mut self, dsts: &[IpAddr]) -> Result<Vec<(IpAddr, Result<Vec<Route>>)>> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .route_get_many(dsts)
+    }
+
     /// Get a list of routes in the system.
     /// The list can be filtered by link and address family.
     ///
+    /// If the kernel marks the dump with `NLM_F_DUMP_FILTERED`, its filtering
+    /// is trusted; otherwise the routes are filtered by link here.
+    ///
+    /// `AddrFamily::All` requests an `AF_UNSPEC` dump, which the kernel answers
+    /// with the routes of every registered family, so IPv4 and IPv6 routes are
+    /// returned by a single call.
+    ///
     /// Equivalent to: `ip route show`
     ///
     /// # Examples
@@ -383,18 +1729,23 @@ impl Netlink {
     /// ```
     pub fn route_list(
         &mut self,
-        link: &(impl Link + ?Sized),
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
         family: AddrFamily,
     ) -> Result<Vec<Route>> {
-        self.sockets
+        let handle = self
+            .sockets
             .entry(libc::NETLINK_ROUTE)
-            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .route_list(family, link.attrs().index, RtFilter::Oif)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?);
+
+        // An index of 0 would select the routes without an output link
+        let index = handle.ensure_index(link.as_ref())?;
+        handle.route_list(family, index, RtFilter::Oif)
     }
 
     /// Add a route to the system.
+    /// An IPv6 route with `expires` set is removed by the kernel once its lifetime ends.
     ///
-    /// Equivalent to: `ip route add $route`
+    /// Equivalent to: `ip route add $route [expires $expires]`
     ///
     /// # Examples
     ///
@@ -428,6 +1779,8 @@ impl Netlink {
     }
 
     /// Append a route to the system.
+    /// The kernel uses the first of the routes with the same metric, so an appended route
+    /// is a fallback; see `route_prepend` for a route that takes precedence.
     ///
     /// Equivalent to: `ip route append $route`
     ///
@@ -468,6 +1821,45 @@ impl Netlink {
         self.route_handle(RtCmd::Append, route)
     }
 
+    /// Add a route that takes precedence over the existing routes to the same destination.
+    /// The kernel can only append routes, so this is emulated with the metric: the route
+    /// is added with a priority one below the lowest one of the routes with the same dst,
+    /// tos and table, and that priority is returned. The `priority` of `route` is only used
+    /// if there is no such route. It fails if one of them already has metric 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{link::{Link, LinkAttrs}, netlink::Netlink, addr::Address, route::Route};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let (foo, _) = nl.veth_add_pair(&LinkAttrs::new("foo"), "bar").unwrap();
+    /// nl.link_setup(&foo).unwrap();
+    /// nl.addr_add(&foo, &Address::new("10.0.0.1/24".parse().unwrap())).unwrap();
+    ///
+    /// let mut route = Route {
+    ///    dst: Some("192.168.0.0/24".parse().unwrap()),
+    ///    gw: Some("10.0.0.2".parse().unwrap()),
+    ///    priority: Some(100),
+    ///    ..Default::default()
+    /// };
+    /// nl.route_add(&route).unwrap();
+    ///
+    /// route.gw = Some("10.0.0.3".parse().unwrap());
+    /// assert_eq!(nl.route_prepend(&route).unwrap(), 99);
+    ///
+    /// let routes = nl.route_get(&"192.168.0.1".parse().unwrap()).unwrap();
+    /// assert_eq!(routes[0].gw, route.gw);
+    /// ```
+    pub fn route_prepend(&mut self, route: &Route) -> Result<u32> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .route_prepend(route)
+    }
+
     /// Replace a route in the system.
     ///
     /// Equivalent to: `ip route replace $route`
@@ -551,14 +1943,197 @@ impl Netlink {
             .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
             .route_handle(cmd, route)
     }
+
+    /// Get a list of routing policy rules in the system.
+    /// The list can be filtered by address family.
+    ///
+    /// Equivalent to: `ip rule show`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{netlink::Netlink, addr::AddrFamily};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    ///
+    /// // The default rules look up the local, main and default tables
+    /// let rules = nl.rule_list(AddrFamily::V4).unwrap();
+    /// let tables = rules.iter().map(|r| r.table).collect::<Vec<_>>();
+    /// assert_eq!(tables, [255, 254, 253]);
+    /// ```
+    pub fn rule_list(&mut self, family: AddrFamily) -> Result<Vec<Rule>> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .rule_list(family)
+    }
+
+    /// Add a routing policy rule.
+    ///
+    /// Equivalent to: `ip rule add $rule`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{netlink::Netlink, addr::AddrFamily, rule::Rule};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let rule = Rule {
+    ///     priority: Some(100),
+    ///     table: 100,
+    ///     mark: Some(0x1),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// nl.rule_add(&rule).unwrap();
+    ///
+    /// let rules = nl.rule_list(AddrFamily::V4).unwrap();
+    /// assert!(rules.iter().any(|r| r.priority == Some(100) && r.table == 100));
+    /// ```
+    pub fn rule_add(&mut self, rule: &Rule) -> Result<()> {
+        self.rule_handle(RuleCmd::Add, rule)
+    }
+
+    /// Delete a routing policy rule.
+    /// The first rule matching all the set fields of `rule` is deleted.
+    ///
+    /// Equivalent to: `ip rule del $rule`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{netlink::Netlink, addr::AddrFamily, rule::Rule};
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// let mut nl = Netlink::new().unwrap();
+    /// let rule = Rule {
+    ///     priority: Some(100),
+    ///     table: 100,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// nl.rule_add(&rule).unwrap();
+    /// nl.rule_del(&rule).unwrap();
+    ///
+    /// let rules = nl.rule_list(AddrFamily::V4).unwrap();
+    /// assert!(rules.iter().all(|r| r.priority != Some(100)));
+    /// ```
+    pub fn rule_del(&mut self, rule: &Rule) -> Result<()> {
+        self.rule_handle(RuleCmd::Del, rule)
+    }
+
+    /// Set the private key, listen port and peers of a WireGuard link.
+    /// This goes through the `wireguard` generic netlink family, on a `NETLINK_GENERIC`
+    /// socket opened on first use.
+    ///
+    /// Equivalent to: `wg set $link private-key $key listen-port $port peer $peer ...`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lnwasi::{
+    ///     link::{Kind, LinkAttrs},
+    ///     netlink::Netlink,
+    ///     wireguard::{WireguardConfig, WireguardPeer},
+    /// };
+    /// # use lnwasi::test_setup;
+    ///
+    /// # test_setup!();
+    /// # if !std::path::Path::new("/sys/module/wireguard").exists() { return; }
+    /// let mut nl = Netlink::new().unwrap();
+    /// let wg = Kind::Wireguard(LinkAttrs::new("wg0"));
+    /// nl.link_add(&wg).unwrap();
+    ///
+    /// let config = WireguardConfig {
+    ///     private_key: Some([1; 32]),
+    ///     listen_port: Some(51820),
+    ///     peers: vec![WireguardPeer {
+    ///         public_key: [2; 32],
+    ///         allowed_ips: vec!["10.0.0.0/24".parse().unwrap()],
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    /// nl.wireguard_set(&wg, &config).unwrap();
+    /// ```
+    pub fn wireguard_set(
+        &mut self,
+        link: &(impl AsRef<LinkAttrs> + ?Sized),
+        config: &WireguardConfig,
+    ) -> Result<()> {
+        let index = match link.as_ref().index {
+            0 => self.link_get(link.as_ref())?.attrs().index,
+            index => index,
+        };
+
+        self.sockets
+            .entry(libc::NETLINK_GENERIC)
+            .or_insert(SocketHandle::new(libc::NETLINK_GENERIC)?)
+            .wireguard_set(index, config)
+    }
+
+    fn rule_handle(&mut self, cmd: RuleCmd, rule: &Rule) -> Result<()> {
+        self.sockets
+            .entry(libc::NETLINK_ROUTE)
+            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+            .rule_handle(cmd, rule)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{link::Kind, test_setup};
+    use crate::{
+        consts,
+        link::{Kind, OperState},
+        test_setup,
+    };
 
     use super::*;
 
+    #[test]
+    fn test_new_in_namespace_missing() {
+        let err = Netlink::new_in_namespace(Path::new("/var/run/netns/nosuchns"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("/var/run/netns/nosuchns"));
+    }
+
+    #[test]
+    fn test_link_cache() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        let (foo, _) = netlink
+            .veth_add_pair(&LinkAttrs::new("foo"), "bar")
+            .unwrap();
+        let index = netlink.link_index_cached("foo").unwrap();
+        assert_eq!(index, foo.attrs().index);
+
+        // Deleted behind the back of the cache, e.g. by another process
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        handle.link_del(foo.attrs()).unwrap();
+        assert_eq!(netlink.link_index_cached("foo").unwrap(), index);
+
+        // Reusing the name for another link, link_get_cached notices the stale index
+        let (foo, _) = netlink
+            .veth_add_pair(&LinkAttrs::new("foo"), "bar")
+            .unwrap();
+        assert_ne!(foo.attrs().index, index);
+
+        let link = netlink.link_get_cached("foo").unwrap();
+        assert_eq!(link.attrs().index, foo.attrs().index);
+        assert_eq!(netlink.link_index_cached("foo").unwrap(), foo.attrs().index);
+
+        netlink.link_del(&foo).unwrap();
+        assert!(netlink.link_index_cached("foo").is_err());
+        assert!(netlink.link_get_cached("foo").is_err());
+    }
+
     #[test]
     fn test_link_add_modify_del() {
         test_setup!();
@@ -675,12 +2250,34 @@ mod tests {
         netlink.link_setup(&link).unwrap();
 
         let link = netlink.link_get(&attr).unwrap();
-        assert_ne!(link.attrs().oper_state, 2);
+        assert_ne!(link.attrs().oper_state_enum(), OperState::Down);
 
         netlink.link_setup(&bar).unwrap();
 
         let bar = netlink.link_get(&bar_attr).unwrap();
-        assert_ne!(bar.attrs().oper_state, 2);
+        assert_ne!(bar.attrs().oper_state_enum(), OperState::Down);
+    }
+
+    #[test]
+    fn test_link_add_up() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        let link = Kind::Ifb(LinkAttrs {
+            flags: consts::IFF_UP,
+            ..LinkAttrs::new("foo")
+        });
+        netlink.link_add(&link).unwrap();
+
+        let link = netlink.link_get(link.attrs()).unwrap();
+        assert_ne!(link.attrs().raw_flags & libc::IFF_UP as u32, 0);
+
+        // Without the flag, the link is created down
+        let link = Kind::Ifb(LinkAttrs::new("bar"));
+        netlink.link_add(&link).unwrap();
+
+        let link = netlink.link_get(link.attrs()).unwrap();
+        assert_eq!(link.attrs().raw_flags & libc::IFF_UP as u32, 0);
     }
 
     #[test]
@@ -694,6 +2291,8 @@ mod tests {
             ageing_time: None,
             multicast_snooping: None,
             vlan_filtering: None,
+            stp_state: None,
+            priority: None,
         };
 
         netlink.link_add(&link).unwrap();
@@ -703,7 +2302,7 @@ mod tests {
         netlink.link_setup(&link).unwrap();
 
         let link = netlink.link_get(&LinkAttrs::new("foo")).unwrap();
-        assert_ne!(link.attrs().oper_state, 2);
+        assert_ne!(link.attrs().oper_state_enum(), OperState::Down);
     }
 
     #[test]
@@ -791,6 +2390,185 @@ mod tests {
             .all(|r| r.src.unwrap() == route.src.unwrap()));
     }
 
+    #[test]
+    fn test_list_by_link_name() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        let (foo, bar) = netlink
+            .veth_add_pair(&LinkAttrs::new("foo"), "bar")
+            .unwrap();
+        for (link, ip) in [(&foo, "10.0.0.1/24"), (&bar, "10.0.1.1/24")] {
+            netlink.link_setup(link).unwrap();
+            netlink
+                .addr_add(link, &Address::new(ip.parse().unwrap()))
+                .unwrap();
+            netlink
+                .neigh_add(&Neighbor::new(
+                    link.attrs().index,
+                    ip.parse::<ipnet::IpNet>().unwrap().addr(),
+                    &[0x02, 0, 0, 0, 0, 0x02],
+                ))
+                .unwrap();
+        }
+
+        // The link is looked up by name instead of standing for every link, or none
+        let by_name = LinkAttrs::new("foo");
+        let index = foo.attrs().index;
+
+        let addrs = netlink.addr_list(&by_name, AddrFamily::V4).unwrap();
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].index, index);
+
+        let routes = netlink.route_list(&by_name, AddrFamily::V4).unwrap();
+        assert!(!routes.is_empty());
+        assert!(routes.iter().all(|r| r.oif_index == index));
+
+        let neighs = netlink.neigh_list(&by_name, AddrFamily::V4, None).unwrap();
+        assert_eq!(neighs.len(), 1);
+        assert_eq!(neighs[0].index, index);
+
+        assert!(netlink
+            .addr_list(&LinkAttrs::new("nosuchlink"), AddrFamily::V4)
+            .is_err());
+    }
+
+    #[test]
+    fn test_route_replace_priority() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        let (foo, _) = netlink
+            .veth_add_pair(&LinkAttrs::new("foo"), "bar")
+            .unwrap();
+        netlink.link_setup(&foo).unwrap();
+        netlink
+            .addr_add(&foo, &Address::new("10.0.0.1/24".parse().unwrap()))
+            .unwrap();
+
+        let dst = "192.168.0.0/24".parse().unwrap();
+        let mut route = Route {
+            oif_index: foo.attrs().index,
+            dst: Some(dst),
+            gw: Some("10.0.0.2".parse().unwrap()),
+            priority: Some(100),
+            ..Default::default()
+        };
+        netlink.route_add(&route).unwrap();
+
+        // Another route to the same prefix with a different metric must be left alone
+        let other = Route {
+            gw: Some("10.0.0.4".parse().unwrap()),
+            priority: Some(200),
+            ..route.clone()
+        };
+        netlink.route_add(&other).unwrap();
+
+        let routes = |netlink: &mut Netlink| {
+            netlink
+                .route_list(&foo, AddrFamily::V4)
+                .unwrap()
+                .into_iter()
+                .filter(|r| r.dst == Some(dst))
+                .map(|r| (r.priority, r.gw.unwrap()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(routes(&mut netlink).len(), 2);
+
+        route.gw = Some("10.0.0.3".parse().unwrap());
+        netlink.route_replace(&route).unwrap();
+
+        let mut res = routes(&mut netlink);
+        res.sort();
+        assert_eq!(
+            res,
+            vec![
+                (Some(100), "10.0.0.3".parse().unwrap()),
+                (Some(200), "10.0.0.4".parse().unwrap())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_route_add_default() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        let (foo, _) = netlink
+            .veth_add_pair(&LinkAttrs::new("foo"), "bar")
+            .unwrap();
+        netlink.link_setup(&foo).unwrap();
+        netlink
+            .addr_add(&foo, &Address::new("10.0.0.1/24".parse().unwrap()))
+            .unwrap();
+
+        // No dst and no family: a default route via the gateway
+        let route = Route {
+            gw: Some("10.0.0.2".parse().unwrap()),
+            ..Default::default()
+        };
+        netlink.route_add(&route).unwrap();
+
+        let routes = netlink.route_list(&foo, AddrFamily::V4).unwrap();
+        let default: Vec<_> = routes.iter().filter(|r| r.dst.is_none()).collect();
+        assert_eq!(default.len(), 1);
+        assert_eq!(default[0].gw, route.gw);
+        assert_eq!(default[0].oif_index, foo.attrs().index);
+
+        let res = netlink.route_get(&"192.168.0.1".parse().unwrap()).unwrap();
+        assert_eq!(res[0].gw, route.gw);
+    }
+
+    #[test]
+    fn test_route_prepend() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        let (foo, _) = netlink
+            .veth_add_pair(&LinkAttrs::new("foo"), "bar")
+            .unwrap();
+        netlink.link_setup(&foo).unwrap();
+        netlink
+            .addr_add(&foo, &Address::new("10.0.0.1/24".parse().unwrap()))
+            .unwrap();
+
+        // Without other routes to the prefix, the route is added as is
+        let mut route = Route {
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            gw: Some("10.0.0.2".parse().unwrap()),
+            priority: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(netlink.route_prepend(&route).unwrap(), 100);
+
+        route.gw = Some("10.0.0.3".parse().unwrap());
+        assert_eq!(netlink.route_prepend(&route).unwrap(), 99);
+
+        // Routes to another prefix don't matter
+        let other = Route {
+            dst: Some("192.168.1.0/24".parse().unwrap()),
+            ..route.clone()
+        };
+        netlink.route_add(&other).unwrap();
+
+        route.gw = Some("10.0.0.4".parse().unwrap());
+        assert_eq!(netlink.route_prepend(&route).unwrap(), 98);
+
+        let routes = netlink.route_get(&"192.168.0.1".parse().unwrap()).unwrap();
+        assert_eq!(routes[0].gw, route.gw);
+
+        // Nothing can take precedence over a route with metric 0
+        let dst = Some("192.168.2.0/24".parse().unwrap());
+        netlink
+            .route_add(&Route {
+                dst,
+                priority: Some(0),
+                ..route.clone()
+            })
+            .unwrap();
+        assert!(netlink.route_prepend(&Route { dst, ..route }).is_err());
+    }
+
     #[test]
     fn test_route_add_del() {
         test_setup!();
@@ -36,8 +36,9 @@ impl NetlinkRequest {
             buf.extend(data);
         }
 
-        let len = buf.len() as u16;
-        buf[..2].copy_from_slice(&len.to_ne_bytes());
+        // nlmsg_len is a u32, requests may well exceed 64KiB
+        let len = buf.len() as u32;
+        buf[..4].copy_from_slice(&len.to_ne_bytes());
 
         Ok(buf)
     }
@@ -105,4 +106,14 @@ mod tests {
         assert_eq!(buf.len(), 40);
         assert_eq!(req.header.nlmsg_len, 38);
     }
+
+    #[test]
+    fn test_netlink_request_large() {
+        let mut req = NetlinkRequest::new(0, 0);
+        req.add_raw_data(vec![0; 70000]);
+
+        let buf = req.serialize().unwrap();
+        assert_eq!(buf.len(), 70016);
+        assert_eq!(u32::from_ne_bytes(buf[..4].try_into().unwrap()), 70016);
+    }
 }
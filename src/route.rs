@@ -1,12 +1,14 @@
 use std::net::IpAddr;
 
-use anyhow::{bail, Ok, Result};
+use anyhow::{Ok, Result};
 use ipnet::IpNet;
 
 use crate::{
-    message::{NetlinkRouteAttr, RouteMessage},
+    consts,
+    error::Error,
+    message::{NetlinkRouteAttr, RouteMessage, RtNextHop},
     request::{NetlinkRequest, NetlinkRequestData},
-    utils::vec_to_addr,
+    utils::{align_of, vec_to_addr},
 };
 
 #[derive(PartialEq)]
@@ -18,25 +20,191 @@ pub enum RtCmd {
     Show,
 }
 
+/// Which routes `route_list` returns. The filter is sent with the dump request, and
+/// is applied again to the routes received when the kernel ignored it (on kernels
+/// before 4.20, which lack strict checking).
 pub enum RtFilter {
+    /// Routes through the given output link.
     Oif,
+    /// Routes of the given table, through any link.
+    Table(u32),
+    /// Routes of the given table through the given output link.
+    OifTable(u32),
     None,
 }
 
-#[derive(Default, Debug)]
+/// Type of a route, as set in `rtm_type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteType {
+    Unspec,
+    Unicast,
+    Local,
+    Broadcast,
+    Anycast,
+    Multicast,
+    Blackhole,
+    Unreachable,
+    Prohibit,
+    Throw,
+    Nat,
+    Other(u8),
+}
+
+impl From<u8> for RouteType {
+    fn from(rtm_type: u8) -> Self {
+        match rtm_type {
+            libc::RTN_UNSPEC => Self::Unspec,
+            libc::RTN_UNICAST => Self::Unicast,
+            libc::RTN_LOCAL => Self::Local,
+            libc::RTN_BROADCAST => Self::Broadcast,
+            libc::RTN_ANYCAST => Self::Anycast,
+            libc::RTN_MULTICAST => Self::Multicast,
+            libc::RTN_BLACKHOLE => Self::Blackhole,
+            libc::RTN_UNREACHABLE => Self::Unreachable,
+            libc::RTN_PROHIBIT => Self::Prohibit,
+            libc::RTN_THROW => Self::Throw,
+            libc::RTN_NAT => Self::Nat,
+            _ => Self::Other(rtm_type),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
 pub struct Route {
     pub oif_index: i32,
     pub iif_index: i32,
+    /// Address family, taken from the addresses of the route when 0.
+    /// Only needed for a route without any, e.g. a default route through a link.
     pub family: u8,
     pub dst: Option<IpNet>,
     pub src: Option<IpAddr>,
     pub gw: Option<IpAddr>,
     pub tos: u8,
+    /// Routing table of the route. Routes are added to `RT_TABLE_MAIN` when 0.
     pub table: u8,
+    /// Routing table of the route as a 32-bit id, for tables above 255 such as 10000.
+    /// Takes precedence over `table` when set, and is only read back for such tables.
+    pub table_id: Option<u32>,
     pub protocol: u8,
     pub scope: u8,
     pub rtm_type: u8,
     pub flags: u32,
+    /// Metric of the route. Along with dst, tos and table, it is part of the key the
+    /// kernel uses to find the route to replace or delete. When unset, the kernel
+    /// uses its default (0 for IPv4, 1024 for IPv6), while `Some(0)` is sent as is.
+    pub priority: Option<u32>,
+    /// Lifetime of the route in seconds, after which the kernel removes it.
+    /// Only IPv6 routes can expire; when read back it is the remaining lifetime.
+    pub expires: Option<u32>,
+    /// Next-hops of an equal-cost multipath route, used instead of `gw` and `oif_index`.
+    pub multipath: Vec<NextHop>,
+}
+
+/// A next-hop of a multipath route.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct NextHop {
+    pub gw: Option<IpAddr>,
+    pub oif_index: i32,
+    /// Share of the traffic sent through this hop relative to the others,
+    /// as `weight` of `ip route`. 0 is the same as 1.
+    pub weight: u8,
+}
+
+impl Route {
+    pub fn builder() -> RouteBuilder {
+        RouteBuilder::default()
+    }
+
+    pub fn route_type(&self) -> RouteType {
+        self.rtm_type.into()
+    }
+
+    /// Whether the route is a cache entry cloned by the kernel (`RTM_F_CLONED`)
+    /// rather than an entry of the FIB. Such routes should not be added back or deleted.
+    pub fn is_cloned(&self) -> bool {
+        self.flags & libc::RTM_F_CLONED != 0
+    }
+}
+
+/// Builds a `Route`, checking that its addresses are all of the same family.
+///
+/// # Examples
+///
+/// ```
+/// use lnwasi::route::Route;
+///
+/// let route = Route::builder()
+///     .dst("192.168.0.0/24".parse().unwrap())
+///     .gw("10.0.0.1".parse().unwrap())
+///     .table(10000)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(route.table_id, Some(10000));
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct RouteBuilder {
+    route: Route,
+}
+
+impl RouteBuilder {
+    pub fn oif(mut self, index: i32) -> Self {
+        self.route.oif_index = index;
+        self
+    }
+
+    pub fn dst(mut self, dst: IpNet) -> Self {
+        self.route.dst = Some(dst);
+        self
+    }
+
+    pub fn src(mut self, src: IpAddr) -> Self {
+        self.route.src = Some(src);
+        self
+    }
+
+    pub fn gw(mut self, gw: IpAddr) -> Self {
+        self.route.gw = Some(gw);
+        self
+    }
+
+    /// Tables above 255 are set in `table_id`.
+    pub fn table(mut self, table: u32) -> Self {
+        if table > u8::MAX as u32 {
+            self.route.table = libc::RT_TABLE_UNSPEC;
+            self.route.table_id = Some(table);
+        } else {
+            self.route.table = table as u8;
+            self.route.table_id = None;
+        }
+        self
+    }
+
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.route.priority = Some(priority);
+        self
+    }
+
+    pub fn scope(mut self, scope: u8) -> Self {
+        self.route.scope = scope;
+        self
+    }
+
+    pub fn build(self) -> Result<Route> {
+        let route = self.route;
+        let mut families = [route.dst.map(|dst| dst.addr()), route.src, route.gw]
+            .into_iter()
+            .flatten()
+            .map(|addr| addr.is_ipv4());
+
+        if let Some(first) = families.next() {
+            if families.any(|is_ipv4| is_ipv4 != first) {
+                return Err(Error::FamilyMismatch("gw, src and dst".to_string()).into());
+            }
+        }
+
+        Ok(route)
+    }
 }
 
 pub fn route_deserialize(buf: &[u8]) -> Result<Route> {
@@ -50,6 +218,7 @@ pub fn route_deserialize(buf: &[u8]) -> Result<Route> {
         protocol: if_route_msg.protocol,
         scope: if_route_msg.scope,
         rtm_type: if_route_msg.rtm_type,
+        flags: if_route_msg.flags,
         ..Default::default()
     };
 
@@ -70,6 +239,31 @@ pub fn route_deserialize(buf: &[u8]) -> Result<Route> {
             libc::RTA_IIF => {
                 route.iif_index = i32::from_ne_bytes(attr.value[..4].try_into()?);
             }
+            // The header only holds the table when it fits in 8 bits
+            libc::RTA_TABLE => match u32::from_ne_bytes(attr.value[..4].try_into()?) {
+                id if id > u8::MAX as u32 => {
+                    route.table = libc::RT_TABLE_UNSPEC;
+                    route.table_id = Some(id);
+                }
+                id => route.table = id as u8,
+            },
+            libc::RTA_MULTIPATH => {
+                route.multipath = multipath_deserialize(&attr.value)?;
+            }
+            libc::RTA_PRIORITY => {
+                route.priority = Some(u32::from_ne_bytes(attr.value[..4].try_into()?));
+            }
+            consts::RTA_EXPIRES => {
+                route.expires = Some(u32::from_ne_bytes(attr.value[..4].try_into()?));
+            }
+            libc::RTA_CACHEINFO => {
+                // The kernel reports the remaining lifetime here, in clock ticks
+                let at = consts::RTA_CACHEINFO_EXPIRES;
+                let ticks = i32::from_ne_bytes(attr.value[at..at + 4].try_into()?);
+                if ticks > 0 && route.expires.is_none() {
+                    route.expires = Some((ticks as libc::c_long / clock_ticks()) as u32);
+                }
+            }
             // TODO: more types
             _ => {}
         }
@@ -78,6 +272,78 @@ pub fn route_deserialize(buf: &[u8]) -> Result<Route> {
     Ok(route)
 }
 
+fn multipath_deserialize(mut buf: &[u8]) -> Result<Vec<NextHop>> {
+    let mut hops = vec![];
+
+    while buf.len() >= consts::RT_NEXTHOP_SIZE {
+        let rtnh = RtNextHop::deserialize(buf)?;
+        let len = rtnh.len as usize;
+        if len < consts::RT_NEXTHOP_SIZE || len > buf.len() {
+            return Err(Error::Parse(format!("invalid rtnexthop length {len}")).into());
+        }
+
+        let mut hop = NextHop {
+            oif_index: rtnh.ifindex,
+            // The kernel counts the hops beyond the first
+            weight: rtnh.hops.saturating_add(1),
+            ..Default::default()
+        };
+
+        for attr in NetlinkRouteAttr::from(&buf[consts::RT_NEXTHOP_SIZE..len])? {
+            if attr.rt_attr.rta_type == libc::RTA_GATEWAY {
+                hop.gw = Some(vec_to_addr(attr.value)?);
+            }
+        }
+
+        hops.push(hop);
+        buf = &buf[align_of(len, consts::RTA_ALIGNTO).min(buf.len())..];
+    }
+
+    Ok(hops)
+}
+
+fn multipath_serialize(hops: &[NextHop], family: &mut u8) -> Result<Vec<u8>> {
+    let mut buf = vec![];
+
+    for hop in hops {
+        let mut gw = vec![];
+
+        if let Some(addr) = hop.gw {
+            let (hop_family, gw_data) = match addr {
+                IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec()),
+                IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec()),
+            };
+
+            if *family == 0 {
+                *family = hop_family as u8;
+            } else if *family != hop_family as u8 {
+                return Err(Error::FamilyMismatch("next-hops and dst".to_string()).into());
+            }
+
+            gw = NetlinkRouteAttr::new(libc::RTA_GATEWAY, gw_data).serialize()?;
+        }
+
+        let rtnh = RtNextHop {
+            len: (consts::RT_NEXTHOP_SIZE + gw.len()) as u16,
+            flags: 0,
+            hops: hop.weight.saturating_sub(1),
+            ifindex: hop.oif_index,
+        };
+
+        buf.extend(rtnh.serialize()?);
+        buf.extend(gw);
+    }
+
+    Ok(buf)
+}
+
+fn clock_ticks() -> libc::c_long {
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks,
+        _ => 100,
+    }
+}
+
 pub fn route_handle(cmd: RtCmd, route: &Route) -> Result<NetlinkRequest> {
     let (proto, flags) = match cmd {
         RtCmd::Add => (
@@ -96,6 +362,18 @@ pub fn route_handle(cmd: RtCmd, route: &Route) -> Result<NetlinkRequest> {
         RtCmd::Show => (libc::RTM_GETROUTE, libc::NLM_F_DUMP),
     };
 
+    if proto == libc::RTM_NEWROUTE
+        && route.dst.is_none()
+        && route.gw.is_none()
+        && route.oif_index == 0
+        && route.multipath.is_empty()
+    {
+        return Err(Error::InvalidRoute(
+            "at least one of dst, gw or oif_index must be set".to_string(),
+        )
+        .into());
+    }
+
     let mut req = NetlinkRequest::new(proto, flags);
 
     let mut msg = match proto {
@@ -132,7 +410,7 @@ pub fn route_handle(cmd: RtCmd, route: &Route) -> Result<NetlinkRequest> {
         if msg.family == 0 {
             msg.family = family as u8;
         } else if msg.family != family as u8 {
-            bail!("src and dst address family mismatch");
+            return Err(Error::FamilyMismatch("src and dst".to_string()).into());
         }
 
         attrs.push(Box::new(NetlinkRouteAttr::new(libc::RTA_PREFSRC, src_data)));
@@ -147,16 +425,77 @@ pub fn route_handle(cmd: RtCmd, route: &Route) -> Result<NetlinkRequest> {
         if msg.family == 0 {
             msg.family = family as u8;
         } else if msg.family != family as u8 {
-            bail!("gw, src and dst address family mismatch");
+            return Err(Error::FamilyMismatch("gw, src and dst".to_string()).into());
         }
 
         attrs.push(Box::new(NetlinkRouteAttr::new(libc::RTA_GATEWAY, gw_data)));
     }
 
+    if !route.multipath.is_empty() {
+        let multipath = multipath_serialize(&route.multipath, &mut msg.family)?;
+        attrs.push(Box::new(NetlinkRouteAttr::new(
+            libc::RTA_MULTIPATH,
+            multipath,
+        )));
+    }
+
+    if let Some(priority) = route.priority {
+        attrs.push(Box::new(NetlinkRouteAttr::new(
+            libc::RTA_PRIORITY,
+            priority.to_ne_bytes().to_vec(),
+        )));
+    }
+
+    if let Some(expires) = route.expires {
+        attrs.push(Box::new(NetlinkRouteAttr::new(
+            consts::RTA_EXPIRES,
+            expires.to_ne_bytes().to_vec(),
+        )));
+    }
+
+    // Without dst the route is the default route of its family, which is taken from
+    // the gateway or src if any, or else from `family` (AF_INET by default, as `ip route` does)
+    if cmd != RtCmd::Show && route.family != 0 {
+        if msg.family == 0 {
+            msg.family = route.family;
+        } else if msg.family != route.family {
+            return Err(Error::FamilyMismatch(format!(
+                "route family {} doesn't match the family of its addresses",
+                route.family
+            ))
+            .into());
+        }
+    }
+
+    if proto == libc::RTM_NEWROUTE && msg.family == 0 {
+        msg.family = libc::AF_INET as u8;
+    }
+
     // TODO: more attributes to be added
 
-    msg.flags = route.flags;
+    // The flags of a route read back are mostly set by the kernel (e.g. RTM_F_CLONED
+    // or RTNH_F_LINKDOWN), of which only onlink can be requested when changing a route
+    msg.flags = match cmd {
+        RtCmd::Show => route.flags,
+        _ => route.flags & consts::RTNH_F_ONLINK,
+    };
     msg.scope = route.scope;
+    msg.tos = route.tos;
+
+    // 0 (RT_TABLE_UNSPEC) keeps the default of the message, i.e. RT_TABLE_MAIN.
+    // Dumps take the table as RTA_TABLE, which the kernel filters on with strict checking.
+    match route.table_id {
+        Some(id) if id > u8::MAX as u32 || cmd == RtCmd::Show => {
+            msg.table = libc::RT_TABLE_UNSPEC;
+            attrs.push(Box::new(NetlinkRouteAttr::new(
+                libc::RTA_TABLE,
+                id.to_ne_bytes().to_vec(),
+            )));
+        }
+        Some(id) => msg.table = id as u8,
+        None if route.table != libc::RT_TABLE_UNSPEC => msg.table = route.table,
+        None => {}
+    }
 
     req.add_data(msg);
 
@@ -180,7 +519,8 @@ pub fn route_get(dst: &IpAddr) -> Result<NetlinkRequest> {
 
     msg.family = family as u8;
     msg.dst_len = bit_len;
-    msg.flags = libc::RTM_F_LOOKUP_TABLE;
+    // Unlike RTM_F_FIB_MATCH, this keeps the selected source address (RTA_PREFSRC) in the result
+    msg.flags = consts::RTM_F_LOOKUP_TABLE;
 
     let rta_dst = Box::new(NetlinkRouteAttr::new(libc::RTA_DST, dst_data));
 
@@ -189,3 +529,377 @@ pub fn route_get(dst: &IpAddr) -> Result<NetlinkRequest> {
 
     Ok(req)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 192.168.0.0/24 dev eth0 proto kernel scope link src 192.168.0.10
+    #[rustfmt::skip]
+    static RT_NEW_ROUTE_MSG: [u8; 44] = [
+        0x02, // family = AF_INET
+        0x18, // dst len = 24
+        0x00, // src len
+        0x00, // tos
+        0xfe, // table = RT_TABLE_MAIN
+        0x02, // protocol = RTPROT_KERNEL
+        0xfd, // scope = RT_SCOPE_LINK
+        0x01, // type = RTN_UNICAST
+        0x00, 0x00, 0x00, 0x00, // flags
+
+        // nlas
+        0x08, 0x00, 0x0f, 0x00, 0xfe, 0x00, 0x00, 0x00, // Table L=8,T=15,V=254
+        0x08, 0x00, 0x01, 0x00, 0xc0, 0xa8, 0x00, 0x00, // Dst L=8,T=1,V=192.168.0.0
+        0x08, 0x00, 0x07, 0x00, 0xc0, 0xa8, 0x00, 0x0a, // PrefSrc L=8,T=7,V=192.168.0.10
+        0x08, 0x00, 0x04, 0x00, 0x02, 0x00, 0x00, 0x00, // Oif L=8,T=4,V=2
+    ];
+
+    // default via 192.168.0.1 dev eth0 proto static metric 100
+    #[rustfmt::skip]
+    static RT_NEW_DEFAULT_ROUTE_MSG: [u8; 44] = [
+        0x02, // family = AF_INET
+        0x00, // dst len = 0
+        0x00, // src len
+        0x00, // tos
+        0xfe, // table = RT_TABLE_MAIN
+        0x04, // protocol = RTPROT_STATIC
+        0x00, // scope = RT_SCOPE_UNIVERSE
+        0x01, // type = RTN_UNICAST
+        0x00, 0x00, 0x00, 0x00, // flags
+
+        // nlas
+        0x08, 0x00, 0x0f, 0x00, 0xfe, 0x00, 0x00, 0x00, // Table L=8,T=15,V=254
+        0x08, 0x00, 0x06, 0x00, 0x64, 0x00, 0x00, 0x00, // Priority L=8,T=6,V=100
+        0x08, 0x00, 0x05, 0x00, 0xc0, 0xa8, 0x00, 0x01, // Gateway L=8,T=5,V=192.168.0.1
+        0x08, 0x00, 0x04, 0x00, 0x02, 0x00, 0x00, 0x00, // Oif L=8,T=4,V=2
+    ];
+
+    #[test]
+    fn test_route_deserialize() {
+        let route = route_deserialize(&RT_NEW_ROUTE_MSG).unwrap();
+
+        assert_eq!(route.family, libc::AF_INET as u8);
+        assert_eq!(route.table, libc::RT_TABLE_MAIN);
+        assert_eq!(route.protocol, libc::RTPROT_KERNEL);
+        assert_eq!(route.scope, libc::RT_SCOPE_LINK);
+        assert_eq!(route.rtm_type, libc::RTN_UNICAST);
+        assert_eq!(route.dst, Some("192.168.0.0/24".parse().unwrap()));
+        assert_eq!(route.src, Some("192.168.0.10".parse().unwrap()));
+        assert_eq!(route.gw, None);
+        assert_eq!(route.oif_index, 2);
+        assert!(!route.is_cloned());
+    }
+
+    #[test]
+    fn test_route_type() {
+        let mut msg = RT_NEW_ROUTE_MSG;
+        assert_eq!(
+            route_deserialize(&msg).unwrap().route_type(),
+            RouteType::Unicast
+        );
+
+        msg[7] = libc::RTN_LOCAL;
+        assert_eq!(
+            route_deserialize(&msg).unwrap().route_type(),
+            RouteType::Local
+        );
+
+        msg[7] = libc::RTN_BROADCAST;
+        assert_eq!(
+            route_deserialize(&msg).unwrap().route_type(),
+            RouteType::Broadcast
+        );
+
+        msg[7] = 42;
+        assert_eq!(
+            route_deserialize(&msg).unwrap().route_type(),
+            RouteType::Other(42)
+        );
+    }
+
+    #[test]
+    fn test_route_table_id() {
+        let route = Route {
+            dst: Some("10.0.0.0/24".parse().unwrap()),
+            table_id: Some(10000),
+            ..Default::default()
+        };
+
+        let buf = route_handle(RtCmd::Add, &route)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let msg = RouteMessage::deserialize(&buf[16..]).unwrap();
+        assert_eq!(msg.table, libc::RT_TABLE_UNSPEC);
+
+        let route = route_deserialize(&buf[16..]).unwrap();
+        assert_eq!(route.table, libc::RT_TABLE_UNSPEC);
+        assert_eq!(route.table_id, Some(10000));
+
+        // Small ids fit in the header
+        let route = Route {
+            table_id: Some(100),
+            ..route
+        };
+        let buf = route_handle(RtCmd::Add, &route)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let route = route_deserialize(&buf[16..]).unwrap();
+        assert_eq!(route.table, 100);
+        assert_eq!(route.table_id, None);
+    }
+
+    #[test]
+    fn test_route_builder() {
+        let route = Route::builder()
+            .oif(2)
+            .dst("10.0.0.0/24".parse().unwrap())
+            .src("10.0.0.1".parse().unwrap())
+            .gw("10.0.0.254".parse().unwrap())
+            .table(100)
+            .priority(10)
+            .scope(libc::RT_SCOPE_LINK)
+            .build()
+            .unwrap();
+
+        assert_eq!(route.oif_index, 2);
+        assert_eq!(route.dst, Some("10.0.0.0/24".parse().unwrap()));
+        assert_eq!(route.src, Some("10.0.0.1".parse().unwrap()));
+        assert_eq!(route.gw, Some("10.0.0.254".parse().unwrap()));
+        assert_eq!(route.table, 100);
+        assert_eq!(route.table_id, None);
+        assert_eq!(route.priority, Some(10));
+        assert_eq!(route.scope, libc::RT_SCOPE_LINK);
+
+        let route = Route::builder().table(10000).build().unwrap();
+        assert_eq!(route.table, libc::RT_TABLE_UNSPEC);
+        assert_eq!(route.table_id, Some(10000));
+
+        let err = Route::builder()
+            .dst("10.0.0.0/24".parse().unwrap())
+            .gw("2001:db8::1".parse().unwrap())
+            .build()
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::FamilyMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_route_multipath() {
+        let route = Route {
+            dst: Some("10.0.0.0/24".parse().unwrap()),
+            multipath: vec![
+                NextHop {
+                    gw: Some("192.168.0.1".parse().unwrap()),
+                    oif_index: 2,
+                    weight: 1,
+                },
+                NextHop {
+                    gw: Some("192.168.1.1".parse().unwrap()),
+                    oif_index: 3,
+                    weight: 10,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let buf = route_handle(RtCmd::Add, &route)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let res = route_deserialize(&buf[16..]).unwrap();
+        assert_eq!(res.multipath, route.multipath);
+
+        let route = Route {
+            multipath: vec![NextHop {
+                gw: Some("2001:db8::1".parse().unwrap()),
+                ..Default::default()
+            }],
+            ..route
+        };
+        assert!(route_handle(RtCmd::Add, &route).is_err());
+
+        // The length of a next-hop must fit in the attribute
+        let mut buf = RtNextHop {
+            len: 16,
+            ..Default::default()
+        }
+        .serialize()
+        .unwrap();
+        assert!(multipath_deserialize(&buf).is_err());
+
+        buf[0] = 4;
+        assert!(multipath_deserialize(&buf).is_err());
+    }
+
+    #[test]
+    fn test_route_deserialize_default() {
+        let route = route_deserialize(&RT_NEW_DEFAULT_ROUTE_MSG).unwrap();
+
+        assert_eq!(route.protocol, libc::RTPROT_STATIC);
+        assert_eq!(route.scope, libc::RT_SCOPE_UNIVERSE);
+        assert_eq!(route.dst, None);
+        assert_eq!(route.src, None);
+        assert_eq!(route.gw, Some("192.168.0.1".parse().unwrap()));
+        assert_eq!(route.oif_index, 2);
+    }
+
+    #[test]
+    fn test_route_deserialize_flags() {
+        let msg = RouteMessage {
+            family: libc::AF_INET6 as u8,
+            dst_len: 128,
+            table: libc::RT_TABLE_MAIN,
+            rtm_type: libc::RTN_UNICAST,
+            flags: libc::RTM_F_CLONED,
+            ..Default::default()
+        };
+
+        let mut buf = msg.serialize().unwrap();
+        buf.extend(
+            NetlinkRouteAttr::new(libc::RTA_OIF, 2i32.to_ne_bytes().to_vec())
+                .serialize()
+                .unwrap(),
+        );
+
+        let route = route_deserialize(&buf).unwrap();
+        assert_eq!(route.oif_index, 2);
+        assert_eq!(route.flags, libc::RTM_F_CLONED);
+        assert!(route.is_cloned());
+
+        let route = Route::default();
+        assert!(!route.is_cloned());
+    }
+
+    #[test]
+    fn test_route_handle_flags() {
+        let route = Route {
+            oif_index: 2,
+            dst: Some("10.0.0.0/24".parse().unwrap()),
+            flags: libc::RTM_F_CLONED | consts::RTNH_F_ONLINK,
+            ..Default::default()
+        };
+
+        // Flags set by the kernel are not sent back
+        for cmd in [RtCmd::Add, RtCmd::Replace, RtCmd::Del] {
+            let buf = route_handle(cmd, &route).unwrap().serialize().unwrap();
+            let msg = RouteMessage::deserialize(&buf[16..]).unwrap();
+            assert_eq!(msg.flags, consts::RTNH_F_ONLINK);
+        }
+
+        let buf = route_handle(RtCmd::Show, &route)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let msg = RouteMessage::deserialize(&buf[16..]).unwrap();
+        assert_eq!(msg.flags, route.flags);
+    }
+
+    #[test]
+    fn test_route_handle_invalid() {
+        for cmd in [RtCmd::Add, RtCmd::Append, RtCmd::Replace] {
+            let err = route_handle(cmd, &Route::default()).err().unwrap();
+            assert!(matches!(
+                err.downcast_ref::<Error>(),
+                Some(Error::InvalidRoute(_))
+            ));
+            assert!(err.to_string().contains("dst, gw or oif_index"));
+        }
+
+        assert!(route_handle(RtCmd::Show, &Route::default()).is_ok());
+
+        let route = Route {
+            oif_index: 1,
+            ..Default::default()
+        };
+        assert!(route_handle(RtCmd::Add, &route).is_ok());
+    }
+
+    #[test]
+    fn test_route_handle_show() {
+        let route = Route {
+            oif_index: 3,
+            table_id: Some(100),
+            ..Default::default()
+        };
+        let buf = route_handle(RtCmd::Show, &route)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        assert_eq!(u16::from_ne_bytes([buf[4], buf[5]]), libc::RTM_GETROUTE);
+
+        let msg = RouteMessage::deserialize(&buf[16..]).unwrap();
+        assert_eq!(msg.table, libc::RT_TABLE_UNSPEC);
+
+        let attrs = NetlinkRouteAttr::map(&buf[16 + consts::ROUTE_MSG_SIZE..]).unwrap();
+        assert_eq!(attrs[&libc::RTA_OIF], 3i32.to_ne_bytes());
+        assert_eq!(attrs[&libc::RTA_TABLE], 100u32.to_ne_bytes());
+
+        // Without filter, the whole table is dumped
+        let buf = route_handle(RtCmd::Show, &Route::default())
+            .unwrap()
+            .serialize()
+            .unwrap();
+        assert_eq!(buf.len(), 16 + consts::ROUTE_MSG_SIZE);
+    }
+
+    #[test]
+    fn test_route_handle_default() {
+        // The family of a default route comes from its gateway
+        let route = Route {
+            gw: Some("2001:db8::1".parse().unwrap()),
+            ..Default::default()
+        };
+        let buf = route_handle(RtCmd::Add, &route)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let msg = RouteMessage::deserialize(&buf[16..]).unwrap();
+        assert_eq!(msg.family, libc::AF_INET6 as u8);
+        assert_eq!(msg.dst_len, 0);
+
+        // Metric 0 is sent, unlike an unset one
+        let attrs = NetlinkRouteAttr::map(&buf[16 + consts::ROUTE_MSG_SIZE..]).unwrap();
+        assert!(!attrs.contains_key(&libc::RTA_PRIORITY));
+
+        let buf = route_handle(
+            RtCmd::Add,
+            &Route {
+                priority: Some(0),
+                ..route
+            },
+        )
+        .unwrap()
+        .serialize()
+        .unwrap();
+        let attrs = NetlinkRouteAttr::map(&buf[16 + consts::ROUTE_MSG_SIZE..]).unwrap();
+        assert_eq!(attrs[&libc::RTA_PRIORITY], 0u32.to_ne_bytes());
+
+        // or from the route itself when it has no address
+        let route = Route {
+            oif_index: 1,
+            family: libc::AF_INET6 as u8,
+            ..Default::default()
+        };
+        let buf = route_handle(RtCmd::Add, &route)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let msg = RouteMessage::deserialize(&buf[16..]).unwrap();
+        assert_eq!(msg.family, libc::AF_INET6 as u8);
+
+        let route = Route {
+            gw: Some("10.0.0.1".parse().unwrap()),
+            family: libc::AF_INET6 as u8,
+            ..Default::default()
+        };
+        let err = route_handle(RtCmd::Add, &route).err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::FamilyMismatch(_))
+        ));
+    }
+}
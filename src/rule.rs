@@ -0,0 +1,257 @@
+use anyhow::Result;
+use ipnet::IpNet;
+
+use crate::{
+    addr::AddrFamily,
+    consts,
+    error::Error,
+    message::{NetlinkRouteAttr, RuleMessage},
+    request::{NetlinkRequest, NetlinkRequestData},
+    utils::{vec_to_addr, zero_terminated},
+};
+
+pub enum RuleCmd {
+    Add,
+    Del,
+}
+
+/// A routing policy rule, selecting the routing table to look up.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// Address family, taken from `src` or `dst` when 0 (`AF_INET` if neither is set).
+    pub family: u8,
+    /// Preference of the rule, lower first. The kernel picks one when `None`.
+    pub priority: Option<u32>,
+    /// Routing table to look up. Rules are added for `RT_TABLE_MAIN` when 0,
+    /// and deleted regardless of their table.
+    pub table: u32,
+    pub src: Option<IpNet>,
+    pub dst: Option<IpNet>,
+    /// Firewall mark of the packets, compared after applying `mask` if set.
+    pub mark: Option<u32>,
+    pub mask: Option<u32>,
+    /// Name of the interface the packets come in from, any if empty.
+    pub iif: String,
+    /// Name of the interface the packets go out to, any if empty.
+    pub oif: String,
+}
+
+pub fn rule_deserialize(buf: &[u8]) -> Result<Rule> {
+    let msg = RuleMessage::deserialize(buf)?;
+    let rt_attrs = NetlinkRouteAttr::from(&buf[msg.len()..])?;
+
+    let mut rule = Rule {
+        family: msg.family,
+        table: msg.table.into(),
+        ..Default::default()
+    };
+
+    for attr in rt_attrs {
+        match attr.rt_attr.rta_type {
+            consts::FRA_SRC => {
+                rule.src = Some(IpNet::new(vec_to_addr(attr.value)?, msg.src_len)?);
+            }
+            consts::FRA_DST => {
+                rule.dst = Some(IpNet::new(vec_to_addr(attr.value)?, msg.dst_len)?);
+            }
+            consts::FRA_PRIORITY => {
+                rule.priority = Some(u32::from_ne_bytes(attr.value[..4].try_into()?));
+            }
+            consts::FRA_TABLE => {
+                rule.table = u32::from_ne_bytes(attr.value[..4].try_into()?);
+            }
+            consts::FRA_FWMARK => {
+                rule.mark = Some(u32::from_ne_bytes(attr.value[..4].try_into()?));
+            }
+            consts::FRA_FWMASK => {
+                rule.mask = Some(u32::from_ne_bytes(attr.value[..4].try_into()?));
+            }
+            consts::FRA_IIFNAME => {
+                let name = attr.value.strip_suffix(&[0]).unwrap_or(&attr.value);
+                rule.iif = String::from_utf8(name.to_vec())?;
+            }
+            consts::FRA_OIFNAME => {
+                let name = attr.value.strip_suffix(&[0]).unwrap_or(&attr.value);
+                rule.oif = String::from_utf8(name.to_vec())?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(rule)
+}
+
+pub fn rule_handle(cmd: RuleCmd, rule: &Rule) -> Result<NetlinkRequest> {
+    let (proto, flags) = match cmd {
+        RuleCmd::Add => (
+            libc::RTM_NEWRULE,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        ),
+        RuleCmd::Del => (libc::RTM_DELRULE, libc::NLM_F_ACK),
+    };
+
+    let mut req = NetlinkRequest::new(proto, flags);
+    let mut msg = Box::new(RuleMessage::new(rule.family));
+    let mut attrs = vec![];
+
+    for (rta_type, net) in [(consts::FRA_SRC, rule.src), (consts::FRA_DST, rule.dst)] {
+        let Some(net) = net else {
+            continue;
+        };
+
+        let (family, data) = match net {
+            IpNet::V4(ip) => (libc::AF_INET as u8, ip.addr().octets().to_vec()),
+            IpNet::V6(ip) => (libc::AF_INET6 as u8, ip.addr().octets().to_vec()),
+        };
+
+        if msg.family == 0 {
+            msg.family = family;
+        } else if msg.family != family {
+            return Err(Error::FamilyMismatch("rule src and dst".to_string()).into());
+        }
+
+        match rta_type {
+            consts::FRA_SRC => msg.src_len = net.prefix_len(),
+            _ => msg.dst_len = net.prefix_len(),
+        }
+
+        attrs.push(Box::new(NetlinkRouteAttr::new(rta_type, data)));
+    }
+
+    if msg.family == 0 {
+        msg.family = libc::AF_INET as u8;
+    }
+
+    let table = match cmd {
+        RuleCmd::Add if rule.table == 0 => libc::RT_TABLE_MAIN.into(),
+        _ => rule.table,
+    };
+
+    // Tables beyond the 8-bit header field are only given by FRA_TABLE
+    if table != 0 {
+        msg.table = u8::try_from(table).unwrap_or(libc::RT_TABLE_UNSPEC);
+        attrs.push(Box::new(NetlinkRouteAttr::new(
+            consts::FRA_TABLE,
+            table.to_ne_bytes().to_vec(),
+        )));
+    }
+
+    if let RuleCmd::Add = cmd {
+        msg.action = consts::FR_ACT_TO_TBL;
+    }
+
+    if let Some(priority) = rule.priority {
+        attrs.push(Box::new(NetlinkRouteAttr::new(
+            consts::FRA_PRIORITY,
+            priority.to_ne_bytes().to_vec(),
+        )));
+    }
+
+    if let Some(mark) = rule.mark {
+        attrs.push(Box::new(NetlinkRouteAttr::new(
+            consts::FRA_FWMARK,
+            mark.to_ne_bytes().to_vec(),
+        )));
+    }
+
+    if let Some(mask) = rule.mask {
+        attrs.push(Box::new(NetlinkRouteAttr::new(
+            consts::FRA_FWMASK,
+            mask.to_ne_bytes().to_vec(),
+        )));
+    }
+
+    if !rule.iif.is_empty() {
+        attrs.push(Box::new(NetlinkRouteAttr::new(
+            consts::FRA_IIFNAME,
+            zero_terminated(&rule.iif),
+        )));
+    }
+
+    if !rule.oif.is_empty() {
+        attrs.push(Box::new(NetlinkRouteAttr::new(
+            consts::FRA_OIFNAME,
+            zero_terminated(&rule.oif),
+        )));
+    }
+
+    req.add_data(msg);
+
+    for attr in attrs {
+        req.add_data(attr);
+    }
+
+    Ok(req)
+}
+
+pub fn rule_list(family: AddrFamily) -> Result<NetlinkRequest> {
+    let mut req = NetlinkRequest::new(libc::RTM_GETRULE, libc::NLM_F_DUMP);
+    req.add_data(Box::new(RuleMessage::new(family as u8)));
+
+    Ok(req)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 100: from 10.0.0.0/8 fwmark 0x1/0xff lookup 1000
+    #[rustfmt::skip]
+    static RULE_NEW_MSG: [u8; 60] = [
+        0x02, // family = AF_INET
+        0x00, // dst len
+        0x08, // src len = 8
+        0x00, // tos
+        0x00, // table = RT_TABLE_UNSPEC
+        0x00, 0x00, // reserved
+        0x01, // action = FR_ACT_TO_TBL
+        0x00, 0x00, 0x00, 0x00, // flags
+
+        // nlas
+        0x08, 0x00, 0x0f, 0x00, 0xe8, 0x03, 0x00, 0x00, // Table L=8,T=15,V=1000
+        0x08, 0x00, 0x06, 0x00, 0x64, 0x00, 0x00, 0x00, // Priority L=8,T=6,V=100
+        0x08, 0x00, 0x02, 0x00, 0x0a, 0x00, 0x00, 0x00, // Src L=8,T=2,V=10.0.0.0
+        0x08, 0x00, 0x0a, 0x00, 0x01, 0x00, 0x00, 0x00, // FwMark L=8,T=10,V=1
+        0x08, 0x00, 0x10, 0x00, 0xff, 0x00, 0x00, 0x00, // FwMask L=8,T=16,V=0xff
+        0x07, 0x00, 0x03, 0x00, 0x6c, 0x6f, 0x00, // IifName L=7,T=3,V=lo
+        0x00, // padding
+    ];
+
+    #[test]
+    fn test_rule_deserialize() {
+        let rule = rule_deserialize(&RULE_NEW_MSG).unwrap();
+
+        assert_eq!(rule.family, libc::AF_INET as u8);
+        assert_eq!(rule.priority, Some(100));
+        assert_eq!(rule.table, 1000);
+        assert_eq!(rule.src, Some("10.0.0.0/8".parse().unwrap()));
+        assert_eq!(rule.dst, None);
+        assert_eq!(rule.mark, Some(1));
+        assert_eq!(rule.mask, Some(0xff));
+        assert_eq!(rule.iif, "lo");
+        assert_eq!(rule.oif, "");
+    }
+
+    #[test]
+    fn test_rule_handle() {
+        let rule = rule_deserialize(&RULE_NEW_MSG).unwrap();
+
+        // The request is the header followed by the rule itself
+        let buf = rule_handle(RuleCmd::Add, &rule)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        assert_eq!(rule_deserialize(&buf[16..]).unwrap(), rule);
+
+        let msg = RuleMessage::deserialize(&buf[16..]).unwrap();
+        assert_eq!(msg.action, consts::FR_ACT_TO_TBL);
+        assert_eq!(msg.table, libc::RT_TABLE_UNSPEC);
+
+        let rule = Rule {
+            src: Some("10.0.0.0/8".parse().unwrap()),
+            dst: Some("fd00::/8".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(rule_handle(RuleCmd::Add, &rule).is_err());
+    }
+}
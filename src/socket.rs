@@ -1,6 +1,8 @@
 use std::{
-    io::{Error, Result},
-    os::fd::RawFd,
+    io::{Error, ErrorKind, Result},
+    os::fd::{AsRawFd, RawFd},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
 };
 
 use crate::{consts, message::NetlinkMessage};
@@ -8,6 +10,8 @@ use crate::{consts, message::NetlinkMessage};
 pub struct NetlinkSocket {
     fd: RawFd,
     lsa: SockAddrNetlink,
+    // Kept by `set_nonblocking`, so telling a timeout from `WouldBlock` takes no syscall
+    nonblocking: AtomicBool,
 }
 
 impl NetlinkSocket {
@@ -23,7 +27,11 @@ impl NetlinkSocket {
             return Err(Error::last_os_error());
         }
         let lsa = SockAddrNetlink::new(pid, groups);
-        let s = Self { fd, lsa };
+        let s = Self {
+            fd,
+            lsa,
+            nonblocking: AtomicBool::new(false),
+        };
         s.bind()?;
         Ok(s)
     }
@@ -49,23 +57,194 @@ impl NetlinkSocket {
     }
 
     pub fn recv(&self) -> Result<(Vec<NetlinkMessage>, libc::sockaddr_nl)> {
+        let (msgs, from, _) = self.recv_with_group()?;
+        Ok((msgs, from))
+    }
+
+    /// Receive messages along with the multicast group that delivered them,
+    /// or `None` for messages sent to this socket only (e.g. responses).
+    /// The group is taken from the `nl_pktinfo` ancillary data when `NETLINK_PKTINFO`
+    /// is enabled, which also covers groups beyond the 32 of the `nl_groups` mask.
+    pub fn recv_with_group(&self) -> Result<(Vec<NetlinkMessage>, libc::sockaddr_nl, Option<u32>)> {
         let mut from: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
-        let mut buf: [u8; consts::RECV_BUF_SIZE] = [0; consts::RECV_BUF_SIZE];
+        // A datagram can be larger than the default buffer, e.g. a dump on a system
+        // with a large page size, and would be truncated silently
+        let mut buf = vec![0u8; self.peek_len()?.max(consts::RECV_BUF_SIZE)];
+        // Room for a single cmsghdr carrying a u32, with alignment to spare
+        let mut control = [0u64; 4];
+
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_name = &mut from as *mut _ as *mut libc::c_void;
+        msg.msg_namelen = std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t;
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = std::mem::size_of_val(&control) as _;
+
+        let ret = unsafe { libc::recvmsg(self.fd, &mut msg, 0) };
+        if ret < 0 {
+            return Err(self.last_recv_error());
+        }
+        if msg.msg_flags & libc::MSG_TRUNC != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "netlink message truncated",
+            ));
+        }
+
+        let mut group = None;
+        let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+        while !cmsg.is_null() {
+            let hdr = unsafe { &*cmsg };
+            if hdr.cmsg_level == libc::SOL_NETLINK && hdr.cmsg_type == consts::NETLINK_PKTINFO {
+                let data = unsafe { libc::CMSG_DATA(cmsg) } as *const u32;
+                group = Some(unsafe { data.read_unaligned() });
+            }
+            cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+        }
+
+        // Without NETLINK_PKTINFO, the group is the single bit set in nl_groups
+        if group.is_none() && from.nl_groups != 0 {
+            group = Some(from.nl_groups.trailing_zeros() + 1);
+        }
+
+        let netlink_msgs = NetlinkMessage::from(&buf[..ret as usize])?;
+        Ok((netlink_msgs, from, group.filter(|&g| g != 0)))
+    }
+
+    /// The size of the next datagram, waiting for one to arrive.
+    fn peek_len(&self) -> Result<usize> {
         let ret = unsafe {
-            libc::recvfrom(
+            libc::recv(
                 self.fd,
-                buf.as_mut_ptr() as *mut libc::c_void,
-                buf.len() as libc::size_t,
+                std::ptr::null_mut(),
                 0,
-                &mut from as *mut _ as *mut libc::sockaddr,
-                &mut std::mem::size_of::<libc::sockaddr_nl>() as *mut _ as *mut libc::socklen_t,
+                libc::MSG_PEEK | libc::MSG_TRUNC,
+            )
+        };
+        if ret < 0 {
+            return Err(self.last_recv_error());
+        }
+        Ok(ret as usize)
+    }
+
+    /// Wait until a message can be received or the timeout expires.
+    /// Returns `false` on timeout.
+    pub fn wait_readable(&self, timeout: Duration) -> Result<bool> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let mut fds = libc::pollfd {
+                fd: self.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+
+            // Round up, so that less than a millisecond left still waits instead of
+            // polling once and reporting a timeout
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let timeout = remaining
+                .as_nanos()
+                .div_ceil(1_000_000)
+                .min(libc::c_int::MAX as u128) as libc::c_int;
+
+            let ret = unsafe { libc::poll(&mut fds, 1, timeout) };
+            if ret >= 0 {
+                return Ok(ret > 0);
+            }
+
+            // A signal interrupting the wait is not an error, wait for the time left
+            let err = Error::last_os_error();
+            if err.kind() != ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    /// Make `recv` fail with `ErrorKind::TimedOut` if no message arrives within `timeout`
+    /// (`SO_RCVTIMEO`). With `None`, `recv` blocks until a message arrives.
+    pub fn set_recv_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        let tv = match timeout {
+            // A zero timeval disables the timeout, so wait at least a microsecond
+            Some(timeout) => libc::timeval {
+                tv_sec: timeout.as_secs() as libc::time_t,
+                tv_usec: timeout.subsec_micros().max(u32::from(timeout.is_zero())) as _,
+            },
+            None => libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+        };
+
+        let ret = unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &tv as *const _ as *const libc::c_void,
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
             )
         };
         if ret < 0 {
             return Err(Error::last_os_error());
         }
-        let netlink_msgs = NetlinkMessage::from(&buf[..ret as usize])?;
-        Ok((netlink_msgs, from))
+        Ok(())
+    }
+
+    pub fn set_strict_check(&self, on: bool) -> Result<()> {
+        self.set_option(consts::NETLINK_GET_STRICT_CHK, on)
+    }
+
+    /// Have the kernel append an extended ACK (message and offending attribute offset)
+    /// to error responses, see `ErrorMessage`.
+    pub fn set_ext_ack(&self, on: bool) -> Result<()> {
+        self.set_option(consts::NETLINK_EXT_ACK, on)
+    }
+
+    /// Report the multicast group of each received message, see `recv_with_group`.
+    pub fn set_pktinfo(&self, on: bool) -> Result<()> {
+        self.set_option(consts::NETLINK_PKTINFO, on)
+    }
+
+    /// Join the multicast groups (`RTNLGRP_*`) to receive their notifications.
+    /// Unlike the `nl_groups` mask given at creation, this covers groups beyond 32.
+    pub fn subscribe(&self, groups: &[u32]) -> Result<()> {
+        for &group in groups {
+            self.set_option_value(consts::NETLINK_ADD_MEMBERSHIP, group as libc::c_int)?;
+        }
+        Ok(())
+    }
+
+    pub fn unsubscribe(&self, groups: &[u32]) -> Result<()> {
+        for &group in groups {
+            self.set_option_value(consts::NETLINK_DROP_MEMBERSHIP, group as libc::c_int)?;
+        }
+        Ok(())
+    }
+
+    fn set_option(&self, option: i32, on: bool) -> Result<()> {
+        self.set_option_value(option, on as libc::c_int)
+    }
+
+    fn set_option_value(&self, option: i32, value: libc::c_int) -> Result<()> {
+        let ret = unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_NETLINK,
+                option,
+                &value as *const _ as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
     }
 
     pub fn pid(&self) -> Result<u32> {
@@ -82,6 +261,43 @@ impl NetlinkSocket {
         }
         Ok(rsa.nl_pid)
     }
+
+    /// Make `send` and `recv` fail with `ErrorKind::WouldBlock` instead of waiting,
+    /// as needed to drive the socket from an event loop.
+    pub fn set_nonblocking(&self, on: bool) -> Result<()> {
+        let flags = unsafe { libc::fcntl(self.fd, libc::F_GETFL) };
+        if flags < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        let flags = match on {
+            true => flags | libc::O_NONBLOCK,
+            false => flags & !libc::O_NONBLOCK,
+        };
+        if unsafe { libc::fcntl(self.fd, libc::F_SETFL, flags) } < 0 {
+            return Err(Error::last_os_error());
+        }
+        self.nonblocking.store(on, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn last_recv_error(&self) -> Error {
+        let err = Error::last_os_error();
+        // EAGAIN on a blocking socket means the receive timeout expired
+        if err.kind() == ErrorKind::WouldBlock && !self.nonblocking.load(Ordering::Relaxed) {
+            return Error::new(
+                ErrorKind::TimedOut,
+                "timed out waiting for a netlink message",
+            );
+        }
+        err
+    }
+}
+
+impl AsRawFd for NetlinkSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
 }
 
 impl Drop for NetlinkSocket {
@@ -111,7 +327,12 @@ impl SockAddrNetlink {
 
 #[cfg(test)]
 mod tests {
-    use crate::message::InfoMessage;
+    use crate::{
+        handle::SocketHandle,
+        link::{Kind, LinkAttrs},
+        message::InfoMessage,
+        test_setup,
+    };
 
     use super::*;
 
@@ -154,6 +375,38 @@ mod tests {
         assert_eq!(msg.change, 0);
     }
 
+    #[test]
+    fn test_wait_readable() {
+        let s = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
+
+        // Less than a millisecond still waits for it
+        let start = Instant::now();
+        assert!(!s.wait_readable(Duration::from_micros(300)).unwrap());
+        assert!(start.elapsed() >= Duration::from_micros(300));
+
+        let msg = vec![
+            0x14, 0x00, 0x00, 0x00, 0x12, 0x00, 0x01, 0x03, 0xfd, 0xfe, 0x38, 0x5c, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        s.send(&msg[..]).unwrap();
+        assert!(s.wait_readable(Duration::from_micros(300)).unwrap());
+    }
+
+    #[test]
+    fn test_nonblocking() {
+        let s = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
+        s.set_nonblocking(true).unwrap();
+
+        let err = s.recv().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        s.set_nonblocking(false).unwrap();
+        s.set_recv_timeout(Some(Duration::from_millis(10))).unwrap();
+
+        let err = s.recv().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+
     #[test]
     fn test_netlink_socket() {
         let s = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
@@ -202,4 +455,100 @@ mod tests {
             let _ = InfoMessage::deserialize(r).unwrap();
         });
     }
+
+    #[test]
+    fn test_recv_timeout() {
+        let s = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
+        s.set_recv_timeout(Some(Duration::from_millis(50))).unwrap();
+
+        // Nothing was requested, so nothing arrives
+        let err = s.recv().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+
+        s.set_recv_timeout(Some(Duration::ZERO)).unwrap();
+        assert_eq!(s.recv().err().unwrap().kind(), ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_recv_large() {
+        test_setup!();
+        let sender = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
+        let receiver = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
+
+        // A single message larger than the default receive buffer
+        let len = consts::RECV_BUF_SIZE * 2;
+        let mut buf = vec![0u8; len];
+        buf[..4].copy_from_slice(&(len as u32).to_ne_bytes());
+        buf[4..6].copy_from_slice(&libc::RTM_NEWLINK.to_ne_bytes());
+
+        let dst = SockAddrNetlink::new(receiver.pid().unwrap(), 0);
+        let (addr, addr_len) = dst.as_raw();
+        let ret = unsafe {
+            libc::sendto(
+                sender.fd,
+                buf.as_ptr() as *const libc::c_void,
+                buf.len(),
+                0,
+                addr,
+                addr_len,
+            )
+        };
+        assert_eq!(ret as usize, len);
+
+        let (msgs, from) = receiver.recv().unwrap();
+        assert_eq!(from.nl_pid, sender.pid().unwrap());
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].header.nlmsg_len as usize, len);
+        assert_eq!(msgs[0].data.len(), len - consts::NLMSG_HDRLEN);
+    }
+
+    #[test]
+    fn test_subscribe() {
+        test_setup!();
+        let s = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
+        s.subscribe(&[libc::RTNLGRP_LINK]).unwrap();
+        s.set_recv_timeout(Some(Duration::from_secs(1))).unwrap();
+
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let ifb = Kind::Ifb(LinkAttrs::new("ifb0"));
+        handle
+            .link_new(
+                &ifb,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let (msgs, _, group) = s.recv_with_group().unwrap();
+        assert_eq!(msgs[0].header.nlmsg_type, libc::RTM_NEWLINK);
+        assert_eq!(group, Some(libc::RTNLGRP_LINK));
+
+        // No notification once unsubscribed
+        s.unsubscribe(&[libc::RTNLGRP_LINK]).unwrap();
+        handle.link_del(&LinkAttrs::new("ifb0")).unwrap();
+        s.set_recv_timeout(Some(Duration::from_millis(50))).unwrap();
+        assert_eq!(s.recv().err().unwrap().kind(), ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_recv_with_group() {
+        test_setup!();
+        let with_pktinfo =
+            NetlinkSocket::new(libc::NETLINK_ROUTE, 0, libc::RTMGRP_LINK as u32).unwrap();
+        with_pktinfo.set_pktinfo(true).unwrap();
+        let without = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, libc::RTMGRP_LINK as u32).unwrap();
+
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        handle
+            .link_new(
+                &Kind::Ifb(LinkAttrs::new("ifb0")),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        for s in [with_pktinfo, without] {
+            let (msgs, _, group) = s.recv_with_group().unwrap();
+            assert_eq!(msgs[0].header.nlmsg_type, libc::RTM_NEWLINK);
+            assert_eq!(group, Some(libc::RTNLGRP_LINK));
+        }
+    }
 }
@@ -1,6 +1,8 @@
 use std::net::IpAddr;
 
-use anyhow::{bail, Result};
+use anyhow::Result;
+
+use crate::error::Error;
 
 pub fn align_of(len: usize, align_to: usize) -> usize {
     (len + align_to - 1) & !(align_to - 1)
@@ -21,7 +23,7 @@ pub fn vec_to_addr(vec: Vec<u8>) -> Result<IpAddr> {
         let buf: [u8; 16] = buf;
         Ok(IpAddr::from(buf))
     } else {
-        bail!("invalid address length: {}", vec.len())
+        Err(Error::Parse(format!("invalid address length: {}", vec.len())).into())
     }
 }
 
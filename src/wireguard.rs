@@ -0,0 +1,277 @@
+use std::net::SocketAddr;
+
+use anyhow::{Ok, Result};
+use ipnet::IpNet;
+
+use crate::{
+    consts,
+    error::Error,
+    message::{GenlMessage, NetlinkRouteAttr},
+    request::NetlinkRequest,
+    utils::zero_terminated,
+};
+
+/// The configuration of a WireGuard link (`Kind::Wireguard`), as set by `wg set`.
+/// Settings left unset are not changed.
+#[derive(Default, Debug, Clone)]
+pub struct WireguardConfig {
+    pub private_key: Option<[u8; consts::WG_KEY_LEN]>,
+    pub listen_port: Option<u16>,
+    pub fwmark: Option<u32>,
+    /// Remove the peers that are not in `peers`, instead of only adding or updating these.
+    pub replace_peers: bool,
+    pub peers: Vec<WireguardPeer>,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct WireguardPeer {
+    pub public_key: [u8; consts::WG_KEY_LEN],
+    pub preshared_key: Option<[u8; consts::WG_KEY_LEN]>,
+    pub endpoint: Option<SocketAddr>,
+    /// Interval in seconds at which an empty packet is sent to keep the tunnel alive,
+    /// 0 to disable it.
+    pub persistent_keepalive: Option<u16>,
+    /// The addresses the peer may send from, replacing its previous ones.
+    pub allowed_ips: Vec<IpNet>,
+}
+
+/// Build a request for the id of the generic netlink family `name`.
+pub fn family_get(name: &str) -> Result<NetlinkRequest> {
+    let mut req = NetlinkRequest::new(consts::GENL_ID_CTRL, libc::NLM_F_ACK);
+
+    req.add_data(Box::new(GenlMessage::new(consts::CTRL_CMD_GETFAMILY, 1)));
+    req.add_data(Box::new(NetlinkRouteAttr::new(
+        consts::CTRL_ATTR_FAMILY_NAME,
+        zero_terminated(name),
+    )));
+
+    Ok(req)
+}
+
+/// The family id in the response to a `family_get` request.
+pub fn family_id_deserialize(buf: &[u8]) -> Result<u16> {
+    if buf.len() < consts::GENL_HDR_SIZE {
+        return Err(Error::Parse("generic netlink message is too short".to_string()).into());
+    }
+
+    let attrs = NetlinkRouteAttr::map(&buf[consts::GENL_HDR_SIZE..])?;
+    match attrs.get(&consts::CTRL_ATTR_FAMILY_ID) {
+        Some(id) if id.len() >= 2 => Ok(u16::from_ne_bytes(id[..2].try_into()?)),
+        _ => Err(Error::Parse("generic netlink family without id".to_string()).into()),
+    }
+}
+
+/// Build a request setting the configuration of the WireGuard link at `index`,
+/// `family_id` being the id of the `wireguard` generic netlink family.
+pub fn wireguard_set(
+    family_id: u16,
+    index: i32,
+    config: &WireguardConfig,
+) -> Result<NetlinkRequest> {
+    let mut req = NetlinkRequest::new(family_id, libc::NLM_F_ACK);
+
+    req.add_data(Box::new(GenlMessage::new(
+        consts::WG_CMD_SET_DEVICE,
+        consts::WG_GENL_VERSION,
+    )));
+    req.add_data(Box::new(NetlinkRouteAttr::new(
+        consts::WGDEVICE_A_IFINDEX,
+        index.to_ne_bytes().to_vec(),
+    )));
+
+    if let Some(private_key) = config.private_key {
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            consts::WGDEVICE_A_PRIVATE_KEY,
+            private_key.to_vec(),
+        )));
+    }
+
+    if let Some(listen_port) = config.listen_port {
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            consts::WGDEVICE_A_LISTEN_PORT,
+            listen_port.to_ne_bytes().to_vec(),
+        )));
+    }
+
+    if let Some(fwmark) = config.fwmark {
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            consts::WGDEVICE_A_FWMARK,
+            fwmark.to_ne_bytes().to_vec(),
+        )));
+    }
+
+    if config.replace_peers {
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            consts::WGDEVICE_A_FLAGS,
+            consts::WGDEVICE_F_REPLACE_PEERS.to_ne_bytes().to_vec(),
+        )));
+    }
+
+    if !config.peers.is_empty() {
+        let mut peers = Box::new(NetlinkRouteAttr::new(
+            consts::WGDEVICE_A_PEERS | consts::NLA_F_NESTED,
+            vec![],
+        ));
+
+        // Each peer is nested in an attribute whose type is its position
+        for (i, peer) in config.peers.iter().enumerate() {
+            peers.add_child_from_attr(peer_attr(i as u16, peer));
+        }
+
+        req.add_data(peers);
+    }
+
+    Ok(req)
+}
+
+fn peer_attr(i: u16, peer: &WireguardPeer) -> Box<NetlinkRouteAttr> {
+    let mut attr = Box::new(NetlinkRouteAttr::new(i | consts::NLA_F_NESTED, vec![]));
+
+    attr.add_child(consts::WGPEER_A_PUBLIC_KEY, peer.public_key.to_vec());
+    attr.add_child(
+        consts::WGPEER_A_FLAGS,
+        consts::WGPEER_F_REPLACE_ALLOWEDIPS.to_ne_bytes().to_vec(),
+    );
+
+    if let Some(preshared_key) = peer.preshared_key {
+        attr.add_child(consts::WGPEER_A_PRESHARED_KEY, preshared_key.to_vec());
+    }
+
+    if let Some(endpoint) = peer.endpoint {
+        attr.add_child(consts::WGPEER_A_ENDPOINT, sockaddr(&endpoint));
+    }
+
+    if let Some(interval) = peer.persistent_keepalive {
+        attr.add_child(
+            consts::WGPEER_A_PERSISTENT_KEEPALIVE_INTERVAL,
+            interval.to_ne_bytes().to_vec(),
+        );
+    }
+
+    let mut allowed_ips = Box::new(NetlinkRouteAttr::new(
+        consts::WGPEER_A_ALLOWEDIPS | consts::NLA_F_NESTED,
+        vec![],
+    ));
+
+    for (i, net) in peer.allowed_ips.iter().enumerate() {
+        let (family, addr) = match net {
+            IpNet::V4(net) => (libc::AF_INET as u16, net.addr().octets().to_vec()),
+            IpNet::V6(net) => (libc::AF_INET6 as u16, net.addr().octets().to_vec()),
+        };
+
+        let mut allowed_ip = Box::new(NetlinkRouteAttr::new(
+            i as u16 | consts::NLA_F_NESTED,
+            vec![],
+        ));
+        allowed_ip.add_child(consts::WGALLOWEDIP_A_FAMILY, family.to_ne_bytes().to_vec());
+        allowed_ip.add_child(consts::WGALLOWEDIP_A_IPADDR, addr);
+        allowed_ip.add_child(consts::WGALLOWEDIP_A_CIDR_MASK, vec![net.prefix_len()]);
+
+        allowed_ips.add_child_from_attr(allowed_ip);
+    }
+
+    attr.add_child_from_attr(allowed_ips);
+    attr
+}
+
+// The endpoint is a `sockaddr_in` or `sockaddr_in6`, with the port in network byte order
+fn sockaddr(addr: &SocketAddr) -> Vec<u8> {
+    let mut buf = vec![];
+
+    match addr {
+        SocketAddr::V4(addr) => {
+            buf.extend((libc::AF_INET as u16).to_ne_bytes());
+            buf.extend(addr.port().to_be_bytes());
+            buf.extend(addr.ip().octets());
+            buf.extend([0; 8]);
+        }
+        SocketAddr::V6(addr) => {
+            buf.extend((libc::AF_INET6 as u16).to_ne_bytes());
+            buf.extend(addr.port().to_be_bytes());
+            buf.extend(addr.flowinfo().to_be_bytes());
+            buf.extend(addr.ip().octets());
+            buf.extend(addr.scope_id().to_ne_bytes());
+        }
+    }
+
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // CTRL_CMD_NEWFAMILY for "wireguard" with family id 0x15
+    #[rustfmt::skip]
+    static NEW_FAMILY_MSG: [u8; 28] = [
+        0x01, // cmd = CTRL_CMD_NEWFAMILY
+        0x02, // version
+        0x00, 0x00, // reserved
+
+        // nlas
+        0x0e, 0x00, 0x02, 0x00, // FamilyName L=14,T=2
+        0x77, 0x69, 0x72, 0x65, 0x67, 0x75, 0x61, 0x72, 0x64, 0x00, // wireguard
+        0x00, 0x00, // padding
+        0x06, 0x00, 0x01, 0x00, 0x15, 0x00, // FamilyId L=6,T=1,V=0x15
+        0x00, 0x00, // padding
+    ];
+
+    #[test]
+    fn test_family_id_deserialize() {
+        assert_eq!(family_id_deserialize(&NEW_FAMILY_MSG).unwrap(), 0x15);
+        assert!(family_id_deserialize(&NEW_FAMILY_MSG[..22]).is_err());
+        assert!(family_id_deserialize(&NEW_FAMILY_MSG[..2]).is_err());
+    }
+
+    #[test]
+    fn test_wireguard_set() {
+        let config = WireguardConfig {
+            private_key: Some([1; consts::WG_KEY_LEN]),
+            listen_port: Some(51820),
+            peers: vec![WireguardPeer {
+                public_key: [2; consts::WG_KEY_LEN],
+                endpoint: Some("192.0.2.1:51820".parse().unwrap()),
+                allowed_ips: vec!["10.0.0.0/24".parse().unwrap()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let buf = wireguard_set(0x15, 4, &config)
+            .unwrap()
+            .serialize()
+            .unwrap();
+        assert_eq!(u16::from_ne_bytes([buf[4], buf[5]]), 0x15);
+        assert_eq!(buf[16], consts::WG_CMD_SET_DEVICE);
+        assert_eq!(buf[17], consts::WG_GENL_VERSION);
+
+        let attrs = NetlinkRouteAttr::map(&buf[20..]).unwrap();
+        assert_eq!(attrs[&consts::WGDEVICE_A_IFINDEX], 4i32.to_ne_bytes());
+        assert_eq!(
+            attrs[&consts::WGDEVICE_A_PRIVATE_KEY],
+            [1; consts::WG_KEY_LEN]
+        );
+        assert_eq!(
+            attrs[&consts::WGDEVICE_A_LISTEN_PORT],
+            51820u16.to_ne_bytes()
+        );
+        assert!(!attrs.contains_key(&consts::WGDEVICE_A_FLAGS));
+
+        let peers =
+            NetlinkRouteAttr::map(&attrs[&(consts::WGDEVICE_A_PEERS | consts::NLA_F_NESTED)])
+                .unwrap();
+        let peer = NetlinkRouteAttr::map(&peers[&consts::NLA_F_NESTED]).unwrap();
+        assert_eq!(peer[&consts::WGPEER_A_PUBLIC_KEY], [2; consts::WG_KEY_LEN]);
+        assert_eq!(
+            peer[&consts::WGPEER_A_ENDPOINT][..8],
+            [libc::AF_INET as u8, 0, 0xca, 0x6c, 192, 0, 2, 1]
+        );
+
+        let allowed_ips =
+            NetlinkRouteAttr::map(&peer[&(consts::WGPEER_A_ALLOWEDIPS | consts::NLA_F_NESTED)])
+                .unwrap();
+        let allowed_ip = NetlinkRouteAttr::map(&allowed_ips[&consts::NLA_F_NESTED]).unwrap();
+        assert_eq!(allowed_ip[&consts::WGALLOWEDIP_A_IPADDR], [10, 0, 0, 0]);
+        assert_eq!(allowed_ip[&consts::WGALLOWEDIP_A_CIDR_MASK], [24]);
+    }
+}